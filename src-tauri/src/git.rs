@@ -1,10 +1,129 @@
 use git2::{build::CheckoutBuilder, BranchType, ErrorCode, Repository, Sort, StatusOptions};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use std::process::Command;
+use tauri::{AppHandle, Emitter};
+
+const MAX_TEXT_BLOB_BYTES: usize = 5 * 1024 * 1024;
+const MAX_IMAGE_PREVIEW_BYTES: usize = 10 * 1024 * 1024;
+/// Same window git uses to look for NUL bytes when deciding a file is binary.
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+#[derive(Serialize)]
+pub struct ImagePreview {
+    pub mime_type: String,
+    pub data_base64: String,
+}
+
+#[derive(Serialize)]
+pub struct BlobContent {
+    pub binary: bool,
+    pub encoding: Option<String>,
+    pub content: String,
+    pub size: usize,
+    pub truncated: bool,
+    pub image: Option<ImagePreview>,
+}
+
+fn image_mime_type(path: &Path, bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some("image/png");
+    }
+    if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        return Some("image/jpeg");
+    }
+    let is_svg_ext = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]);
+    if is_svg_ext || head.trim_start().starts_with("<svg") {
+        return Some("image/svg+xml");
+    }
+    None
+}
+
+fn decode_lossless(encoding: &'static encoding_rs::Encoding, bytes: &[u8]) -> Option<String> {
+    let text = encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)?
+        .into_owned();
+    let (encoded, _, had_errors) = encoding.encode(&text);
+    (!had_errors && encoded == bytes).then_some(text)
+}
+
+fn decode_text(bytes: &[u8], truncated: bool) -> Option<(&'static encoding_rs::Encoding, String)> {
+    if let Some((encoding, bom_len)) = encoding_rs::Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return Some((encoding, text.into_owned()));
+    }
+
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return None;
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => return Some((encoding_rs::UTF_8, text.to_string())),
+        // A multi-byte character cut off by truncation is still UTF-8.
+        Err(e) if truncated && e.error_len().is_none() => {
+            let text = std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default();
+            return Some((encoding_rs::UTF_8, text.to_string()));
+        }
+        Err(_) => {}
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    let guess = detector.guess(None, true);
+
+    for encoding in [guess, encoding_rs::WINDOWS_1252] {
+        if truncated {
+            let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+            if !had_errors || encoding == encoding_rs::WINDOWS_1252 {
+                return Some((encoding, text.into_owned()));
+            }
+        } else if let Some(text) = decode_lossless(encoding, bytes) {
+            return Some((encoding, text));
+        }
+    }
+    None
+}
+
+fn blob_content(path: &Path, bytes: &[u8]) -> BlobContent {
+    let size = bytes.len();
+    let image = image_mime_type(path, bytes)
+        .filter(|_| size <= MAX_IMAGE_PREVIEW_BYTES)
+        .map(|mime_type| {
+            use base64::Engine;
+            ImagePreview {
+                mime_type: mime_type.to_string(),
+                data_base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+            }
+        });
+
+    let truncated = size > MAX_TEXT_BLOB_BYTES;
+    let text_bytes = &bytes[..size.min(MAX_TEXT_BLOB_BYTES)];
+    match decode_text(text_bytes, truncated) {
+        Some((encoding, content)) => BlobContent {
+            binary: false,
+            encoding: Some(encoding.name().to_string()),
+            content,
+            size,
+            truncated,
+            image,
+        },
+        None => BlobContent {
+            binary: true,
+            encoding: None,
+            content: String::new(),
+            size,
+            truncated: false,
+            image,
+        },
+    }
+}
 
 #[tauri::command]
-pub fn get_file_at_ref(file_path: String, git_ref: String) -> Result<String, String> {
+pub fn get_file_at_ref(file_path: String, git_ref: String) -> Result<BlobContent, String> {
     let path = Path::new(&file_path);
     let repo = Repository::discover(path).map_err(|e| e.message().to_string())?;
 
@@ -30,15 +149,11 @@ pub fn get_file_at_ref(file_path: String, git_ref: String) -> Result<String, Str
         .find_blob(entry.id())
         .map_err(|e| e.message().to_string())?;
 
-    let content = std::str::from_utf8(blob.content())
-        .map_err(|e| e.to_string())?
-        .to_string();
-
-    Ok(content)
+    Ok(blob_content(relative_path, blob.content()))
 }
 
 #[tauri::command]
-pub fn get_staged_content(file_path: String) -> Result<String, String> {
+pub fn get_staged_content(file_path: String) -> Result<BlobContent, String> {
     let path = Path::new(&file_path);
     let repo = Repository::discover(path).map_err(|e| e.message().to_string())?;
 
@@ -62,31 +177,128 @@ pub fn get_staged_content(file_path: String) -> Result<String, String> {
         .find_blob(entry.id)
         .map_err(|e| e.message().to_string())?;
 
-    let content = std::str::from_utf8(blob.content())
-        .map_err(|e| e.to_string())?
-        .to_string();
+    Ok(blob_content(relative_path, blob.content()))
+}
+
+#[derive(Serialize)]
+pub struct BranchInfo {
+    pub name: String,
+    pub refname: String,
+    pub is_remote: bool,
+    pub is_head: bool,
+    pub upstream: Option<String>,
+    pub ahead: Option<usize>,
+    pub behind: Option<usize>,
+    pub last_commit_hash: Option<String>,
+    pub last_commit_summary: Option<String>,
+    pub last_commit_timestamp: Option<i64>,
+    pub worktree_path: Option<String>,
+}
 
-    Ok(content)
+fn checked_out_branches(repo: &Repository) -> HashMap<String, String> {
+    fn head_branch(repo: &Repository) -> Option<String> {
+        repo.find_reference("HEAD")
+            .ok()?
+            .symbolic_target()
+            .map(|s| s.to_string())
+    }
+
+    let mut result = HashMap::new();
+    if let Some(main_path) = main_workdir(repo) {
+        if let Some(branch) = Repository::open(&main_path)
+            .ok()
+            .and_then(|main| head_branch(&main))
+        {
+            let path = main_path
+                .to_string_lossy()
+                .trim_end_matches('/')
+                .to_string();
+            result.insert(branch, path);
+        }
+    }
+    if let Ok(names) = repo.worktrees() {
+        for name in names.iter().flatten() {
+            let Ok(worktree) = repo.find_worktree(name) else {
+                continue;
+            };
+            if worktree.validate().is_err() {
+                continue;
+            }
+            if let Some(branch) = Repository::open_from_worktree(&worktree)
+                .ok()
+                .and_then(|wt| head_branch(&wt))
+            {
+                let path = worktree.path().to_string_lossy();
+                result.insert(branch, path.trim_end_matches('/').to_string());
+            }
+        }
+    }
+    result
 }
 
 #[tauri::command]
-pub fn list_branches(file_path: String) -> Result<Vec<String>, String> {
+pub fn list_branches(file_path: String) -> Result<Vec<BranchInfo>, String> {
     let path = Path::new(&file_path);
     let repo = Repository::discover(path).map_err(|e| e.message().to_string())?;
 
-    let branches = repo
-        .branches(Some(BranchType::Local))
-        .map_err(|e| e.message().to_string())?;
+    let checked_out = checked_out_branches(&repo);
+    let head_ref = repo
+        .find_reference("HEAD")
+        .ok()
+        .and_then(|r| r.symbolic_target().map(|s| s.to_string()));
+
+    let branches = repo.branches(None).map_err(|e| e.message().to_string())?;
 
-    let mut names = Vec::new();
+    let mut result = Vec::new();
     for branch in branches {
-        let (branch, _) = branch.map_err(|e| e.message().to_string())?;
-        if let Some(name) = branch.name().map_err(|e| e.message().to_string())? {
-            names.push(name.to_string());
+        let (branch, branch_type) = branch.map_err(|e| e.message().to_string())?;
+        let reference = branch.get();
+        // Skip symbolic refs such as `origin/HEAD`.
+        if reference.kind() != Some(git2::ReferenceType::Direct) {
+            continue;
         }
+        let (Some(name), Some(refname)) = (
+            branch.name().map_err(|e| e.message().to_string())?,
+            reference.name(),
+        ) else {
+            continue;
+        };
+        let is_remote = branch_type == BranchType::Remote;
+        let target = reference.target();
+        let commit = target.and_then(|oid| repo.find_commit(oid).ok());
+
+        let upstream = if is_remote {
+            None
+        } else {
+            branch.upstream().ok()
+        };
+        let upstream_name = upstream
+            .as_ref()
+            .and_then(|u| u.name().ok().flatten().map(|s| s.to_string()));
+        let ahead_behind = match (target, upstream.as_ref().and_then(|u| u.get().target())) {
+            (Some(local), Some(remote)) => repo.graph_ahead_behind(local, remote).ok(),
+            _ => None,
+        };
+
+        result.push(BranchInfo {
+            name: name.to_string(),
+            refname: refname.to_string(),
+            is_remote,
+            is_head: head_ref.as_deref() == Some(refname),
+            upstream: upstream_name,
+            ahead: ahead_behind.map(|(ahead, _)| ahead),
+            behind: ahead_behind.map(|(_, behind)| behind),
+            last_commit_hash: target.map(|oid| oid.to_string()),
+            last_commit_summary: commit
+                .as_ref()
+                .and_then(|c| c.summary().map(|s| s.to_string())),
+            last_commit_timestamp: commit.as_ref().map(|c| c.time().seconds()),
+            worktree_path: checked_out.get(refname).cloned(),
+        });
     }
 
-    Ok(names)
+    result.sort_by(|a, b| a.is_remote.cmp(&b.is_remote).then(a.name.cmp(&b.name)));
+    Ok(result)
 }
 
 #[tauri::command]
@@ -105,11 +317,14 @@ pub struct GitFileStatus {
     pub path: String,
     pub index_status: String,
     pub worktree_status: String,
+    pub old_path: Option<String>,
+    pub similarity: Option<u32>,
+    pub review_state: String,
 }
 
 fn index_status_from_flags(status: git2::Status) -> &'static str {
     if status.contains(git2::Status::CONFLICTED) {
-        "modified"
+        "conflicted"
     } else if status.contains(git2::Status::INDEX_NEW) {
         "new"
     } else if status.contains(git2::Status::INDEX_MODIFIED) {
@@ -129,22 +344,92 @@ fn worktree_status_from_flags(status: git2::Status) -> &'static str {
     if status.contains(git2::Status::IGNORED) {
         "ignored"
     } else if status.contains(git2::Status::CONFLICTED) {
-        "modified"
+        "conflicted"
     } else if status.contains(git2::Status::WT_NEW) {
         "new"
     } else if status.contains(git2::Status::WT_MODIFIED) {
         "modified"
     } else if status.contains(git2::Status::WT_DELETED) {
         "deleted"
-    } else if status.contains(git2::Status::WT_RENAMED)
-        || status.contains(git2::Status::WT_TYPECHANGE)
-    {
+    } else if status.contains(git2::Status::WT_RENAMED) {
+        "renamed"
+    } else if status.contains(git2::Status::WT_TYPECHANGE) {
         "modified"
     } else {
         "none"
     }
 }
 
+struct SimilarPair {
+    old_path: String,
+    similarity: Option<u32>,
+    copied: bool,
+}
+
+fn detect_renames(diff: &mut git2::Diff<'_>, untracked: bool) -> Result<(), String> {
+    let mut opts = git2::DiffFindOptions::new();
+    opts.renames(true).copies(true).for_untracked(untracked);
+    diff.find_similar(Some(&mut opts))
+        .map_err(|e| e.message().to_string())
+}
+
+/// libgit2 does not expose `git_diff_delta.similarity`, so read it back from
+/// the `similarity index N%` line of each printed patch header.
+fn similarity_by_path(diff: &git2::Diff<'_>) -> Result<HashMap<String, u32>, String> {
+    let mut result = HashMap::new();
+    let has_pairs = diff
+        .deltas()
+        .any(|d| matches!(d.status(), git2::Delta::Renamed | git2::Delta::Copied));
+    if !has_pairs {
+        return Ok(result);
+    }
+
+    diff.print(git2::DiffFormat::PatchHeader, |delta, _, line| {
+        let header = String::from_utf8_lossy(line.content());
+        let similarity = header
+            .lines()
+            .find_map(|l| l.strip_prefix("similarity index "))
+            .and_then(|v| v.trim_end_matches('%').parse().ok());
+        if let (Some(path), Some(similarity)) = (delta_path(delta.new_file()), similarity) {
+            result.insert(path, similarity);
+        }
+        true
+    })
+    .map_err(|e| e.message().to_string())?;
+
+    Ok(result)
+}
+
+fn similar_pairs(
+    mut diff: git2::Diff<'_>,
+    untracked: bool,
+) -> Result<HashMap<String, SimilarPair>, String> {
+    detect_renames(&mut diff, untracked)?;
+    let similarity = similarity_by_path(&diff)?;
+
+    let mut pairs = HashMap::new();
+    for delta in diff.deltas() {
+        let copied = match delta.status() {
+            git2::Delta::Renamed => false,
+            git2::Delta::Copied => true,
+            _ => continue,
+        };
+        if let (Some(old_path), Some(new_path)) =
+            (delta_path(delta.old_file()), delta_path(delta.new_file()))
+        {
+            pairs.insert(
+                new_path.clone(),
+                SimilarPair {
+                    old_path,
+                    similarity: similarity.get(&new_path).copied(),
+                    copied,
+                },
+            );
+        }
+    }
+    Ok(pairs)
+}
+
 #[tauri::command]
 pub fn get_git_status(repo_path: String) -> Result<Vec<GitFileStatus>, String> {
     let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
@@ -152,27 +437,94 @@ pub fn get_git_status(repo_path: String) -> Result<Vec<GitFileStatus>, String> {
     let mut opts = StatusOptions::new();
     opts.include_untracked(true)
         .recurse_untracked_dirs(true)
-        .include_ignored(true);
+        .include_ignored(true)
+        .renames_head_to_index(true)
+        .renames_index_to_workdir(true);
 
     let statuses = repo
         .statuses(Some(&mut opts))
         .map_err(|e| e.message().to_string())?;
 
+    // Status only reports renames; a second pass over the two diffs also finds
+    // copies and the similarity score. Skipped when nothing could be a pair.
+    let candidates = statuses.iter().any(|e| {
+        e.status().intersects(
+            git2::Status::INDEX_NEW
+                | git2::Status::INDEX_RENAMED
+                | git2::Status::WT_NEW
+                | git2::Status::WT_RENAMED,
+        )
+    });
+    let (index_pairs, worktree_pairs) = if candidates {
+        let tree = head_tree(&repo)?;
+        let index_diff = repo
+            .diff_tree_to_index(tree.as_ref(), None, None)
+            .map_err(|e| e.message().to_string())?;
+        let mut diff_opts = git2::DiffOptions::new();
+        diff_opts
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        let worktree_diff = repo
+            .diff_index_to_workdir(None, Some(&mut diff_opts))
+            .map_err(|e| e.message().to_string())?;
+        (
+            similar_pairs(index_diff, false)?,
+            similar_pairs(worktree_diff, true)?,
+        )
+    } else {
+        (HashMap::new(), HashMap::new())
+    };
+    let reviews = load_json::<ReviewStore>(&repo, REVIEW_STORE_FILE).unwrap_or_default();
+
     let result: Vec<GitFileStatus> = statuses
         .iter()
         .filter_map(|entry| {
-            let path = entry.path()?.to_string();
+            let index_delta = entry.head_to_index();
+            let worktree_delta = entry.index_to_workdir();
+            let index_new = index_delta.as_ref().and_then(|d| delta_path(d.new_file()));
+            let worktree_new = worktree_delta
+                .as_ref()
+                .and_then(|d| delta_path(d.new_file()));
+
+            let path = match worktree_new.clone().or(index_new.clone()) {
+                Some(p) => p,
+                None => entry.path()?.to_string(),
+            };
             let path = path.trim_end_matches('/').to_string();
             let status = entry.status();
-            let idx = index_status_from_flags(status);
-            let wt = worktree_status_from_flags(status);
+            let mut idx = index_status_from_flags(status);
+            let mut wt = worktree_status_from_flags(status);
             if idx == "none" && wt == "none" {
                 return None;
             }
+
+            let mut old_path = None;
+            let mut similarity = None;
+            if let Some(pair) = index_new.as_ref().and_then(|p| index_pairs.get(p)) {
+                idx = if pair.copied { "copied" } else { "renamed" };
+                old_path = Some(pair.old_path.clone());
+                similarity = pair.similarity;
+            } else if idx == "renamed" {
+                old_path = index_delta.as_ref().and_then(|d| delta_path(d.old_file()));
+            }
+            if let Some(pair) = worktree_new.as_ref().and_then(|p| worktree_pairs.get(p)) {
+                wt = if pair.copied { "copied" } else { "renamed" };
+                old_path = old_path.or(Some(pair.old_path.clone()));
+                similarity = pair.similarity.or(similarity);
+            } else if wt == "renamed" && old_path.is_none() {
+                old_path = worktree_delta
+                    .as_ref()
+                    .and_then(|d| delta_path(d.old_file()));
+            }
+
             Some(GitFileStatus {
+                review_state: review_state(&reviews, repo.workdir(), &path).to_string(),
                 path,
                 index_status: idx.to_string(),
                 worktree_status: wt.to_string(),
+                old_path,
+                similarity,
             })
         })
         .collect();
@@ -188,788 +540,10957 @@ pub struct CommitInfo {
     pub author_name: String,
     pub author_email: String,
     pub timestamp: i64,
+    pub parents: Vec<String>,
+    pub insertions: usize,
+    pub deletions: usize,
+    pub files_changed: usize,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct LogFilter {
+    pub cursor: Option<String>,
+    pub revision: Option<String>,
+    pub path: Option<String>,
+    pub author: Option<String>,
+    pub message_regex: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+fn push_revision(
+    repo: &Repository,
+    revwalk: &mut git2::Revwalk<'_>,
+    spec: &str,
+) -> Result<(), git2::Error> {
+    let revspec = repo.revparse(spec)?;
+    let from = revspec.from().map(|o| o.peel_to_commit()).transpose()?;
+    let to = revspec.to().map(|o| o.peel_to_commit()).transpose()?;
+    let mode = revspec.mode();
+
+    match (from, to) {
+        (Some(from), Some(to)) if mode.contains(git2::RevparseMode::MERGE_BASE) => {
+            revwalk.push(from.id())?;
+            revwalk.push(to.id())?;
+            if let Ok(base) = repo.merge_base(from.id(), to.id()) {
+                revwalk.hide(base)?;
+            }
+        }
+        (Some(from), Some(to)) => {
+            revwalk.push(to.id())?;
+            revwalk.hide(from.id())?;
+        }
+        (Some(commit), None) | (None, Some(commit)) => revwalk.push(commit.id())?,
+        (None, None) => return Err(git2::Error::from_str("empty revision")),
+    }
+    Ok(())
+}
+
+/// Whether `commit` changes `path`: a root commit touches it when the path
+/// exists, a merge only when it differs from every parent, like `git log`.
+fn commit_touches_path(commit: &git2::Commit<'_>, path: &Path) -> Result<bool, git2::Error> {
+    let entry_id = |c: &git2::Commit<'_>| -> Result<Option<git2::Oid>, git2::Error> {
+        Ok(c.tree()?.get_path(path).ok().map(|e| e.id()))
+    };
+    let own = entry_id(commit)?;
+    if commit.parent_count() == 0 {
+        return Ok(own.is_some());
+    }
+    for parent in commit.parents() {
+        if entry_id(&parent)? == own {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn commit_info(repo: &Repository, commit: &git2::Commit<'_>) -> Result<CommitInfo, git2::Error> {
+    let parent_tree = match commit.parent_count() {
+        0 => None,
+        _ => Some(commit.parent(0)?.tree()?),
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    let stats = diff.stats()?;
+
+    let hash = commit.id().to_string();
+    let short_hash = hash[..7.min(hash.len())].to_string();
+    Ok(CommitInfo {
+        hash,
+        short_hash,
+        message: commit.message().unwrap_or("").to_string(),
+        author_name: commit.author().name().unwrap_or("").to_string(),
+        author_email: commit.author().email().unwrap_or("").to_string(),
+        timestamp: commit.time().seconds(),
+        parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+        files_changed: stats.files_changed(),
+    })
 }
 
 #[tauri::command]
-pub fn get_git_log(repo_path: String, limit: Option<usize>) -> Result<Vec<CommitInfo>, String> {
+pub fn get_git_log(
+    repo_path: String,
+    limit: Option<usize>,
+    filter: Option<LogFilter>,
+) -> Result<Vec<CommitInfo>, String> {
     let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
     let limit = limit.unwrap_or(50);
-
-    let head = match repo.head() {
-        Ok(h) => h,
-        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => return Ok(Vec::new()),
-        Err(e) => return Err(e.message().to_string()),
-    };
+    let filter = filter.unwrap_or_default();
 
     let mut revwalk = repo.revwalk().map_err(|e| e.message().to_string())?;
+    match filter.revision.as_deref() {
+        Some(spec) => push_revision(&repo, &mut revwalk, spec)
+            .map_err(|e| format!("Invalid revision '{spec}': {}", e.message()))?,
+        None => {
+            let head = match repo.head() {
+                Ok(h) => h,
+                Err(e) if e.code() == git2::ErrorCode::UnbornBranch => return Ok(Vec::new()),
+                Err(e) => return Err(e.message().to_string()),
+            };
+            revwalk
+                .push(head.target().ok_or("HEAD has no target")?)
+                .map_err(|e| e.message().to_string())?;
+        }
+    }
     revwalk
-        .push(head.target().ok_or("HEAD has no target")?)
-        .map_err(|e| e.message().to_string())?;
-    revwalk
-        .set_sorting(Sort::TIME)
+        .set_sorting(Sort::TOPOLOGICAL | Sort::TIME)
         .map_err(|e| e.message().to_string())?;
 
+    let cursor = filter
+        .cursor
+        .as_deref()
+        .map(git2::Oid::from_str)
+        .transpose()
+        .map_err(|_| "Invalid cursor".to_string())?;
+    let message_regex = filter
+        .message_regex
+        .as_deref()
+        .map(regex::Regex::new)
+        .transpose()
+        .map_err(|e| format!("Invalid message regex: {e}"))?;
+    let author = filter.author.as_deref().map(str::to_lowercase);
+    let path = filter.path.as_deref().map(Path::new);
+
+    let mut skipping = cursor.is_some();
     let mut commits = Vec::new();
     for oid in revwalk {
         if commits.len() >= limit {
             break;
         }
         let oid = oid.map_err(|e| e.message().to_string())?;
+        if skipping {
+            skipping = Some(oid) != cursor;
+            continue;
+        }
         let commit = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
-        let hash = oid.to_string();
-        let short_hash = hash[..7.min(hash.len())].to_string();
-        commits.push(CommitInfo {
-            hash,
-            short_hash,
-            message: commit.message().unwrap_or("").to_string(),
-            author_name: commit.author().name().unwrap_or("").to_string(),
-            author_email: commit.author().email().unwrap_or("").to_string(),
-            timestamp: commit.time().seconds(),
-        });
+
+        let time = commit.time().seconds();
+        if filter.since.is_some_and(|since| time < since)
+            || filter.until.is_some_and(|until| time > until)
+        {
+            continue;
+        }
+        if let Some(author) = &author {
+            let sig = commit.author();
+            let name = sig.name().unwrap_or("").to_lowercase();
+            let email = sig.email().unwrap_or("").to_lowercase();
+            if !name.contains(author.as_str()) && !email.contains(author.as_str()) {
+                continue;
+            }
+        }
+        if let Some(re) = &message_regex {
+            if !re.is_match(commit.message().unwrap_or("")) {
+                continue;
+            }
+        }
+        if let Some(path) = path {
+            if !commit_touches_path(&commit, path).map_err(|e| e.message().to_string())? {
+                continue;
+            }
+        }
+
+        commits.push(commit_info(&repo, &commit).map_err(|e| e.message().to_string())?);
     }
 
+    if skipping {
+        return Err("Cursor is not part of this history".to_string());
+    }
     Ok(commits)
 }
 
-#[tauri::command]
-pub fn get_current_branch(repo_path: String) -> Result<String, String> {
-    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+#[derive(Serialize)]
+pub struct CommitTrailer {
+    pub key: String,
+    pub value: String,
+}
 
-    let head = match repo.head() {
-        Ok(h) => h,
-        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
-            return Ok("(no commits)".to_string())
-        }
-        Err(e) => return Err(e.message().to_string()),
-    };
+#[derive(Serialize)]
+pub struct CommitSignature {
+    pub format: String,
+    pub status: String,
+    pub signer: Option<String>,
+}
 
-    if head.is_branch() {
-        Ok(head.shorthand().unwrap_or("HEAD").to_string())
-    } else {
-        let oid = head.target().ok_or("HEAD has no target")?;
-        let short = &oid.to_string()[..7];
-        Ok(format!("({short})"))
-    }
+#[derive(Serialize)]
+pub struct CommitDetail {
+    pub hash: String,
+    pub short_hash: String,
+    pub message: String,
+    pub author_name: String,
+    pub author_email: String,
+    pub author_timestamp: i64,
+    pub committer_name: String,
+    pub committer_email: String,
+    pub committer_timestamp: i64,
+    pub parents: Vec<String>,
+    pub trailers: Vec<CommitTrailer>,
+    pub signature: Option<CommitSignature>,
+    pub compared_parent: Option<String>,
+    pub files: Vec<FileDiff>,
 }
 
-#[tauri::command]
-pub fn git_stage(repo_path: String, paths: Vec<String>) -> Result<(), String> {
-    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
-    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+fn temp_file_path(name: &str, extension: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!(
+        "releash-{name}-{}-{}.{extension}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0)
+    ))
+}
 
-    let targets: Vec<String> = if paths.is_empty() {
-        let mut opts = StatusOptions::new();
-        opts.include_untracked(true)
-            .recurse_untracked_dirs(true)
-            .renames_index_to_workdir(true);
-        let statuses = repo
-            .statuses(Some(&mut opts))
-            .map_err(|e| e.message().to_string())?;
-        statuses
-            .iter()
-            .filter_map(|entry| {
-                let s = entry.status();
-                if s.contains(git2::Status::WT_NEW)
-                    || s.contains(git2::Status::WT_MODIFIED)
-                    || s.contains(git2::Status::WT_DELETED)
-                    || s.contains(git2::Status::WT_RENAMED)
-                    || s.contains(git2::Status::WT_TYPECHANGE)
-                {
-                    entry.path().map(|p| p.to_string())
-                } else {
-                    None
-                }
-            })
-            .collect()
+fn run_with_stdin(command: &mut Command, stdin: &[u8]) -> Option<std::process::Output> {
+    use std::io::Write;
+
+    command
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            if let Some(mut pipe) = child.stdin.take() {
+                pipe.write_all(stdin)?;
+            }
+            child.wait_with_output()
+        })
+        .ok()
+}
+
+/// Verifies a commit signature with the same programs `git verify-commit`
+/// uses. SSH signatures are only `"good"` against `gpg.ssh.allowedSignersFile`.
+fn verify_commit_signature(
+    config: &git2::Config,
+    signature: &[u8],
+    signed_data: &[u8],
+    committer_email: &str,
+) -> CommitSignature {
+    let armor = String::from_utf8_lossy(signature);
+    let format = if armor.starts_with("-----BEGIN SSH SIGNATURE") {
+        "ssh"
+    } else if armor.starts_with("-----BEGIN PGP") {
+        "openpgp"
     } else {
-        paths
+        "x509"
+    };
+    let mut result = CommitSignature {
+        format: format.to_string(),
+        status: "unknown".to_string(),
+        signer: None,
     };
 
-    let workdir = repo
-        .workdir()
-        .ok_or_else(|| "bare repository".to_string())?;
+    let sig_path = temp_file_path("signature", "sig");
+    if std::fs::write(&sig_path, signature).is_err() {
+        return result;
+    }
 
-    for p in &targets {
-        let full_path = workdir.join(p);
-        if full_path.exists() {
-            index
-                .add_path(Path::new(p))
-                .map_err(|e| e.message().to_string())?;
-        } else {
-            index
-                .remove_path(Path::new(p))
-                .map_err(|e| e.message().to_string())?;
+    if format == "ssh" {
+        let program = config
+            .get_string("gpg.ssh.program")
+            .unwrap_or_else(|_| "ssh-keygen".to_string());
+        let allowed = config.get_path("gpg.ssh.allowedSignersFile").ok();
+        let verified = allowed.and_then(|allowed| {
+            run_with_stdin(
+                Command::new(&program)
+                    .args(["-Y", "verify", "-n", "git", "-f"])
+                    .arg(allowed)
+                    .args(["-I", committer_email, "-s"])
+                    .arg(&sig_path),
+                signed_data,
+            )
+        });
+        if verified.is_some_and(|o| o.status.success()) {
+            result.status = "good".to_string();
+            result.signer = Some(committer_email.to_string());
+        } else if let Some(output) = run_with_stdin(
+            Command::new(&program)
+                .args(["-Y", "check-novalidate", "-n", "git", "-s"])
+                .arg(&sig_path),
+            signed_data,
+        ) {
+            result.status = if output.status.success() {
+                "untrusted"
+            } else {
+                "bad"
+            }
+            .to_string();
+        }
+    } else {
+        let default_program = if format == "x509" { "gpgsm" } else { "gpg" };
+        let program = config
+            .get_string(&format!("gpg.{format}.program"))
+            .or_else(|_| config.get_string("gpg.program"))
+            .unwrap_or_else(|_| default_program.to_string());
+        if let Some(output) = run_with_stdin(
+            Command::new(program)
+                .args(["--status-fd=1", "--verify"])
+                .arg(&sig_path)
+                .arg("-"),
+            signed_data,
+        ) {
+            for line in String::from_utf8_lossy(&output.stdout).lines() {
+                let mut fields = line.splitn(4, ' ').skip(1);
+                match (fields.next(), fields.next(), fields.next()) {
+                    (Some("GOODSIG"), _, signer) => {
+                        result.status = "good".to_string();
+                        result.signer = signer.map(str::to_string);
+                    }
+                    (Some("EXPSIG" | "EXPKEYSIG" | "REVKEYSIG"), _, signer) => {
+                        result.status = "untrusted".to_string();
+                        result.signer = signer.map(str::to_string);
+                    }
+                    (Some("BADSIG"), _, signer) => {
+                        result.status = "bad".to_string();
+                        result.signer = signer.map(str::to_string);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
         }
     }
 
-    index.write().map_err(|e| e.message().to_string())?;
-    Ok(())
+    let _ = std::fs::remove_file(sig_path);
+    result
 }
 
 #[tauri::command]
-pub fn git_unstage(repo_path: String, paths: Vec<String>) -> Result<(), String> {
+pub fn get_commit_detail(
+    repo_path: String,
+    hash: String,
+    parent: Option<usize>,
+    context_lines: Option<u32>,
+) -> Result<CommitDetail, String> {
     let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let commit = repo
+        .revparse_single(&hash)
+        .and_then(|o| o.peel_to_commit())
+        .map_err(|e| e.message().to_string())?;
 
-    let head_result = repo.head();
-    let is_unborn = matches!(&head_result, Err(e) if e.code() == ErrorCode::UnbornBranch);
-
-    if is_unborn {
-        let mut index = repo.index().map_err(|e| e.message().to_string())?;
-        if paths.is_empty() {
-            index.clear().map_err(|e| e.message().to_string())?;
-        } else {
-            for p in &paths {
-                index
-                    .remove_path(Path::new(p))
-                    .map_err(|e| e.message().to_string())?;
+    let parent_commit = match (parent, commit.parent_count()) {
+        (None, 0) => None,
+        (parent, count) => {
+            let index = parent.unwrap_or(0);
+            if index >= count {
+                return Err(format!("Commit has no parent at index {index}"));
             }
+            Some(commit.parent(index).map_err(|e| e.message().to_string())?)
         }
-        index.write().map_err(|e| e.message().to_string())?;
-    } else {
-        let head_ref = head_result.map_err(|e| e.message().to_string())?;
-        let head_obj = head_ref
-            .peel(git2::ObjectType::Any)
-            .map_err(|e| e.message().to_string())?;
+    };
+    let parent_tree = parent_commit
+        .as_ref()
+        .map(|p| p.tree())
+        .transpose()
+        .map_err(|e| e.message().to_string())?;
+    let tree = commit.tree().map_err(|e| e.message().to_string())?;
 
-        let targets: Vec<String> = if paths.is_empty() {
-            let mut opts = StatusOptions::new();
-            opts.include_untracked(true).recurse_untracked_dirs(true);
-            let statuses = repo
-                .statuses(Some(&mut opts))
-                .map_err(|e| e.message().to_string())?;
-            statuses
+    let mut opts = git2::DiffOptions::new();
+    opts.context_lines(context_lines.unwrap_or(3));
+    let mut diff = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts))
+        .map_err(|e| e.message().to_string())?;
+    detect_renames(&mut diff, false)?;
+    let files = file_diffs_from_diff(&diff)?;
+
+    let message = commit.message().unwrap_or("").to_string();
+    let trailers = git2::message_trailers_strs(&message)
+        .map(|trailers| {
+            trailers
                 .iter()
-                .filter_map(|entry| {
-                    let s = entry.status();
-                    if s.contains(git2::Status::INDEX_NEW)
-                        || s.contains(git2::Status::INDEX_MODIFIED)
-                        || s.contains(git2::Status::INDEX_DELETED)
-                        || s.contains(git2::Status::INDEX_RENAMED)
-                    {
-                        entry.path().map(|p| p.to_string())
-                    } else {
-                        None
-                    }
+                .map(|(key, value)| CommitTrailer {
+                    key: key.to_string(),
+                    value: value.to_string(),
                 })
                 .collect()
-        } else {
-            paths
-        };
+        })
+        .unwrap_or_default();
+
+    let author = commit.author();
+    let committer = commit.committer();
+    let signature = match repo.extract_signature(&commit.id(), None) {
+        Ok((signature, signed_data)) => {
+            let config = repo.config().map_err(|e| e.message().to_string())?;
+            Some(verify_commit_signature(
+                &config,
+                &signature,
+                &signed_data,
+                committer.email().unwrap_or(""),
+            ))
+        }
+        Err(e) if e.code() == ErrorCode::NotFound => None,
+        Err(e) => return Err(e.message().to_string()),
+    };
 
-        let path_specs: Vec<&str> = targets.iter().map(|s| s.as_str()).collect();
-        repo.reset_default(Some(&head_obj), &path_specs)
-            .map_err(|e| e.message().to_string())?;
-    }
+    let hash = commit.id().to_string();
+    let short_hash = hash[..7.min(hash.len())].to_string();
+    Ok(CommitDetail {
+        hash,
+        short_hash,
+        message,
+        author_name: author.name().unwrap_or("").to_string(),
+        author_email: author.email().unwrap_or("").to_string(),
+        author_timestamp: author.when().seconds(),
+        committer_name: committer.name().unwrap_or("").to_string(),
+        committer_email: committer.email().unwrap_or("").to_string(),
+        committer_timestamp: committer.when().seconds(),
+        parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+        trailers,
+        signature,
+        compared_parent: parent_commit.map(|p| p.id().to_string()),
+        files,
+    })
+}
 
-    Ok(())
+#[derive(Serialize)]
+pub struct BlameLine {
+    pub line: usize,
+    pub content: String,
+    pub hash: Option<String>,
+    pub short_hash: String,
+    pub author_name: String,
+    pub author_email: String,
+    pub timestamp: i64,
+    pub summary: String,
+    pub original_path: Option<String>,
+    pub original_line: usize,
 }
 
+/// libgit2 ignores its copy and move tracking flags, so with `follow_moves`
+/// a line is traced past any commit that removed the same line elsewhere
+/// (in that file or another), like `git blame -M -C`.
 #[tauri::command]
-pub fn git_commit(repo_path: String, message: String) -> Result<String, String> {
+pub fn git_blame(
+    repo_path: String,
+    path: String,
+    git_ref: Option<String>,
+    include_worktree: Option<bool>,
+    ignore_whitespace: Option<bool>,
+    follow_moves: Option<bool>,
+) -> Result<Vec<BlameLine>, String> {
     let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
-    let sig = repo.signature().map_err(|e| e.message().to_string())?;
+    let include_worktree = include_worktree.unwrap_or(false);
+    let ignore_whitespace = ignore_whitespace.unwrap_or(false);
 
-    let mut index = repo.index().map_err(|e| e.message().to_string())?;
-    let tree_id = index.write_tree().map_err(|e| e.message().to_string())?;
-    let tree = repo
-        .find_tree(tree_id)
+    let newest = match repo.revparse_single(git_ref.as_deref().unwrap_or("HEAD")) {
+        Ok(obj) => Some(obj.peel_to_commit().map_err(|e| e.message().to_string())?),
+        Err(e) if include_worktree && git_ref.is_none() && e.code() == ErrorCode::NotFound => None,
+        Err(e) => return Err(e.message().to_string()),
+    };
+
+    let content = if include_worktree {
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| "repository has no working directory".to_string())?;
+        std::fs::read(workdir.join(&path)).map_err(|e| format!("Failed to read {path}: {e}"))?
+    } else {
+        let commit = newest.as_ref().ok_or("HEAD has no commits")?;
+        let entry = commit
+            .tree()
+            .and_then(|t| t.get_path(Path::new(&path)))
+            .map_err(|e| e.message().to_string())?;
+        repo.find_blob(entry.id())
+            .map_err(|e| e.message().to_string())?
+            .content()
+            .to_vec()
+    };
+    if content.contains(&0) {
+        return Err(format!("{path} is binary"));
+    }
+    let text = String::from_utf8_lossy(&content);
+
+    let mut opts = git2::BlameOptions::new();
+    opts.ignore_whitespace(ignore_whitespace);
+    if let Some(commit) = &newest {
+        opts.newest_commit(commit.id());
+    }
+    // A file that is new in the worktree has no history to blame.
+    let committed = match &newest {
+        Some(commit) => commit
+            .tree()
+            .map_err(|e| e.message().to_string())?
+            .get_path(Path::new(&path))
+            .is_ok(),
+        None => false,
+    };
+    let file_blame = if committed {
+        Some(
+            repo.blame_file(Path::new(&path), Some(&mut opts))
+                .map_err(|e| e.message().to_string())?,
+        )
+    } else {
+        None
+    };
+    let blame = match &file_blame {
+        Some(blame) if include_worktree => Some(
+            blame
+                .blame_buffer(&content)
+                .map_err(|e| e.message().to_string())?,
+        ),
+        _ => None,
+    };
+    let blame = blame.as_ref().or(file_blame.as_ref());
+
+    let mut commits: HashMap<git2::Oid, (String, String, i64, String)> = HashMap::new();
+    let mut moves = MoveTracker::new(&repo, ignore_whitespace);
+    let mut lines = Vec::new();
+    for (idx, content) in text.lines().enumerate() {
+        let line = idx + 1;
+        let hunk = blame
+            .and_then(|b| b.get_line(line))
+            .filter(|h| !h.final_commit_id().is_zero());
+        let Some(hunk) = hunk else {
+            lines.push(BlameLine {
+                line,
+                content: content.to_string(),
+                hash: None,
+                short_hash: String::new(),
+                author_name: String::new(),
+                author_email: String::new(),
+                timestamp: 0,
+                summary: String::new(),
+                original_path: None,
+                original_line: line,
+            });
+            continue;
+        };
+
+        let mut oid = hunk.final_commit_id();
+        let mut original_path = hunk.path().map(|p| p.to_string_lossy().replace('\\', "/"));
+        let mut original_line = hunk.orig_start_line() + (line - hunk.final_start_line());
+        if follow_moves.unwrap_or(false) {
+            while let Some(source) = moves.source_of(oid, content)? {
+                (oid, original_path, original_line) = (source.0, Some(source.1), source.2);
+            }
+        }
+
+        // Hunks split by `blame_buffer` carry no signature, so read the commit.
+        if let std::collections::hash_map::Entry::Vacant(entry) = commits.entry(oid) {
+            let commit = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
+            let author = commit.author();
+            entry.insert((
+                author.name().unwrap_or("").to_string(),
+                author.email().unwrap_or("").to_string(),
+                author.when().seconds(),
+                commit.summary().unwrap_or("").to_string(),
+            ));
+        }
+        let (author_name, author_email, timestamp, summary) = commits[&oid].clone();
+        let hash = oid.to_string();
+        lines.push(BlameLine {
+            line,
+            content: content.to_string(),
+            short_hash: hash[..7.min(hash.len())].to_string(),
+            hash: Some(hash),
+            author_name,
+            author_email,
+            timestamp,
+            summary,
+            original_path,
+            original_line,
+        });
+    }
+
+    Ok(lines)
+}
+
+// Same threshold `git blame -M` uses before it believes a line was moved.
+const MIN_MOVED_LINE_CHARS: usize = 20;
+
+struct MoveTracker<'r> {
+    repo: &'r Repository,
+    ignore_whitespace: bool,
+    removed: HashMap<git2::Oid, HashMap<String, (String, usize)>>,
+    blames: HashMap<(git2::Oid, String), git2::Blame<'r>>,
+}
+
+impl<'r> MoveTracker<'r> {
+    fn new(repo: &'r Repository, ignore_whitespace: bool) -> Self {
+        MoveTracker {
+            repo,
+            ignore_whitespace,
+            removed: HashMap::new(),
+            blames: HashMap::new(),
+        }
+    }
+
+    fn key(&self, content: &str) -> Option<String> {
+        let content = content.trim_end_matches(['\n', '\r']);
+        if content.chars().filter(|c| c.is_alphanumeric()).count() < MIN_MOVED_LINE_CHARS {
+            return None;
+        }
+        Some(match self.ignore_whitespace {
+            true => content.split_whitespace().collect(),
+            false => content.to_string(),
+        })
+    }
+
+    fn removed_lines(
+        &self,
+        commit: &git2::Commit<'_>,
+    ) -> Result<HashMap<String, (String, usize)>, String> {
+        let parent_tree = commit
+            .parent(0)
+            .and_then(|p| p.tree())
+            .map_err(|e| e.message().to_string())?;
+        let tree = commit.tree().map_err(|e| e.message().to_string())?;
+        let mut diff = self
+            .repo
+            .diff_tree_to_tree(Some(&parent_tree), Some(&tree), None)
+            .map_err(|e| e.message().to_string())?;
+        diff.find_similar(None)
+            .map_err(|e| e.message().to_string())?;
+
+        let mut removed = HashMap::new();
+        diff.foreach(
+            &mut |_, _| true,
+            None,
+            None,
+            Some(&mut |delta, _, line| {
+                if line.origin() == '-' {
+                    let path = delta
+                        .old_file()
+                        .path()
+                        .map(|p| p.to_string_lossy().replace('\\', "/"));
+                    let key = self.key(&String::from_utf8_lossy(line.content()));
+                    if let (Some(path), Some(key), Some(old)) = (path, key, line.old_lineno()) {
+                        removed.entry(key).or_insert((path, old as usize));
+                    }
+                }
+                true
+            }),
+        )
         .map_err(|e| e.message().to_string())?;
+        Ok(removed)
+    }
 
-    let parents = match repo.head() {
-        Ok(head_ref) => {
-            let commit = head_ref
-                .peel_to_commit()
+    fn source_of(
+        &mut self,
+        oid: git2::Oid,
+        content: &str,
+    ) -> Result<Option<(git2::Oid, String, usize)>, String> {
+        let Some(key) = self.key(content) else {
+            return Ok(None);
+        };
+        let commit = self
+            .repo
+            .find_commit(oid)
+            .map_err(|e| e.message().to_string())?;
+        if commit.parent_count() != 1 {
+            return Ok(None);
+        }
+        if !self.removed.contains_key(&oid) {
+            let removed = self.removed_lines(&commit)?;
+            self.removed.insert(oid, removed);
+        }
+        let Some((path, line)) = self.removed[&oid].get(&key).cloned() else {
+            return Ok(None);
+        };
+
+        let parent = commit.parent_id(0).map_err(|e| e.message().to_string())?;
+        let cache_key = (parent, path.clone());
+        if !self.blames.contains_key(&cache_key) {
+            let mut opts = git2::BlameOptions::new();
+            opts.newest_commit(parent)
+                .ignore_whitespace(self.ignore_whitespace);
+            let blame = self
+                .repo
+                .blame_file(Path::new(&path), Some(&mut opts))
                 .map_err(|e| e.message().to_string())?;
-            vec![commit]
+            self.blames.insert(cache_key.clone(), blame);
+        }
+        let Some(hunk) = self.blames[&cache_key].get_line(line) else {
+            return Ok(None);
+        };
+        let source_path = hunk
+            .path()
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or(path);
+        Ok(Some((
+            hunk.final_commit_id(),
+            source_path,
+            hunk.orig_start_line() + (line - hunk.final_start_line()),
+        )))
+    }
+}
+
+#[tauri::command]
+pub fn get_current_branch(repo_path: String) -> Result<String, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+
+    let head = match repo.head() {
+        Ok(h) => h,
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+            return Ok("(no commits)".to_string())
         }
-        Err(e) if e.code() == ErrorCode::UnbornBranch => vec![],
         Err(e) => return Err(e.message().to_string()),
     };
 
-    let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
-    let oid = repo
-        .commit(Some("HEAD"), &sig, &sig, &message, &tree, &parent_refs)
+    if head.is_branch() {
+        Ok(head.shorthand().unwrap_or("HEAD").to_string())
+    } else {
+        let oid = head.target().ok_or("HEAD has no target")?;
+        let short = &oid.to_string()[..7];
+        Ok(format!("({short})"))
+    }
+}
+
+fn with_rename_partners(
+    repo: &Repository,
+    paths: Vec<String>,
+    index_side: bool,
+) -> Result<Vec<String>, String> {
+    let mut opts = StatusOptions::new();
+    if index_side {
+        opts.renames_head_to_index(true);
+    } else {
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .renames_index_to_workdir(true);
+    }
+    let statuses = repo
+        .statuses(Some(&mut opts))
         .map_err(|e| e.message().to_string())?;
 
-    Ok(oid.to_string())
+    let mut result = paths;
+    for entry in statuses.iter() {
+        let delta = if index_side {
+            entry.head_to_index()
+        } else {
+            entry.index_to_workdir()
+        };
+        let Some(delta) = delta.filter(|d| d.status() == git2::Delta::Renamed) else {
+            continue;
+        };
+        if let (Some(old), Some(new)) = (delta_path(delta.old_file()), delta_path(delta.new_file()))
+        {
+            if result.contains(&new) && !result.contains(&old) {
+                result.push(old);
+            } else if result.contains(&old) && !result.contains(&new) {
+                result.push(new);
+            }
+        }
+    }
+    Ok(result)
 }
 
 #[tauri::command]
-pub fn git_push(repo_path: String) -> Result<String, String> {
-    Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
-
-    let output = Command::new("git")
-        .args(["push", "-u", "origin", "HEAD"])
-        .current_dir(&repo_path)
-        .output()
-        .map_err(|e| format!("Failed to execute git push: {e}"))?;
+pub fn git_stage(repo_path: String, paths: Vec<String>) -> Result<(), String> {
+    journaled(&repo_path, "stage", || stage_paths(&repo_path, paths))
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+fn stage_paths(repo_path: &str, paths: Vec<String>) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
 
-    if output.status.success() {
-        Ok(format!("{stdout}{stderr}").trim().to_string())
+    let targets: Vec<String> = if paths.is_empty() {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .renames_index_to_workdir(true);
+        let statuses = repo
+            .statuses(Some(&mut opts))
+            .map_err(|e| e.message().to_string())?;
+        statuses
+            .iter()
+            .filter_map(|entry| {
+                let s = entry.status();
+                if s.contains(git2::Status::WT_NEW)
+                    || s.contains(git2::Status::WT_MODIFIED)
+                    || s.contains(git2::Status::WT_DELETED)
+                    || s.contains(git2::Status::WT_RENAMED)
+                    || s.contains(git2::Status::WT_TYPECHANGE)
+                {
+                    let delta = entry.index_to_workdir();
+                    let old = delta.as_ref().and_then(|d| delta_path(d.old_file()));
+                    let new = delta.as_ref().and_then(|d| delta_path(d.new_file()));
+                    Some(match (old, new) {
+                        (Some(old), Some(new)) if old != new => vec![old, new],
+                        (old, new) => new.or(old).into_iter().collect(),
+                    })
+                } else {
+                    None
+                }
+            })
+            .flatten()
+            .collect()
     } else {
-        Err(stderr.trim().to_string())
+        with_rename_partners(&repo, paths, false)?
+    };
+
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "bare repository".to_string())?;
+
+    for p in &targets {
+        let full_path = workdir.join(p);
+        if full_path.exists() {
+            index
+                .add_path(Path::new(p))
+                .map_err(|e| e.message().to_string())?;
+        } else {
+            index
+                .remove_path(Path::new(p))
+                .map_err(|e| e.message().to_string())?;
+        }
     }
+
+    index.write().map_err(|e| e.message().to_string())?;
+    Ok(())
 }
 
 #[tauri::command]
-pub fn git_stage_hunk(repo_path: String, patch: String) -> Result<(), String> {
-    Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+pub fn git_unstage(repo_path: String, paths: Vec<String>) -> Result<(), String> {
+    journaled(&repo_path, "unstage", || unstage_paths(&repo_path, paths))
+}
 
-    let mut child = Command::new("git")
-        .args(["apply", "--cached"])
-        .current_dir(&repo_path)
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to execute git apply: {e}"))?;
+fn unstage_paths(repo_path: &str, paths: Vec<String>) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
 
-    {
-        use std::io::Write;
-        let stdin = child
-            .stdin
-            .as_mut()
-            .ok_or("Failed to open stdin for git apply")?;
-        stdin
-            .write_all(patch.as_bytes())
-            .map_err(|e| format!("Failed to write patch: {e}"))?;
+    let head_result = repo.head();
+    let is_unborn = matches!(&head_result, Err(e) if e.code() == ErrorCode::UnbornBranch);
+
+    if is_unborn {
+        let mut index = repo.index().map_err(|e| e.message().to_string())?;
+        if paths.is_empty() {
+            index.clear().map_err(|e| e.message().to_string())?;
+        } else {
+            for p in &paths {
+                index
+                    .remove_path(Path::new(p))
+                    .map_err(|e| e.message().to_string())?;
+            }
+        }
+        index.write().map_err(|e| e.message().to_string())?;
+    } else {
+        let head_ref = head_result.map_err(|e| e.message().to_string())?;
+        let head_obj = head_ref
+            .peel(git2::ObjectType::Any)
+            .map_err(|e| e.message().to_string())?;
+
+        let targets: Vec<String> = if paths.is_empty() {
+            let mut opts = StatusOptions::new();
+            opts.include_untracked(true).recurse_untracked_dirs(true);
+            let statuses = repo
+                .statuses(Some(&mut opts))
+                .map_err(|e| e.message().to_string())?;
+            statuses
+                .iter()
+                .filter_map(|entry| {
+                    let s = entry.status();
+                    if s.contains(git2::Status::INDEX_NEW)
+                        || s.contains(git2::Status::INDEX_MODIFIED)
+                        || s.contains(git2::Status::INDEX_DELETED)
+                        || s.contains(git2::Status::INDEX_RENAMED)
+                    {
+                        entry.path().map(|p| p.to_string())
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        } else {
+            with_rename_partners(&repo, paths, true)?
+        };
+
+        let path_specs: Vec<&str> = targets.iter().map(|s| s.as_str()).collect();
+        repo.reset_default(Some(&head_obj), &path_specs)
+            .map_err(|e| e.message().to_string())?;
     }
 
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for git apply: {e}"))?;
+    Ok(())
+}
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(stderr.trim().to_string())
+#[derive(Serialize)]
+pub struct ConflictContent {
+    pub path: String,
+    pub base: Option<BlobContent>,
+    pub ours: Option<BlobContent>,
+    pub theirs: Option<BlobContent>,
+}
+
+fn conflict_for_path(index: &git2::Index, path: &str) -> Result<git2::IndexConflict, String> {
+    let conflicts = index.conflicts().map_err(|e| e.message().to_string())?;
+    for conflict in conflicts {
+        let conflict = conflict.map_err(|e| e.message().to_string())?;
+        let matches = [&conflict.ancestor, &conflict.our, &conflict.their]
+            .into_iter()
+            .flatten()
+            .any(|entry| entry.path == path.as_bytes());
+        if matches {
+            return Ok(conflict);
+        }
     }
+    Err(format!("no conflict for {path}"))
 }
 
-#[tauri::command]
-pub fn git_unstage_hunk(repo_path: String, patch: String) -> Result<(), String> {
-    Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+fn conflict_side_content(
+    repo: &Repository,
+    entry: Option<&git2::IndexEntry>,
+) -> Result<Option<BlobContent>, String> {
+    entry
+        .map(|entry| {
+            let blob = repo
+                .find_blob(entry.id)
+                .map_err(|e| e.message().to_string())?;
+            let path = String::from_utf8_lossy(&entry.path).to_string();
+            Ok(blob_content(Path::new(&path), blob.content()))
+        })
+        .transpose()
+}
 
-    let mut child = Command::new("git")
-        .args(["apply", "--cached", "--reverse"])
-        .current_dir(&repo_path)
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to execute git apply: {e}"))?;
+#[tauri::command]
+pub fn get_conflict_content(repo_path: String, path: String) -> Result<ConflictContent, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let index = repo.index().map_err(|e| e.message().to_string())?;
+    let conflict = conflict_for_path(&index, &path)?;
+
+    Ok(ConflictContent {
+        base: conflict_side_content(&repo, conflict.ancestor.as_ref())?,
+        ours: conflict_side_content(&repo, conflict.our.as_ref())?,
+        theirs: conflict_side_content(&repo, conflict.their.as_ref())?,
+        path,
+    })
+}
 
+fn write_worktree_file(path: &Path, content: &[u8], mode: u32) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    #[cfg(unix)]
+    if mode == 0o120000 {
+        if std::fs::symlink_metadata(path).is_ok() {
+            std::fs::remove_file(path).map_err(|e| e.to_string())?;
+        }
+        use std::os::unix::ffi::OsStrExt;
+        let target = std::ffi::OsStr::from_bytes(content);
+        return std::os::unix::fs::symlink(target, path).map_err(|e| e.to_string());
+    }
+    std::fs::write(path, content).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
     {
-        use std::io::Write;
-        let stdin = child
-            .stdin
-            .as_mut()
-            .ok_or("Failed to open stdin for git apply")?;
-        stdin
-            .write_all(patch.as_bytes())
-            .map_err(|e| format!("Failed to write patch: {e}"))?;
+        use std::os::unix::fs::PermissionsExt;
+        let perm = if mode == 0o100755 { 0o755 } else { 0o644 };
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(perm))
+            .map_err(|e| e.to_string())?;
     }
+    #[cfg(not(unix))]
+    let _ = mode;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn git_resolve_conflict(
+    repo_path: String,
+    path: String,
+    resolution: String,
+) -> Result<(), String> {
+    journaled(&repo_path, "resolve_conflict", || {
+        resolve_conflict(&repo_path, path, resolution)
+    })
+}
+
+fn resolve_conflict(repo_path: &str, path: String, resolution: String) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "bare repository".to_string())?;
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+    let conflict = conflict_for_path(&index, &path)?;
+    let full_path = workdir.join(&path);
+
+    let side = match resolution.as_str() {
+        "mark_resolved" => None,
+        "ours" => Some(conflict.our),
+        "theirs" => Some(conflict.their),
+        other => return Err(format!("unknown resolution: {other}")),
+    };
+
+    if let Some(side) = side {
+        match side {
+            Some(entry) => {
+                let blob = repo
+                    .find_blob(entry.id)
+                    .map_err(|e| e.message().to_string())?;
+                write_worktree_file(&full_path, blob.content(), entry.mode)?;
+            }
+            None if full_path.exists() => {
+                std::fs::remove_file(&full_path).map_err(|e| e.to_string())?;
+            }
+            None => {}
+        }
+    }
+
+    if full_path.exists() {
+        index
+            .add_path(Path::new(&path))
+            .map_err(|e| e.message().to_string())?;
+    } else {
+        // Also drops the conflict stages for the path.
+        index
+            .remove_path(Path::new(&path))
+            .map_err(|e| e.message().to_string())?;
+    }
+
+    index.write().map_err(|e| e.message().to_string())?;
+    Ok(())
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct CommitOptions {
+    /// Replace HEAD instead of committing on top of it. An empty `message`
+    /// keeps HEAD's message.
+    pub amend: bool,
+    pub sign_off: bool,
+    pub author_name: Option<String>,
+    pub author_email: Option<String>,
+    pub allow_empty: bool,
+    pub sign: Option<bool>,
+    pub skip_hooks: bool,
+}
+
+fn add_sign_off(message: &str, sig: &git2::Signature<'_>) -> String {
+    let trailer = format!(
+        "Signed-off-by: {} <{}>",
+        sig.name().unwrap_or(""),
+        sig.email().unwrap_or("")
+    );
+    let body = message.trim_end();
+    if body.lines().last() == Some(trailer.as_str()) {
+        return format!("{body}\n");
+    }
+
+    let last_paragraph = body.rsplit("\n\n").next().unwrap_or("");
+    let ends_with_trailers = body.contains("\n\n")
+        && last_paragraph.lines().all(|line| {
+            line.split_once(": ").is_some_and(|(key, _)| {
+                !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            })
+        });
+    let separator = if ends_with_trailers { "\n" } else { "\n\n" };
+    format!("{body}{separator}{trailer}\n")
+}
+
+fn sign_commit_buffer(
+    config: &git2::Config,
+    buffer: &str,
+    committer: &git2::Signature<'_>,
+) -> Result<String, String> {
+    use std::io::Write;
+
+    let format = config
+        .get_string("gpg.format")
+        .unwrap_or_else(|_| "openpgp".to_string());
+    let signing_key = config.get_string("user.signingkey").ok();
+
+    // Literal SSH public keys are handed to ssh-keygen through a temp file.
+    let mut literal_key_file = None;
+    let mut command = match format.as_str() {
+        "ssh" => {
+            let key = signing_key
+                .ok_or_else(|| "gpg.format is ssh but user.signingkey is not set".to_string())?;
+            let program = config
+                .get_string("gpg.ssh.program")
+                .unwrap_or_else(|_| "ssh-keygen".to_string());
+            let literal = key.strip_prefix("key::").unwrap_or(&key);
+            let key_path = if literal.starts_with("ssh-") || literal.starts_with("ecdsa-") {
+                let path = temp_file_path("signing-key", "pub");
+                std::fs::write(&path, literal)
+                    .map_err(|e| format!("Failed to write signing key: {e}"))?;
+                literal_key_file = Some(path.clone());
+                path
+            } else {
+                std::path::PathBuf::from(key)
+            };
+            let mut command = Command::new(program);
+            command
+                .args(["-Y", "sign", "-n", "git", "-f"])
+                .arg(&key_path);
+            if literal_key_file.is_some() {
+                command.arg("-U");
+            }
+            command
+        }
+        "openpgp" | "x509" => {
+            let default_program = if format == "x509" { "gpgsm" } else { "gpg" };
+            let program = config
+                .get_string(&format!("gpg.{format}.program"))
+                .or_else(|_| config.get_string("gpg.program"))
+                .unwrap_or_else(|_| default_program.to_string());
+            let key = signing_key.unwrap_or_else(|| {
+                format!(
+                    "{} <{}>",
+                    committer.name().unwrap_or(""),
+                    committer.email().unwrap_or("")
+                )
+            });
+            let mut command = Command::new(program);
+            command.args(["--status-fd=2", "-bsau", &key]);
+            command
+        }
+        other => return Err(format!("unsupported gpg.format: {other}")),
+    };
+
+    let output = command
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(buffer.as_bytes())?;
+            }
+            child.wait_with_output()
+        });
+    if let Some(path) = literal_key_file {
+        let _ = std::fs::remove_file(path);
+    }
+    let output = output.map_err(|e| format!("Failed to run commit signing program: {e}"))?;
+
+    let signature = String::from_utf8_lossy(&output.stdout).to_string();
+    if !output.status.success() || signature.trim().is_empty() {
+        return Err(format!(
+            "commit signing failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(signature)
+}
+
+fn update_head(repo: &Repository, oid: git2::Oid, log_message: &str) -> Result<(), String> {
+    let head = repo
+        .find_reference("HEAD")
+        .map_err(|e| e.message().to_string())?;
+    let target = head.symbolic_target().unwrap_or("HEAD").to_string();
+    repo.reference(&target, oid, true, log_message)
+        .map_err(|e| e.message().to_string())?;
+    Ok(())
+}
+
+#[derive(Clone, Serialize)]
+pub struct HookOutput {
+    pub repo_path: String,
+    pub hook: String,
+    pub stream: String,
+    pub line: String,
+}
+
+const HOOK_OUTPUT_EVENT: &str = "git-hook-output";
+
+#[derive(Debug, Serialize)]
+pub struct CommitError {
+    pub reason: String,
+    pub hook: Option<String>,
+    pub exit_code: Option<i32>,
+    pub output: String,
+    pub message: String,
+}
+
+impl From<String> for CommitError {
+    fn from(message: String) -> Self {
+        CommitError {
+            reason: "other".to_string(),
+            hook: None,
+            exit_code: None,
+            output: String::new(),
+            message,
+        }
+    }
+}
+
+/// `core.hooksPath` is relative to the worktree root, like git's.
+fn hooks_dir(repo: &Repository) -> Option<std::path::PathBuf> {
+    let workdir = repo.workdir()?;
+    if let Some(custom) = config_string(repo, "core.hooksPath") {
+        let path = match custom.strip_prefix("~/") {
+            Some(rest) => std::path::PathBuf::from(std::env::var_os("HOME")?).join(rest),
+            None => std::path::PathBuf::from(custom),
+        };
+        return Some(if path.is_absolute() {
+            path
+        } else {
+            workdir.join(path)
+        });
+    }
+    let common_dir = if repo.is_worktree() {
+        let common = std::fs::read_to_string(repo.path().join("commondir")).ok()?;
+        repo.path().join(common.trim())
+    } else {
+        repo.path().to_path_buf()
+    };
+    Some(common_dir.join("hooks"))
+}
+
+fn find_hook(repo: &Repository, name: &str) -> Option<std::path::PathBuf> {
+    let path = hooks_dir(repo)?.join(name);
+    let metadata = std::fs::metadata(&path).ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return None;
+        }
+    }
+    Some(path)
+}
+
+fn run_hook(
+    repo: &Repository,
+    repo_path: &str,
+    name: &str,
+    args: &[&str],
+    emit: &dyn Fn(HookOutput),
+) -> Result<(), CommitError> {
+    use std::io::BufRead;
+
+    let Some(hook) = find_hook(repo, name) else {
+        return Ok(());
+    };
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "cannot run hooks in a bare repository".to_string())?;
+
+    // Git for Windows runs hooks through its bundled shell.
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("sh");
+        command.arg(&hook);
+        command
+    } else {
+        Command::new(&hook)
+    };
+    let mut child = command
+        .args(args)
+        .current_dir(workdir)
+        .env("GIT_INDEX_FILE", repo.path().join("index"))
+        .env("GIT_EDITOR", ":")
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {name} hook: {e}"))?;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let stdout = child.stdout.take().map(|out| {
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            for line in std::io::BufReader::new(out).lines().map_while(Result::ok) {
+                let _ = sender.send(("stdout", line));
+            }
+        })
+    });
+    let stderr = child.stderr.take().map(|err| {
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            for line in std::io::BufReader::new(err).lines().map_while(Result::ok) {
+                let _ = sender.send(("stderr", line));
+            }
+        })
+    });
+    drop(sender);
+
+    let mut output = String::new();
+    for (stream, line) in receiver {
+        output.push_str(&line);
+        output.push('\n');
+        emit(HookOutput {
+            repo_path: repo_path.to_string(),
+            hook: name.to_string(),
+            stream: stream.to_string(),
+            line,
+        });
+    }
+    for reader in [stdout, stderr].into_iter().flatten() {
+        let _ = reader.join();
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for {name} hook: {e}"))?;
+    if status.success() {
+        return Ok(());
+    }
+    Err(CommitError {
+        reason: "hook_failed".to_string(),
+        hook: Some(name.to_string()),
+        exit_code: status.code(),
+        message: format!("{name} hook failed; commit aborted"),
+        output,
+    })
+}
+
+fn create_commit(
+    repo_path: &str,
+    message: String,
+    options: CommitOptions,
+    emit: &dyn Fn(HookOutput),
+) -> Result<String, CommitError> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let sig = repo.signature().map_err(|e| e.message().to_string())?;
+
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+    if index.has_conflicts() {
+        return Err("cannot commit: unresolved conflicts remain"
+            .to_string()
+            .into());
+    }
+    if !options.skip_hooks {
+        run_hook(&repo, repo_path, "pre-commit", &[], emit)?;
+        // The hook may have re-staged files (formatters).
+        index.read(true).map_err(|e| e.message().to_string())?;
+    }
+    let tree_id = index.write_tree().map_err(|e| e.message().to_string())?;
+    let tree = repo
+        .find_tree(tree_id)
+        .map_err(|e| e.message().to_string())?;
+
+    let head_commit = match repo.head() {
+        Ok(head_ref) => Some(
+            head_ref
+                .peel_to_commit()
+                .map_err(|e| e.message().to_string())?,
+        ),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(e.message().to_string().into()),
+    };
+
+    let state = repo.state();
+    let mut parents = if options.amend {
+        let amended = head_commit
+            .as_ref()
+            .ok_or_else(|| "cannot amend: no commit to amend".to_string())?;
+        if state != git2::RepositoryState::Clean {
+            return Err("cannot amend while a merge or cherry-pick is in progress"
+                .to_string()
+                .into());
+        }
+        amended.parents().collect()
+    } else {
+        head_commit.iter().cloned().collect::<Vec<_>>()
+    };
+
+    if state == git2::RepositoryState::Merge {
+        // `mergehead_foreach` needs `&mut Repository`; MERGE_HEAD is one oid per line.
+        let merge_head = std::fs::read_to_string(repo.path().join("MERGE_HEAD"))
+            .map_err(|e| format!("Failed to read MERGE_HEAD: {e}"))?;
+        for line in merge_head.lines().filter(|l| !l.trim().is_empty()) {
+            let oid = git2::Oid::from_str(line.trim()).map_err(|e| e.message().to_string())?;
+            parents.push(repo.find_commit(oid).map_err(|e| e.message().to_string())?);
+        }
+    }
+
+    if !options.allow_empty && parents.len() <= 1 {
+        let parent_tree = parents.first().map(|p| p.tree_id());
+        let unchanged = match parent_tree {
+            Some(parent_tree) => parent_tree == tree_id,
+            None => tree.is_empty(),
+        };
+        if unchanged {
+            return Err("nothing to commit: no changes staged".to_string().into());
+        }
+    }
+
+    let reuse_message = options.amend && message.trim().is_empty();
+    let mut message = match (&head_commit, reuse_message) {
+        (Some(amended), true) => amended.message().unwrap_or("").to_string(),
+        _ => message,
+    };
+    if options.sign_off {
+        message = add_sign_off(&message, &sig);
+    }
+
+    // Message hooks edit COMMIT_EDITMSG in place, as with `git commit -m`.
+    let message_file = repo.path().join("COMMIT_EDITMSG");
+    std::fs::write(&message_file, &message)
+        .map_err(|e| format!("Failed to write COMMIT_EDITMSG: {e}"))?;
+    let message_path = message_file.to_string_lossy().to_string();
+    let source: &[&str] = if reuse_message {
+        &["commit", "HEAD"]
+    } else {
+        &["message"]
+    };
+    let mut prepare_args = vec![message_path.as_str()];
+    prepare_args.extend_from_slice(source);
+    run_hook(&repo, repo_path, "prepare-commit-msg", &prepare_args, emit)?;
+    if !options.skip_hooks {
+        run_hook(&repo, repo_path, "commit-msg", &[&message_path], emit)?;
+    }
+    let message = std::fs::read_to_string(&message_file)
+        .map_err(|e| format!("Failed to read COMMIT_EDITMSG: {e}"))?;
+    if message.trim().is_empty() {
+        return Err("aborting commit due to empty commit message"
+            .to_string()
+            .into());
+    }
+
+    let author = match (&options.author_name, &options.author_email) {
+        (None, None) if options.amend => head_commit
+            .as_ref()
+            .map(|c| c.author().to_owned())
+            .unwrap_or_else(|| sig.to_owned()),
+        // Like `git commit` after a cherry-pick, keep the picked commit's author.
+        (None, None) if state == git2::RepositoryState::CherryPick => {
+            let picked = std::fs::read_to_string(repo.path().join("CHERRY_PICK_HEAD"))
+                .map_err(|e| format!("Failed to read CHERRY_PICK_HEAD: {e}"))?;
+            let oid = git2::Oid::from_str(picked.trim()).map_err(|e| e.message().to_string())?;
+            let picked = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
+            let author = picked.author().to_owned();
+            author
+        }
+        (None, None) => sig.to_owned(),
+        (name, email) => git2::Signature::now(
+            name.as_deref().unwrap_or(sig.name().unwrap_or("")),
+            email.as_deref().unwrap_or(sig.email().unwrap_or("")),
+        )
+        .map_err(|e| e.message().to_string())?,
+    };
+
+    let config = repo.config().map_err(|e| e.message().to_string())?;
+    let sign = options
+        .sign
+        .unwrap_or_else(|| config.get_bool("commit.gpgsign").unwrap_or(false));
+
+    let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+    let oid = if sign {
+        let buffer = repo
+            .commit_create_buffer(&author, &sig, &message, &tree, &parent_refs)
+            .map_err(|e| e.message().to_string())?;
+        let buffer = buffer
+            .as_str()
+            .ok_or_else(|| "commit buffer is not valid UTF-8".to_string())?;
+        let signature = sign_commit_buffer(&config, buffer, &sig)?;
+        repo.commit_signed(buffer, &signature, None)
+            .map_err(|e| e.message().to_string())?
+    } else {
+        repo.commit(None, &author, &sig, &message, &tree, &parent_refs)
+            .map_err(|e| e.message().to_string())?
+    };
+
+    let summary = message.lines().next().unwrap_or("");
+    let log_message = if options.amend {
+        format!("commit (amend): {summary}")
+    } else if head_commit.is_none() {
+        format!("commit (initial): {summary}")
+    } else if state == git2::RepositoryState::Merge {
+        format!("commit (merge): {summary}")
+    } else if state == git2::RepositoryState::CherryPick {
+        format!("commit (cherry-pick): {summary}")
+    } else {
+        format!("commit: {summary}")
+    };
+    update_head(&repo, oid, &log_message)?;
+
+    if matches!(
+        state,
+        git2::RepositoryState::Merge
+            | git2::RepositoryState::CherryPick
+            | git2::RepositoryState::Revert
+    ) {
+        repo.cleanup_state().map_err(|e| e.message().to_string())?;
+    }
+
+    // Like git, a failing post-commit hook doesn't undo the commit.
+    let _ = run_hook(&repo, repo_path, "post-commit", &[], emit);
+
+    Ok(oid.to_string())
+}
+
+#[tauri::command(async)]
+pub fn git_commit(
+    app: AppHandle,
+    repo_path: String,
+    message: String,
+    options: Option<CommitOptions>,
+) -> Result<String, CommitError> {
+    journaled(&repo_path, "commit", || {
+        create_commit(
+            &repo_path,
+            message,
+            options.unwrap_or_default(),
+            &|output| {
+                let _ = app.emit(HOOK_OUTPUT_EVENT, output);
+            },
+        )
+    })
+}
+
+#[derive(Clone, Serialize)]
+pub struct RemoteProgress {
+    pub repo_path: String,
+    pub operation: String,
+    pub stage: String,
+    pub current: usize,
+    pub total: usize,
+    pub bytes: usize,
+    pub message: Option<String>,
+}
+
+const REMOTE_PROGRESS_EVENT: &str = "git-remote-progress";
+const REMOTE_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+struct ProgressReporter<'a> {
+    repo_path: &'a str,
+    operation: &'a str,
+    emit: &'a dyn Fn(RemoteProgress),
+    last: std::cell::Cell<Option<std::time::Instant>>,
+}
+
+impl<'a> ProgressReporter<'a> {
+    fn new(repo_path: &'a str, operation: &'a str, emit: &'a dyn Fn(RemoteProgress)) -> Self {
+        Self {
+            repo_path,
+            operation,
+            emit,
+            last: std::cell::Cell::new(None),
+        }
+    }
+
+    fn counter(&self, stage: &str, current: usize, total: usize, bytes: usize) {
+        let now = std::time::Instant::now();
+        let due = self
+            .last
+            .get()
+            .is_none_or(|last| now.duration_since(last) >= REMOTE_PROGRESS_INTERVAL);
+        if !due && current < total {
+            return;
+        }
+        self.last.set(Some(now));
+        self.send(stage, current, total, bytes, None);
+    }
+
+    fn message(&self, stage: &str, message: String) {
+        self.send(stage, 0, 0, 0, Some(message));
+    }
+
+    fn send(
+        &self,
+        stage: &str,
+        current: usize,
+        total: usize,
+        bytes: usize,
+        message: Option<String>,
+    ) {
+        (self.emit)(RemoteProgress {
+            repo_path: self.repo_path.to_string(),
+            operation: self.operation.to_string(),
+            stage: stage.to_string(),
+            current,
+            total,
+            bytes,
+            message,
+        });
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct RefUpdate {
+    pub refname: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+    pub message: Option<String>,
+}
+
+fn non_zero(oid: git2::Oid) -> Option<String> {
+    (!oid.is_zero()).then(|| oid.to_string())
+}
+
+/// Each credential method is offered once so a rejected credential fails
+/// instead of looping.
+fn credentials_callback(
+    config: git2::Config,
+    token: Option<String>,
+) -> impl FnMut(&str, Option<&str>, git2::CredentialType) -> Result<git2::Cred, git2::Error> {
+    use git2::CredentialType;
+
+    let mut tried = CredentialType::empty();
+    move |url, username_from_url, allowed| {
+        if allowed.contains(CredentialType::USERNAME) {
+            return git2::Cred::username(username_from_url.unwrap_or("git"));
+        }
+        if allowed.contains(CredentialType::SSH_KEY) && !tried.contains(CredentialType::SSH_KEY) {
+            tried |= CredentialType::SSH_KEY;
+            return git2::Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"));
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT)
+            && !tried.contains(CredentialType::USER_PASS_PLAINTEXT)
+        {
+            tried |= CredentialType::USER_PASS_PLAINTEXT;
+            return match &token {
+                Some(token) => git2::Cred::userpass_plaintext(
+                    username_from_url.unwrap_or("x-access-token"),
+                    token,
+                ),
+                None => git2::Cred::credential_helper(&config, url, username_from_url),
+            };
+        }
+        if allowed.contains(CredentialType::DEFAULT) && !tried.contains(CredentialType::DEFAULT) {
+            tried |= CredentialType::DEFAULT;
+            return git2::Cred::default();
+        }
+        Err(git2::Error::from_str(&format!(
+            "authentication failed for {url}"
+        )))
+    }
+}
+
+fn remote_callbacks<'a>(
+    repo: &Repository,
+    token: Option<&str>,
+    reporter: &'a ProgressReporter<'a>,
+) -> Result<git2::RemoteCallbacks<'a>, String> {
+    let config = repo.config().map_err(|e| e.message().to_string())?;
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks.credentials(credentials_callback(config, token.map(|t| t.to_string())));
+    callbacks.transfer_progress(move |stats| {
+        if stats.received_objects() < stats.total_objects() || stats.total_deltas() == 0 {
+            reporter.counter(
+                "receiving",
+                stats.received_objects(),
+                stats.total_objects(),
+                stats.received_bytes(),
+            );
+        } else {
+            reporter.counter(
+                "resolving",
+                stats.indexed_deltas(),
+                stats.total_deltas(),
+                stats.received_bytes(),
+            );
+        }
+        true
+    });
+    callbacks.sideband_progress(move |data| {
+        let text = String::from_utf8_lossy(data).trim().to_string();
+        if !text.is_empty() {
+            reporter.message("remote", text);
+        }
+        true
+    });
+    callbacks.pack_progress(move |_stage, current, total| {
+        reporter.counter("packing", current, total, 0);
+    });
+    callbacks.push_transfer_progress(move |current, total, bytes| {
+        reporter.counter("sending", current, total, bytes);
+    });
+    Ok(callbacks)
+}
+
+fn current_branch_name(repo: &Repository) -> Result<String, String> {
+    let head = repo.head().map_err(|e| e.message().to_string())?;
+    if !head.is_branch() {
+        return Err("HEAD is detached".to_string());
+    }
+    head.shorthand()
+        .map(|s| s.to_string())
+        .ok_or_else(|| "invalid branch name encoding".to_string())
+}
+
+fn config_string(repo: &Repository, key: &str) -> Option<String> {
+    repo.config().ok()?.get_string(key).ok()
+}
+
+fn resolve_remote_name(repo: &Repository, remote: Option<&str>) -> String {
+    if let Some(remote) = remote {
+        return remote.to_string();
+    }
+    current_branch_name(repo)
+        .ok()
+        .and_then(|branch| config_string(repo, &format!("branch.{branch}.remote")))
+        .unwrap_or_else(|| "origin".to_string())
+}
+
+fn tracking_ref_for(remote: &git2::Remote<'_>, refname: &str) -> Option<String> {
+    remote
+        .refspecs()
+        .filter(|spec| spec.direction() == git2::Direction::Fetch)
+        .find(|spec| spec.src_matches(refname))
+        .and_then(|spec| spec.transform(refname).ok())
+        .and_then(|buf| buf.as_str().map(|s| s.to_string()))
+}
+
+#[derive(Serialize)]
+pub struct PushResult {
+    pub remote: String,
+    pub refspecs: Vec<String>,
+    pub updated: Vec<RefUpdate>,
+}
+
+fn push_with_progress(
+    repo_path: &str,
+    remote: Option<&str>,
+    refspec: Option<&str>,
+    force_with_lease: bool,
+    token: Option<&str>,
+    emit: &dyn Fn(RemoteProgress),
+) -> Result<PushResult, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let remote_name = resolve_remote_name(&repo, remote);
+    let mut git_remote = repo
+        .find_remote(&remote_name)
+        .map_err(|e| e.message().to_string())?;
+
+    // Without an explicit refspec, push the current branch to its upstream
+    // (or a same-named branch) and record that as upstream, like `push -u`.
+    let mut set_upstream = None;
+    let refspec = match refspec {
+        Some(spec) => spec.to_string(),
+        None => {
+            let branch =
+                current_branch_name(&repo).map_err(|e| format!("{e}; pass a refspec to push"))?;
+            let merge = config_string(&repo, &format!("branch.{branch}.merge"));
+            let dst = merge
+                .clone()
+                .unwrap_or_else(|| format!("refs/heads/{branch}"));
+            if merge.is_none() {
+                set_upstream = Some((branch.clone(), dst.clone()));
+            }
+            format!("refs/heads/{branch}:{dst}")
+        }
+    };
+    let unforced = refspec.trim_start_matches('+');
+    let dst = unforced
+        .split_once(':')
+        .map_or(unforced, |(_, dst)| dst)
+        .to_string();
+
+    // libgit2 has no server-side lease; check the remote's current value
+    // against our remote-tracking ref during negotiation instead.
+    let lease = if force_with_lease {
+        let expected = tracking_ref_for(&git_remote, &dst)
+            .and_then(|tracking| repo.refname_to_id(&tracking).ok())
+            .unwrap_or_else(git2::Oid::zero);
+        Some((dst.clone(), expected))
+    } else {
+        None
+    };
+    let refspecs = vec![if force_with_lease && !refspec.starts_with('+') {
+        format!("+{refspec}")
+    } else {
+        refspec.clone()
+    }];
+
+    let reporter = ProgressReporter::new(repo_path, "push", emit);
+    let negotiated = std::cell::RefCell::new(HashMap::<String, (git2::Oid, git2::Oid)>::new());
+    let updated = std::cell::RefCell::new(Vec::new());
+    let rejected = std::cell::RefCell::new(Vec::new());
+
+    let mut callbacks = remote_callbacks(&repo, token, &reporter)?;
+    callbacks.push_negotiation(|updates| {
+        for update in updates {
+            let Some(dst_ref) = update.dst_refname() else {
+                continue;
+            };
+            if let Some((lease_ref, expected)) = &lease {
+                if lease_ref == dst_ref && update.src() != *expected {
+                    return Err(git2::Error::from_str(&format!(
+                        "stale info: {dst_ref} on the remote is not at the expected commit"
+                    )));
+                }
+            }
+            negotiated
+                .borrow_mut()
+                .insert(dst_ref.to_string(), (update.src(), update.dst()));
+        }
+        Ok(())
+    });
+    callbacks.push_update_reference(|refname, status| {
+        let (old, new) = negotiated
+            .borrow()
+            .get(refname)
+            .copied()
+            .unwrap_or((git2::Oid::zero(), git2::Oid::zero()));
+        let update = RefUpdate {
+            refname: refname.to_string(),
+            old: non_zero(old),
+            new: non_zero(new),
+            message: status.map(|s| s.to_string()),
+        };
+        match status {
+            Some(reason) => {
+                reporter.message("ref_rejected", format!("{refname}: {reason}"));
+                rejected.borrow_mut().push(update);
+            }
+            None => {
+                reporter.message("ref_updated", refname.to_string());
+                updated.borrow_mut().push(update);
+            }
+        }
+        Ok(())
+    });
+
+    let mut opts = git2::PushOptions::new();
+    opts.remote_callbacks(callbacks);
+    git_remote
+        .push(&refspecs, Some(&mut opts))
+        .map_err(|e| e.message().to_string())?;
+    drop(opts);
+
+    let rejected = rejected.into_inner();
+    if !rejected.is_empty() {
+        let reasons: Vec<String> = rejected
+            .iter()
+            .map(|r| {
+                format!(
+                    "{}: {}",
+                    r.refname,
+                    r.message.as_deref().unwrap_or("rejected")
+                )
+            })
+            .collect();
+        return Err(format!("push rejected: {}", reasons.join("; ")));
+    }
+
+    if let Some((branch, merge)) = set_upstream {
+        let mut config = repo.config().map_err(|e| e.message().to_string())?;
+        config
+            .set_str(&format!("branch.{branch}.remote"), &remote_name)
+            .map_err(|e| e.message().to_string())?;
+        config
+            .set_str(&format!("branch.{branch}.merge"), &merge)
+            .map_err(|e| e.message().to_string())?;
+    }
+
+    Ok(PushResult {
+        remote: remote_name,
+        refspecs,
+        updated: updated.into_inner(),
+    })
+}
+
+#[tauri::command(async)]
+pub fn git_push(
+    app: AppHandle,
+    repo_path: String,
+    remote: Option<String>,
+    refspec: Option<String>,
+    force_with_lease: Option<bool>,
+    token: Option<String>,
+) -> Result<PushResult, String> {
+    push_with_progress(
+        &repo_path,
+        remote.as_deref(),
+        refspec.as_deref(),
+        force_with_lease.unwrap_or(false),
+        token.as_deref(),
+        &|progress| {
+            let _ = app.emit(REMOTE_PROGRESS_EVENT, progress);
+        },
+    )
+}
+
+#[derive(Serialize)]
+pub struct FetchResult {
+    pub remote: String,
+    pub received_objects: usize,
+    pub received_bytes: usize,
+    pub updated: Vec<RefUpdate>,
+}
+
+fn fetch_with_progress(
+    repo: &Repository,
+    remote_name: &str,
+    refspecs: &[String],
+    prune: bool,
+    token: Option<&str>,
+    reporter: &ProgressReporter<'_>,
+) -> Result<FetchResult, String> {
+    let mut git_remote = repo
+        .find_remote(remote_name)
+        .map_err(|e| e.message().to_string())?;
+
+    let updated = std::cell::RefCell::new(Vec::new());
+
+    let mut callbacks = remote_callbacks(repo, token, reporter)?;
+    callbacks.update_tips(|refname, old, new| {
+        reporter.message("ref_updated", refname.to_string());
+        updated.borrow_mut().push(RefUpdate {
+            refname: refname.to_string(),
+            old: non_zero(old),
+            new: non_zero(new),
+            message: None,
+        });
+        true
+    });
+
+    let mut opts = git2::FetchOptions::new();
+    opts.remote_callbacks(callbacks);
+    if prune {
+        opts.prune(git2::FetchPrune::On);
+    }
+    git_remote
+        .fetch(refspecs, Some(&mut opts), None)
+        .map_err(|e| e.message().to_string())?;
+    drop(opts);
+
+    let stats = git_remote.stats();
+    Ok(FetchResult {
+        remote: remote_name.to_string(),
+        received_objects: stats.received_objects(),
+        received_bytes: stats.received_bytes(),
+        updated: updated.into_inner(),
+    })
+}
+
+#[tauri::command(async)]
+pub fn git_fetch(
+    app: AppHandle,
+    repo_path: String,
+    remote: Option<String>,
+    refspecs: Option<Vec<String>>,
+    prune: Option<bool>,
+    token: Option<String>,
+) -> Result<FetchResult, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let remote_name = resolve_remote_name(&repo, remote.as_deref());
+    let emit = |progress| {
+        let _ = app.emit(REMOTE_PROGRESS_EVENT, progress);
+    };
+    fetch_with_progress(
+        &repo,
+        &remote_name,
+        &refspecs.unwrap_or_default(),
+        prune.unwrap_or(false),
+        token.as_deref(),
+        &ProgressReporter::new(&repo_path, "fetch", &emit),
+    )
+}
+
+#[derive(Serialize)]
+pub struct PullResult {
+    pub fetch: FetchResult,
+    pub outcome: String,
+    pub head: Option<String>,
+}
+
+fn rebase_onto(repo: &Repository, upstream: &git2::AnnotatedCommit<'_>) -> Result<(), String> {
+    let sig = repo.signature().map_err(|e| e.message().to_string())?;
+    let mut rebase = repo
+        .rebase(None, Some(upstream), None, None)
+        .map_err(|e| e.message().to_string())?;
+
+    while let Some(op) = rebase.next() {
+        let step = op.map_err(|e| e.message().to_string()).and_then(|_| {
+            let index = repo.index().map_err(|e| e.message().to_string())?;
+            if index.has_conflicts() {
+                let paths: Vec<String> = index
+                    .conflicts()
+                    .map_err(|e| e.message().to_string())?
+                    .flatten()
+                    .filter_map(|c| c.our.or(c.their).or(c.ancestor))
+                    .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+                    .collect();
+                return Err(format!("rebase conflicts in: {}", paths.join(", ")));
+            }
+            match rebase.commit(None, &sig, None) {
+                Ok(_) => Ok(()),
+                // The commit's changes are already upstream.
+                Err(e) if e.code() == ErrorCode::Applied => Ok(()),
+                Err(e) => Err(e.message().to_string()),
+            }
+        });
+        if let Err(e) = step {
+            let _ = rebase.abort();
+            return Err(e);
+        }
+    }
+
+    rebase
+        .finish(Some(&sig))
+        .map_err(|e| e.message().to_string())
+}
+
+fn pull_with_progress(
+    repo_path: &str,
+    remote: Option<&str>,
+    branch: Option<&str>,
+    rebase: bool,
+    token: Option<&str>,
+    emit: &dyn Fn(RemoteProgress),
+) -> Result<PullResult, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let local_branch = current_branch_name(&repo).map_err(|e| format!("cannot pull: {e}"))?;
+    let remote_name = resolve_remote_name(&repo, remote);
+
+    let reporter = ProgressReporter::new(repo_path, "pull", emit);
+    let fetch = fetch_with_progress(&repo, &remote_name, &[], false, token, &reporter)?;
+
+    let remote_ref = match branch {
+        Some(branch) => format!("refs/heads/{branch}"),
+        None => config_string(&repo, &format!("branch.{local_branch}.merge"))
+            .unwrap_or_else(|| format!("refs/heads/{local_branch}")),
+    };
+    let git_remote = repo
+        .find_remote(&remote_name)
+        .map_err(|e| e.message().to_string())?;
+    let tracking = tracking_ref_for(&git_remote, &remote_ref)
+        .ok_or_else(|| format!("{remote_ref} is not fetched from {remote_name}"))?;
+    let upstream_ref = repo
+        .find_reference(&tracking)
+        .map_err(|e| e.message().to_string())?;
+    let upstream = repo
+        .reference_to_annotated_commit(&upstream_ref)
+        .map_err(|e| e.message().to_string())?;
+
+    let touched = |repo: &Repository| checkout_paths(repo, &tracking);
+    let outcome = journaled_worktree(repo_path, "pull", touched, || {
+        integrate_upstream(&repo, &local_branch, &tracking, &upstream, rebase)
+    })?;
+
+    Ok(PullResult {
+        fetch,
+        outcome: outcome.to_string(),
+        head: repo
+            .head()
+            .ok()
+            .and_then(|h| h.target())
+            .map(|oid| oid.to_string()),
+    })
+}
+
+fn integrate_upstream(
+    repo: &Repository,
+    local_branch: &str,
+    tracking: &str,
+    upstream: &git2::AnnotatedCommit<'_>,
+    rebase: bool,
+) -> Result<&'static str, String> {
+    let (analysis, _) = repo
+        .merge_analysis(&[upstream])
+        .map_err(|e| e.message().to_string())?;
+
+    let outcome = if analysis.is_up_to_date() {
+        "up_to_date"
+    } else if analysis.is_fast_forward() || analysis.is_unborn() {
+        let target = repo
+            .find_object(upstream.id(), None)
+            .map_err(|e| e.message().to_string())?;
+        repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))
+            .map_err(|e| e.message().to_string())?;
+        repo.reference(
+            &format!("refs/heads/{local_branch}"),
+            upstream.id(),
+            true,
+            &format!("pull: fast-forward to {tracking}"),
+        )
+        .map_err(|e| e.message().to_string())?;
+        "fast_forward"
+    } else if rebase {
+        rebase_onto(repo, upstream)?;
+        "rebased"
+    } else {
+        return Err(format!(
+            "cannot fast-forward {local_branch} to {tracking}: branches have diverged; pull with rebase"
+        ));
+    };
+    Ok(outcome)
+}
+
+#[tauri::command(async)]
+pub fn git_pull(
+    app: AppHandle,
+    repo_path: String,
+    remote: Option<String>,
+    branch: Option<String>,
+    rebase: Option<bool>,
+    token: Option<String>,
+) -> Result<PullResult, String> {
+    pull_with_progress(
+        &repo_path,
+        remote.as_deref(),
+        branch.as_deref(),
+        rebase.unwrap_or(false),
+        token.as_deref(),
+        &|progress| {
+            let _ = app.emit(REMOTE_PROGRESS_EVENT, progress);
+        },
+    )
+}
+
+#[derive(Debug, Serialize)]
+pub struct HunkApplyError {
+    pub reason: String,
+    pub path: Option<String>,
+    pub hunk_index: Option<usize>,
+    pub hunk_header: Option<String>,
+    pub message: String,
+}
+
+impl HunkApplyError {
+    fn other(message: impl Into<String>) -> Self {
+        HunkApplyError {
+            reason: "other".to_string(),
+            path: None,
+            hunk_index: None,
+            hunk_header: None,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ApplyProgress {
+    path: Option<String>,
+    binary: bool,
+    hunk_index: Option<usize>,
+    hunk_header: Option<String>,
+}
+
+fn strip_patch_path(raw: &str) -> Option<&str> {
+    let raw = raw.split('\t').next().unwrap_or(raw).trim_end();
+    if raw == "/dev/null" {
+        return None;
+    }
+    Some(
+        raw.strip_prefix("a/")
+            .or_else(|| raw.strip_prefix("b/"))
+            .unwrap_or(raw),
+    )
+}
+
+/// Hunk patches carry file content verbatim, which need not be UTF-8. Mapping
+/// each byte to one char lets the text helpers below rewrite them losslessly.
+fn patch_bytes_to_text(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| char::from(b)).collect()
+}
+
+fn patch_text_to_bytes(text: &str) -> Vec<u8> {
+    text.chars().map(|c| c as u8).collect()
+}
+
+fn decode_patch(patch_base64: &str) -> Result<Vec<u8>, HunkApplyError> {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD
+        .decode(patch_base64)
+        .map_err(|e| invalid_patch(format!("patch is not valid base64: {e}")))
+}
+
+/// libgit2 only parses patches that carry a `diff --git` header, while the
+/// frontend sends bare `---`/`+++` patches. Synthesize the header where missing.
+fn normalize_patch(patch: &str) -> String {
+    let lines: Vec<&str> = patch.split_terminator('\n').collect();
+    let mut out = String::with_capacity(patch.len() + 64);
+    let mut in_git_header = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.starts_with("diff --git ") {
+            in_git_header = true;
+        } else if line.starts_with("@@") {
+            in_git_header = false;
+        } else if let Some(old) = line.strip_prefix("--- ") {
+            let new = lines.get(i + 1).and_then(|l| l.strip_prefix("+++ "));
+            if let (false, Some(new)) = (in_git_header, new) {
+                let old_path = strip_patch_path(old);
+                let new_path = strip_patch_path(new);
+                if let Some(fallback) = old_path.or(new_path) {
+                    let old_path = old_path.unwrap_or(fallback);
+                    let new_path = new_path.unwrap_or(fallback);
+                    out.push_str(&format!("diff --git a/{old_path} b/{new_path}\n"));
+                }
+            }
+            in_git_header = false;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    out
+}
+
+fn parse_hunk_range(range: &str) -> Option<(u32, u32)> {
+    match range.split_once(',') {
+        Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+struct HunkHeader<'a> {
+    old_start: u32,
+    old_lines: u32,
+    new_start: u32,
+    new_lines: u32,
+    section: &'a str,
+}
+
+fn parse_hunk_header(line: &str) -> Option<HunkHeader<'_>> {
+    let rest = line.strip_prefix("@@ -")?;
+    let (ranges, section) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let (old_start, old_lines) = parse_hunk_range(old)?;
+    let (new_start, new_lines) = parse_hunk_range(new)?;
+    Some(HunkHeader {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        section,
+    })
+}
+
+fn swap_patch_prefix(path: &str, from: &str, to: &str) -> String {
+    match path.strip_prefix(from) {
+        Some(rest) => format!("{to}{rest}"),
+        None => path.to_string(),
+    }
+}
+
+fn flush_reversed_run(out: &mut Vec<String>, run: &mut Vec<(char, Vec<String>)>) {
+    for origin in ['-', '+'] {
+        for (o, lines) in run.iter() {
+            if *o == origin {
+                out.extend(lines.iter().cloned());
+            }
+        }
+    }
+    run.clear();
+}
+
+fn reverse_patch(patch: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut run: Vec<(char, Vec<String>)> = Vec::new();
+    let mut old_remaining = 0u32;
+    let mut new_remaining = 0u32;
+    let mut lines = patch.split_terminator('\n').peekable();
+
+    while let Some(line) = lines.next() {
+        if old_remaining > 0 || new_remaining > 0 {
+            let (origin, body) = match line.chars().next() {
+                Some(c @ ('+' | '-' | ' ')) => (c, &line[1..]),
+                None => (' ', ""),
+                Some(_) => {
+                    flush_reversed_run(&mut out, &mut run);
+                    out.push(line.to_string());
+                    continue;
+                }
+            };
+            match origin {
+                '+' => new_remaining = new_remaining.saturating_sub(1),
+                '-' => old_remaining = old_remaining.saturating_sub(1),
+                _ => {
+                    old_remaining = old_remaining.saturating_sub(1);
+                    new_remaining = new_remaining.saturating_sub(1);
+                }
+            }
+
+            let flipped = match origin {
+                '+' => '-',
+                '-' => '+',
+                _ => ' ',
+            };
+            let mut entry = vec![format!("{flipped}{body}")];
+            if lines.peek().is_some_and(|next| next.starts_with('\\')) {
+                entry.extend(lines.next().map(str::to_string));
+            }
+            if flipped == ' ' {
+                flush_reversed_run(&mut out, &mut run);
+                out.extend(entry);
+            } else {
+                run.push((flipped, entry));
+            }
+            if old_remaining == 0 && new_remaining == 0 {
+                flush_reversed_run(&mut out, &mut run);
+            }
+            continue;
+        }
+
+        if let Some(h) = parse_hunk_header(line) {
+            old_remaining = h.old_lines;
+            new_remaining = h.new_lines;
+            out.push(format!(
+                "@@ -{},{} +{},{} @@{}",
+                h.new_start, h.new_lines, h.old_start, h.old_lines, h.section
+            ));
+        } else if let Some(rest) = line.strip_prefix("diff --git ") {
+            match rest.split_once(" b/") {
+                Some((old, new)) => {
+                    let old = old.strip_prefix("a/").unwrap_or(old);
+                    out.push(format!("diff --git a/{new} b/{old}"));
+                }
+                None => out.push(line.to_string()),
+            }
+        } else if let Some(old) = line.strip_prefix("--- ") {
+            match lines.peek().and_then(|next| next.strip_prefix("+++ ")) {
+                Some(new) => {
+                    out.push(format!("--- {}", swap_patch_prefix(new, "b/", "a/")));
+                    out.push(format!("+++ {}", swap_patch_prefix(old, "a/", "b/")));
+                    lines.next();
+                }
+                None => out.push(line.to_string()),
+            }
+        } else if let Some(mode) = line.strip_prefix("new file mode ") {
+            out.push(format!("deleted file mode {mode}"));
+        } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+            out.push(format!("new file mode {mode}"));
+        } else if let Some(mode) = line.strip_prefix("old mode ") {
+            out.push(format!("new mode {mode}"));
+        } else if let Some(mode) = line.strip_prefix("new mode ") {
+            out.push(format!("old mode {mode}"));
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            out.push(format!("rename to {path}"));
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            out.push(format!("rename from {path}"));
+        } else if let Some(rest) = line.strip_prefix("index ") {
+            let (ids, mode) = rest.split_once(' ').unwrap_or((rest, ""));
+            match ids.split_once("..") {
+                Some((from, to)) if mode.is_empty() => out.push(format!("index {to}..{from}")),
+                Some((from, to)) => out.push(format!("index {to}..{from} {mode}")),
+                None => out.push(line.to_string()),
+            }
+        } else {
+            out.push(line.to_string());
+        }
+    }
+    flush_reversed_run(&mut out, &mut run);
+
+    let mut result = out.join("\n");
+    result.push('\n');
+    result
+}
+
+/// libgit2 places each hunk at `new_start - 1` in the partly patched file,
+/// which only holds when the patch carries every hunk above it. A hunk picked
+/// out of a larger diff is offset by the ones left behind, so recompute
+/// `new_start` from `old_start` and the hunks this patch actually applies.
+fn rebase_hunk_positions(patch: &str) -> String {
+    let mut out = String::with_capacity(patch.len());
+    let mut offset = 0i64;
+    for line in patch.split_terminator('\n') {
+        if line.starts_with("diff --git ") {
+            offset = 0;
+        }
+        match parse_hunk_header(line) {
+            Some(h) => {
+                // An empty side names the line before the change.
+                let before = if h.old_lines == 0 {
+                    h.old_start
+                } else {
+                    h.old_start.saturating_sub(1)
+                };
+                let new_start = i64::from(before) + offset + 1;
+                offset += i64::from(h.new_lines) - i64::from(h.old_lines);
+                out.push_str(&format!(
+                    "@@ -{},{} +{},{} @@{}",
+                    h.old_start, h.old_lines, new_start, h.new_lines, h.section
+                ));
+            }
+            None => out.push_str(line),
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn classify_apply_error(
+    e: &git2::Error,
+    progress: &ApplyProgress,
+    repo: &Repository,
+    location: git2::ApplyLocation,
+) -> HunkApplyError {
+    let missing_file = progress.hunk_index.is_none()
+        && (e.code() == ErrorCode::NotFound
+            || e.message().contains("does not contain")
+            || e.message().contains("does not exist")
+            || progress.path.as_deref().is_some_and(|p| {
+                let in_index = repo
+                    .index()
+                    .map(|idx| idx.get_path(Path::new(p), 0).is_some())
+                    .unwrap_or(false);
+                let in_workdir = repo.workdir().is_some_and(|w| w.join(p).exists());
+                match location {
+                    git2::ApplyLocation::Index => !in_index,
+                    git2::ApplyLocation::WorkDir => !in_workdir,
+                    git2::ApplyLocation::Both => !in_index || !in_workdir,
+                }
+            }));
+
+    let reason = if progress.binary {
+        "binary"
+    } else if missing_file {
+        "missing_file"
+    } else if progress.hunk_index.is_some() {
+        "context_mismatch"
+    } else {
+        "other"
+    };
+
+    HunkApplyError {
+        reason: reason.to_string(),
+        path: progress.path.clone(),
+        hunk_index: progress.hunk_index,
+        hunk_header: progress.hunk_header.clone(),
+        message: e.message().to_string(),
+    }
+}
+
+fn apply_patch(
+    repo: &Repository,
+    patch: &[u8],
+    location: git2::ApplyLocation,
+    reverse: bool,
+) -> Result<(), HunkApplyError> {
+    let normalized = normalize_patch(&patch_bytes_to_text(patch));
+    let text = rebase_hunk_positions(&if reverse {
+        reverse_patch(&normalized)
+    } else {
+        normalized
+    });
+
+    let diff =
+        git2::Diff::from_buffer(&patch_text_to_bytes(&text)).map_err(|e| HunkApplyError {
+            reason: "invalid_patch".to_string(),
+            path: None,
+            hunk_index: None,
+            hunk_header: None,
+            message: e.message().to_string(),
+        })?;
+
+    if diff.deltas().len() == 0 {
+        return Err(HunkApplyError {
+            reason: "invalid_patch".to_string(),
+            path: None,
+            hunk_index: None,
+            hunk_header: None,
+            message: "patch contains no file changes".to_string(),
+        });
+    }
+
+    let progress = std::cell::RefCell::new(ApplyProgress::default());
+    let mut opts = git2::ApplyOptions::new();
+    opts.delta_callback(|delta| {
+        let mut p = progress.borrow_mut();
+        *p = ApplyProgress::default();
+        if let Some(delta) = delta {
+            p.binary = delta.flags().is_binary();
+            p.path = delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())
+                .map(|path| path.to_string_lossy().to_string());
+        }
+        true
+    });
+    opts.hunk_callback(|hunk| {
+        let mut p = progress.borrow_mut();
+        p.hunk_index = Some(p.hunk_index.map_or(0, |i| i + 1));
+        p.hunk_header = hunk.map(|h| String::from_utf8_lossy(h.header()).trim_end().to_string());
+        true
+    });
+
+    let result = repo.apply(&diff, location, Some(&mut opts));
+    drop(opts);
+    result.map_err(|e| classify_apply_error(&e, &progress.borrow(), repo, location))
+}
+
+#[tauri::command]
+pub fn git_stage_hunk(repo_path: String, patch_base64: String) -> Result<(), HunkApplyError> {
+    let patch = decode_patch(&patch_base64)?;
+    journaled(&repo_path, "stage_hunk", || stage_hunk(&repo_path, &patch))
+}
+
+fn stage_hunk(repo_path: &str, patch: &[u8]) -> Result<(), HunkApplyError> {
+    let repo = Repository::open(repo_path).map_err(|e| HunkApplyError::other(e.message()))?;
+    apply_patch(&repo, patch, git2::ApplyLocation::Index, false)
+}
+
+#[tauri::command]
+pub fn git_unstage_hunk(repo_path: String, patch_base64: String) -> Result<(), HunkApplyError> {
+    let patch = decode_patch(&patch_base64)?;
+    journaled(&repo_path, "unstage_hunk", || {
+        unstage_hunk(&repo_path, &patch)
+    })
+}
+
+fn unstage_hunk(repo_path: &str, patch: &[u8]) -> Result<(), HunkApplyError> {
+    let repo = Repository::open(repo_path).map_err(|e| HunkApplyError::other(e.message()))?;
+    apply_patch(&repo, patch, git2::ApplyLocation::Index, true)
+}
+
+#[derive(Serialize)]
+pub struct DiffLineInfo {
+    pub origin: String,
+    pub content: String,
+    pub old_lineno: Option<u32>,
+    pub new_lineno: Option<u32>,
+    pub no_newline_at_eof: bool,
+}
+
+#[derive(Serialize)]
+pub struct DiffHunkInfo {
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLineInfo>,
+    pub patch_base64: String,
+}
+
+#[derive(Serialize)]
+pub struct FileDiff {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub status: String,
+    pub similarity: Option<u32>,
+    pub binary: bool,
+    pub additions: usize,
+    pub deletions: usize,
+    pub hunks: Vec<DiffHunkInfo>,
+}
+
+fn delta_status_str(status: git2::Delta) -> &'static str {
+    match status {
+        git2::Delta::Added | git2::Delta::Untracked => "new",
+        git2::Delta::Deleted => "deleted",
+        git2::Delta::Renamed => "renamed",
+        git2::Delta::Copied => "copied",
+        git2::Delta::Typechange => "typechange",
+        git2::Delta::Conflicted => "conflicted",
+        _ => "modified",
+    }
+}
+
+fn delta_path(file: git2::DiffFile<'_>) -> Option<String> {
+    file.path().map(|p| p.to_string_lossy().replace('\\', "/"))
+}
+
+fn patch_file_header(delta: &git2::DiffDelta<'_>, similarity: Option<u32>) -> String {
+    let old_path = delta_path(delta.old_file());
+    let new_path = delta_path(delta.new_file());
+    let old = old_path
+        .clone()
+        .or_else(|| new_path.clone())
+        .unwrap_or_default();
+    let new = new_path.or(old_path).unwrap_or_default();
+
+    let mut header = format!("diff --git a/{old} b/{new}\n");
+    match delta.status() {
+        git2::Delta::Renamed | git2::Delta::Copied => {
+            let kind = if delta.status() == git2::Delta::Renamed {
+                "rename"
+            } else {
+                "copy"
+            };
+            // libgit2's parser insists on an `index` line after rename/copy headers.
+            header.push_str(&format!(
+                "similarity index {}%\n{kind} from {old}\n{kind} to {new}\n",
+                similarity.unwrap_or(100)
+            ));
+            header.push_str(&format!(
+                "index {:.7}..{:.7} {:o}\n--- a/{old}\n+++ b/{new}\n",
+                delta.old_file().id().to_string(),
+                delta.new_file().id().to_string(),
+                u32::from(delta.new_file().mode())
+            ));
+        }
+        git2::Delta::Added | git2::Delta::Untracked => {
+            header.push_str(&format!(
+                "new file mode {:o}\n--- /dev/null\n+++ b/{new}\n",
+                u32::from(delta.new_file().mode())
+            ));
+        }
+        git2::Delta::Deleted => {
+            header.push_str(&format!(
+                "deleted file mode {:o}\n--- a/{old}\n+++ /dev/null\n",
+                u32::from(delta.old_file().mode())
+            ));
+        }
+        _ => header.push_str(&format!("--- a/{old}\n+++ b/{new}\n")),
+    }
+    header
+}
+
+fn line_origin_str(origin: char) -> Option<&'static str> {
+    match origin {
+        ' ' => Some("context"),
+        '+' => Some("addition"),
+        '-' => Some("deletion"),
+        _ => None,
+    }
+}
+
+fn file_diffs_from_diff(diff: &git2::Diff<'_>) -> Result<Vec<FileDiff>, String> {
+    let similarities = similarity_by_path(diff)?;
+    let mut files = Vec::new();
+
+    for idx in 0..diff.deltas().len() {
+        let Some(patch) = git2::Patch::from_diff(diff, idx).map_err(|e| e.message().to_string())?
+        else {
+            continue;
+        };
+        let delta = patch.delta();
+        let similarity = match delta.status() {
+            git2::Delta::Renamed | git2::Delta::Copied => {
+                delta_path(delta.new_file()).and_then(|p| similarities.get(&p).copied())
+            }
+            _ => None,
+        };
+        let file_header = patch_file_header(&delta, similarity);
+        let mut file = FileDiff {
+            old_path: delta_path(delta.old_file()),
+            new_path: delta_path(delta.new_file()),
+            status: delta_status_str(delta.status()).to_string(),
+            similarity,
+            binary: delta.flags().is_binary(),
+            additions: 0,
+            deletions: 0,
+            hunks: Vec::new(),
+        };
+
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(hunk_idx).map_err(|e| e.message().to_string())?;
+            let header = String::from_utf8_lossy(hunk.header())
+                .trim_end()
+                .to_string();
+            let mut info = DiffHunkInfo {
+                header: header.clone(),
+                old_start: hunk.old_start(),
+                old_lines: hunk.old_lines(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new_lines(),
+                lines: Vec::with_capacity(line_count),
+                patch_base64: String::new(),
+            };
+            let mut hunk_patch = format!("{file_header}{header}\n").into_bytes();
+
+            for line_idx in 0..line_count {
+                let line = patch
+                    .line_in_hunk(hunk_idx, line_idx)
+                    .map_err(|e| e.message().to_string())?;
+                let Some(origin) = line_origin_str(line.origin()) else {
+                    // '=', '>' and '<' mark a missing newline on the previous line.
+                    if let Some(prev) = info.lines.last_mut() {
+                        prev.no_newline_at_eof = true;
+                        hunk_patch.extend_from_slice(b"\\ No newline at end of file\n");
+                    }
+                    continue;
+                };
+                let raw = String::from_utf8_lossy(line.content());
+                let content = raw.strip_suffix('\n').unwrap_or(&raw).to_string();
+                match line.origin() {
+                    '+' => file.additions += 1,
+                    '-' => file.deletions += 1,
+                    _ => {}
+                }
+                let bytes = line.content();
+                hunk_patch.push(line.origin() as u8);
+                hunk_patch.extend_from_slice(bytes.strip_suffix(b"\n").unwrap_or(bytes));
+                hunk_patch.push(b'\n');
+                info.lines.push(DiffLineInfo {
+                    origin: origin.to_string(),
+                    content,
+                    old_lineno: line.old_lineno(),
+                    new_lineno: line.new_lineno(),
+                    no_newline_at_eof: false,
+                });
+            }
+
+            info.patch_base64 = {
+                use base64::Engine;
+                base64::engine::general_purpose::STANDARD.encode(&hunk_patch)
+            };
+            file.hunks.push(info);
+        }
+
+        files.push(file);
+    }
+
+    Ok(files)
+}
+
+fn head_tree(repo: &Repository) -> Result<Option<git2::Tree<'_>>, String> {
+    match repo.head() {
+        Ok(head) => head
+            .peel_to_tree()
+            .map(Some)
+            .map_err(|e| e.message().to_string()),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => Ok(None),
+        Err(e) => Err(e.message().to_string()),
+    }
+}
+
+#[tauri::command]
+pub fn get_file_diff(
+    repo_path: String,
+    mode: String,
+    paths: Option<Vec<String>>,
+    context_lines: Option<u32>,
+    base: Option<String>,
+) -> Result<Vec<FileDiff>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+
+    let mut opts = git2::DiffOptions::new();
+    opts.context_lines(context_lines.unwrap_or(3))
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    for p in paths.iter().flatten() {
+        opts.pathspec(p);
+    }
+
+    let mut diff = match mode.as_str() {
+        "head_to_index" => {
+            let tree = head_tree(&repo)?;
+            repo.diff_tree_to_index(tree.as_ref(), None, Some(&mut opts))
+        }
+        "index_to_worktree" => repo.diff_index_to_workdir(None, Some(&mut opts)),
+        "head_to_worktree" => {
+            let tree = head_tree(&repo)?;
+            repo.diff_tree_to_workdir_with_index(tree.as_ref(), Some(&mut opts))
+        }
+        "commit_to_worktree" => {
+            let spec = base
+                .as_deref()
+                .ok_or("commit_to_worktree needs a base revision")?;
+            let tree = resolve_commit(&repo, spec)?
+                .tree()
+                .map_err(|e| e.message().to_string())?;
+            repo.diff_tree_to_workdir_with_index(Some(&tree), Some(&mut opts))
+        }
+        other => return Err(format!("unknown diff mode: {other}")),
+    }
+    .map_err(|e| e.message().to_string())?;
+    detect_renames(&mut diff, mode != "head_to_index")?;
+
+    file_diffs_from_diff(&diff)
+}
+
+#[derive(Serialize)]
+pub struct RefDiff {
+    pub base: String,
+    pub target: String,
+    pub merge_base: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    pub files: Vec<FileDiff>,
+}
+
+fn resolve_commit<'r>(repo: &'r Repository, spec: &str) -> Result<git2::Commit<'r>, String> {
+    repo.revparse_single(spec)
+        .and_then(|o| o.peel_to_commit())
+        .map_err(|e| format!("Invalid revision '{spec}': {}", e.message()))
+}
+
+#[tauri::command]
+pub fn get_ref_diff(
+    repo_path: String,
+    base: String,
+    target: Option<String>,
+    merge_base: Option<bool>,
+    paths: Option<Vec<String>>,
+    context_lines: Option<u32>,
+) -> Result<RefDiff, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let base_commit = resolve_commit(&repo, &base)?;
+    let target_commit = resolve_commit(&repo, target.as_deref().unwrap_or("HEAD"))?;
+
+    let merge_base = if merge_base.unwrap_or(false) {
+        Some(
+            repo.merge_base(base_commit.id(), target_commit.id())
+                .map_err(|e| e.message().to_string())?,
+        )
+    } else {
+        None
+    };
+    let from = match merge_base {
+        Some(oid) => repo.find_commit(oid).map_err(|e| e.message().to_string())?,
+        None => base_commit.clone(),
+    };
+    let (ahead, behind) = repo
+        .graph_ahead_behind(target_commit.id(), base_commit.id())
+        .map_err(|e| e.message().to_string())?;
+
+    let mut opts = git2::DiffOptions::new();
+    opts.context_lines(context_lines.unwrap_or(3));
+    for p in paths.iter().flatten() {
+        opts.pathspec(p);
+    }
+    let from_tree = from.tree().map_err(|e| e.message().to_string())?;
+    let target_tree = target_commit.tree().map_err(|e| e.message().to_string())?;
+    let mut diff = repo
+        .diff_tree_to_tree(Some(&from_tree), Some(&target_tree), Some(&mut opts))
+        .map_err(|e| e.message().to_string())?;
+    detect_renames(&mut diff, false)?;
+    let stats = diff.stats().map_err(|e| e.message().to_string())?;
+
+    Ok(RefDiff {
+        base: from.id().to_string(),
+        target: target_commit.id().to_string(),
+        merge_base: merge_base.map(|oid| oid.to_string()),
+        ahead,
+        behind,
+        files_changed: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+        files: file_diffs_from_diff(&diff)?,
+    })
+}
+
+#[tauri::command]
+pub fn get_merge_base(
+    repo_path: String,
+    base: String,
+    target: Option<String>,
+) -> Result<String, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let base_commit = resolve_commit(&repo, &base)?;
+    let target_commit = resolve_commit(&repo, target.as_deref().unwrap_or("HEAD"))?;
+    repo.merge_base(base_commit.id(), target_commit.id())
+        .map(|oid| oid.to_string())
+        .map_err(|e| e.message().to_string())
+}
+
+#[derive(Serialize)]
+pub struct DiscardBackup {
+    pub id: String,
+    pub description: String,
+    pub paths: Vec<String>,
+    pub timestamp: i64,
+}
+
+const DISCARD_BACKUP_PREFIX: &str = "refs/releash/discards/";
+const MAX_DISCARD_BACKUPS: usize = 50;
+const DISCARD_ABSENT_TRAILER: &str = "Absent: ";
+
+fn releash_signature(repo: &Repository) -> Result<git2::Signature<'static>, String> {
+    repo.signature()
+        .or_else(|_| git2::Signature::now("Releash", "releash@localhost"))
+        .map_err(|e| e.message().to_string())
+}
+
+fn worktree_files(workdir: &Path, rel: &str) -> Vec<String> {
+    let full = workdir.join(rel);
+    // Symlinks are leaves, like in `read_worktree_content`; following them
+    // could loop or leave the repository.
+    if !std::fs::symlink_metadata(&full).is_ok_and(|m| m.is_dir()) {
+        return vec![rel.to_string()];
+    }
+    let mut files = Vec::new();
+    let mut pending = vec![full];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                pending.push(path);
+            } else if let Ok(relative) = path.strip_prefix(workdir) {
+                files.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+fn read_worktree_content(full: &Path) -> Result<Option<(Vec<u8>, u32)>, String> {
+    let Ok(metadata) = std::fs::symlink_metadata(full) else {
+        return Ok(None);
+    };
+    let display = full.display();
+    if metadata.file_type().is_symlink() {
+        let target =
+            std::fs::read_link(full).map_err(|e| format!("Failed to read {display}: {e}"))?;
+        return Ok(Some((
+            target.to_string_lossy().replace('\\', "/").into_bytes(),
+            0o120000,
+        )));
+    }
+    let content = std::fs::read(full).map_err(|e| format!("Failed to read {display}: {e}"))?;
+    #[cfg(unix)]
+    let executable = {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o111 != 0
+    };
+    #[cfg(not(unix))]
+    let executable = false;
+    Ok(Some((
+        content,
+        if executable { 0o100755 } else { 0o100644 },
+    )))
+}
+
+fn blob_index_entry(path: &str, id: git2::Oid, mode: u32) -> git2::IndexEntry {
+    git2::IndexEntry {
+        ctime: git2::IndexTime::new(0, 0),
+        mtime: git2::IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode,
+        uid: 0,
+        gid: 0,
+        file_size: 0,
+        id,
+        flags: 0,
+        flags_extended: 0,
+        path: path.as_bytes().to_vec(),
+    }
+}
+
+fn worktree_index_entry(
+    repo: &Repository,
+    workdir: &Path,
+    rel: &str,
+) -> Result<Option<git2::IndexEntry>, String> {
+    let Some((content, mode)) = read_worktree_content(&workdir.join(rel))? else {
+        return Ok(None);
+    };
+    let id = repo.blob(&content).map_err(|e| e.message().to_string())?;
+    Ok(Some(git2::IndexEntry {
+        file_size: content.len() as u32,
+        ..blob_index_entry(rel, id, mode)
+    }))
+}
+
+/// With `index`, the staged entries for `paths` are kept too, as the backup
+/// commit's parent.
+fn backup_worktree_paths(
+    repo: &Repository,
+    paths: &[String],
+    description: &str,
+    index: Option<&git2::Index>,
+) -> Result<DiscardBackup, String> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "repository has no working directory".to_string())?;
+    let sig = releash_signature(repo)?;
+
+    let index_commit = match index {
+        Some(index) => {
+            let mut staged = git2::Index::new().map_err(|e| e.message().to_string())?;
+            for entry in index.iter() {
+                let path = String::from_utf8_lossy(&entry.path);
+                let stage = (entry.flags >> 12) & 0x3;
+                if stage == 0
+                    && paths.iter().any(|p| {
+                        let p = p.trim_end_matches('/');
+                        path == p || path.starts_with(&format!("{p}/"))
+                    })
+                {
+                    staged.add(&entry).map_err(|e| e.message().to_string())?;
+                }
+            }
+            let tree_id = staged
+                .write_tree_to(repo)
+                .map_err(|e| e.message().to_string())?;
+            let tree = repo
+                .find_tree(tree_id)
+                .map_err(|e| e.message().to_string())?;
+            let oid = repo
+                .commit(
+                    None,
+                    &sig,
+                    &sig,
+                    &format!("discard index: {description}\n"),
+                    &tree,
+                    &[],
+                )
+                .map_err(|e| e.message().to_string())?;
+            Some(repo.find_commit(oid).map_err(|e| e.message().to_string())?)
+        }
+        None => None,
+    };
+
+    let mut index = git2::Index::new().map_err(|e| e.message().to_string())?;
+    let mut captured = Vec::new();
+    let mut absent = Vec::new();
+    for rel in paths
+        .iter()
+        .flat_map(|p| worktree_files(workdir, p.trim_end_matches('/')))
+    {
+        let Some(entry) = worktree_index_entry(repo, workdir, &rel)? else {
+            absent.push(rel.clone());
+            captured.push(rel);
+            continue;
+        };
+        index.add(&entry).map_err(|e| e.message().to_string())?;
+        captured.push(rel);
+    }
+
+    let tree_id = index
+        .write_tree_to(repo)
+        .map_err(|e| e.message().to_string())?;
+    let tree = repo
+        .find_tree(tree_id)
+        .map_err(|e| e.message().to_string())?;
+    let mut message = format!("discard: {description}\n");
+    if !absent.is_empty() {
+        message.push('\n');
+        for path in &absent {
+            message.push_str(&format!("{DISCARD_ABSENT_TRAILER}{path}\n"));
+        }
+    }
+    let parents: Vec<&git2::Commit<'_>> = index_commit.iter().collect();
+    let oid = repo
+        .commit(None, &sig, &sig, &message, &tree, &parents)
+        .map_err(|e| e.message().to_string())?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let mut id = now;
+    while repo
+        .find_reference(&format!("{DISCARD_BACKUP_PREFIX}{id}"))
+        .is_ok()
+    {
+        id += 1;
+    }
+    repo.reference(
+        &format!("{DISCARD_BACKUP_PREFIX}{id}"),
+        oid,
+        false,
+        &format!("discard backup: {description}"),
+    )
+    .map_err(|e| e.message().to_string())?;
+
+    let mut existing = discard_backup_refs(repo)?;
+    if existing.len() > MAX_DISCARD_BACKUPS {
+        existing.sort_by_key(|(id, _)| *id);
+        for (old_id, _) in &existing[..existing.len() - MAX_DISCARD_BACKUPS] {
+            if let Ok(mut reference) =
+                repo.find_reference(&format!("{DISCARD_BACKUP_PREFIX}{old_id}"))
+            {
+                let _ = reference.delete();
+            }
+        }
+    }
+
+    Ok(DiscardBackup {
+        id: id.to_string(),
+        description: description.to_string(),
+        paths: captured,
+        timestamp: sig.when().seconds(),
+    })
+}
+
+fn discard_backup_refs(repo: &Repository) -> Result<Vec<(u128, git2::Oid)>, String> {
+    let refs = repo
+        .references_glob(&format!("{DISCARD_BACKUP_PREFIX}*"))
+        .map_err(|e| e.message().to_string())?;
+    Ok(refs
+        .flatten()
+        .filter_map(|r| {
+            let id = r
+                .name()?
+                .strip_prefix(DISCARD_BACKUP_PREFIX)?
+                .parse()
+                .ok()?;
+            Some((id, r.target()?))
+        })
+        .collect())
+}
+
+fn backup_files(commit: &git2::Commit<'_>) -> Result<Vec<(String, git2::Oid, i32)>, String> {
+    let tree = commit.tree().map_err(|e| e.message().to_string())?;
+    let mut files = Vec::new();
+    tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+        if entry.kind() == Some(git2::ObjectType::Blob) {
+            if let Some(name) = entry.name() {
+                files.push((format!("{root}{name}"), entry.id(), entry.filemode()));
+            }
+        }
+        git2::TreeWalkResult::Ok
+    })
+    .map_err(|e| e.message().to_string())?;
+    Ok(files)
+}
+
+fn backup_absent_paths(commit: &git2::Commit<'_>) -> Vec<String> {
+    commit
+        .message()
+        .unwrap_or("")
+        .lines()
+        .filter_map(|line| line.strip_prefix(DISCARD_ABSENT_TRAILER))
+        .map(|s| s.to_string())
+        .collect()
+}
+
+#[tauri::command]
+pub fn list_discard_backups(repo_path: String) -> Result<Vec<DiscardBackup>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let mut refs = discard_backup_refs(&repo)?;
+    refs.sort_by_key(|(id, _)| std::cmp::Reverse(*id));
+
+    refs.into_iter()
+        .map(|(id, oid)| {
+            let commit = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
+            let mut paths: Vec<String> = backup_files(&commit)?
+                .into_iter()
+                .map(|(path, _, _)| path)
+                .chain(backup_absent_paths(&commit))
+                .collect();
+            paths.sort();
+            let summary = commit.summary().unwrap_or("");
+            Ok(DiscardBackup {
+                id: id.to_string(),
+                description: summary
+                    .strip_prefix("discard: ")
+                    .unwrap_or(summary)
+                    .to_string(),
+                paths,
+                timestamp: commit.time().seconds(),
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn git_restore_discard(repo_path: String, id: String) -> Result<(), String> {
+    let touched = |repo: &Repository| discard_backup_paths(repo, &id);
+    journaled_worktree(&repo_path, "restore_discard", touched, || {
+        restore_discard(&repo_path, &id)
+    })
+}
+
+fn discard_backup_paths(repo: &Repository, id: &str) -> Vec<String> {
+    let Ok(commit) = repo
+        .find_reference(&format!("{DISCARD_BACKUP_PREFIX}{id}"))
+        .and_then(|r| r.peel_to_commit())
+    else {
+        return Vec::new();
+    };
+    let mut paths: Vec<String> = backup_files(&commit)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(path, _, _)| path)
+        .collect();
+    paths.extend(backup_absent_paths(&commit));
+    paths
+}
+
+fn restore_discard(repo_path: &str, id: &str) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "repository has no working directory".to_string())?;
+    let reference = repo
+        .find_reference(&format!("{DISCARD_BACKUP_PREFIX}{id}"))
+        .map_err(|_| format!("no discard backup with id {id}"))?;
+    let commit = reference
+        .peel_to_commit()
+        .map_err(|e| e.message().to_string())?;
+
+    for (path, oid, mode) in backup_files(&commit)? {
+        let blob = repo.find_blob(oid).map_err(|e| e.message().to_string())?;
+        write_worktree_file(&workdir.join(&path), blob.content(), mode as u32)?;
+    }
+    for path in backup_absent_paths(&commit) {
+        let full = workdir.join(&path);
+        if full.is_file() {
+            std::fs::remove_file(&full).map_err(|e| format!("Failed to remove {path}: {e}"))?;
+        }
+    }
+
+    if let Ok(staged) = commit.parent(0) {
+        let mut index = repo.index().map_err(|e| e.message().to_string())?;
+        let entries = backup_files(&staged)?;
+        for path in discard_backup_paths(&repo, id) {
+            if !entries.iter().any(|(p, _, _)| *p == path) {
+                let _ = index.remove_path(Path::new(&path));
+            }
+        }
+        for (path, oid, mode) in entries {
+            index
+                .add(&blob_index_entry(&path, oid, mode as u32))
+                .map_err(|e| e.message().to_string())?;
+        }
+        index.write().map_err(|e| e.message().to_string())?;
+    }
+    Ok(())
+}
+
+fn index_tracks(index: &git2::Index, rel: &str) -> bool {
+    if index.get_path(Path::new(rel), 0).is_some() {
+        return true;
+    }
+    let prefix = format!("{rel}/");
+    index
+        .iter()
+        .any(|entry| entry.path.starts_with(prefix.as_bytes()))
+}
+
+#[tauri::command]
+pub fn git_discard_files(
+    repo_path: String,
+    paths: Vec<String>,
+    source: Option<String>,
+) -> Result<DiscardBackup, String> {
+    let touched = |repo: &Repository| match repo.workdir() {
+        Some(workdir) => paths
+            .iter()
+            .flat_map(|p| worktree_files(workdir, p))
+            .collect(),
+        None => Vec::new(),
+    };
+    journaled_worktree(&repo_path, "discard_files", touched, || {
+        discard_files(&repo_path, paths.clone(), source)
+    })
+}
+
+fn discard_files(
+    repo_path: &str,
+    paths: Vec<String>,
+    source: Option<String>,
+) -> Result<DiscardBackup, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "repository has no working directory".to_string())?
+        .to_path_buf();
+    if paths.is_empty() {
+        return Err("no paths to discard".to_string());
+    }
+    let from_head = match source.as_deref().unwrap_or("index") {
+        "index" => false,
+        "head" => true,
+        other => return Err(format!("unknown discard source: {other}")),
+    };
+
+    let rels: Vec<String> = paths
+        .iter()
+        .map(|p| p.trim_end_matches('/').to_string())
+        .collect();
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+    let backup =
+        backup_worktree_paths(&repo, &rels, &rels.join(", "), from_head.then_some(&index))?;
+
+    let tree = head_tree(&repo)?;
+    let mut restore = Vec::new();
+    let mut index_changed = false;
+    for rel in &rels {
+        let tracked = if from_head {
+            tree.as_ref()
+                .is_some_and(|t| t.get_path(Path::new(rel)).is_ok())
+        } else {
+            index_tracks(&index, rel)
+        };
+        if tracked {
+            restore.push(rel.clone());
+            continue;
+        }
+        if from_head && index_tracks(&index, rel) {
+            index
+                .remove_all([rel.as_str()], None)
+                .map_err(|e| e.message().to_string())?;
+            index_changed = true;
+        }
+        let full = workdir.join(rel);
+        let removed = if full.is_dir() {
+            std::fs::remove_dir_all(&full)
+        } else if full.exists() {
+            std::fs::remove_file(&full)
+        } else {
+            Ok(())
+        };
+        removed.map_err(|e| format!("Failed to remove {rel}: {e}"))?;
+    }
+    if index_changed {
+        index.write().map_err(|e| e.message().to_string())?;
+    }
+
+    if !restore.is_empty() {
+        let mut builder = CheckoutBuilder::new();
+        builder.force();
+        for rel in &restore {
+            builder.path(rel.as_str());
+        }
+        match (&tree, from_head) {
+            (Some(tree), true) => repo.checkout_tree(tree.as_object(), Some(&mut builder)),
+            _ => repo.checkout_index(Some(&mut index), Some(&mut builder)),
+        }
+        .map_err(|e| e.message().to_string())?;
+    }
+
+    Ok(backup)
+}
+
+fn patch_paths(patch: &[u8]) -> Vec<String> {
+    let mut paths = Vec::new();
+    for line in String::from_utf8_lossy(patch).lines() {
+        let raw = line
+            .strip_prefix("--- ")
+            .or_else(|| line.strip_prefix("+++ "));
+        if let Some(path) = raw.and_then(strip_patch_path) {
+            if !paths.iter().any(|p| p == path) {
+                paths.push(path.to_string());
+            }
+        }
+    }
+    paths
+}
+
+#[tauri::command]
+pub fn git_discard_hunk(
+    repo_path: String,
+    patch_base64: String,
+) -> Result<DiscardBackup, HunkApplyError> {
+    let patch = decode_patch(&patch_base64)?;
+    journaled_worktree(
+        &repo_path,
+        "discard_hunk",
+        |_| patch_paths(&patch),
+        || discard_hunk(&repo_path, &patch),
+    )
+}
+
+fn discard_hunk(repo_path: &str, patch: &[u8]) -> Result<DiscardBackup, HunkApplyError> {
+    let repo = Repository::open(repo_path).map_err(|e| HunkApplyError::other(e.message()))?;
+    let paths = patch_paths(patch);
+    if paths.is_empty() {
+        return Err(HunkApplyError {
+            reason: "invalid_patch".to_string(),
+            path: None,
+            hunk_index: None,
+            hunk_header: None,
+            message: "patch names no file".to_string(),
+        });
+    }
+    let backup = backup_worktree_paths(
+        &repo,
+        &paths,
+        &format!("hunk in {}", paths.join(", ")),
+        None,
+    )
+    .map_err(HunkApplyError::other)?;
+    apply_patch(&repo, patch, git2::ApplyLocation::WorkDir, true)?;
+    Ok(backup)
+}
+
+/// A deleted block is selected by the line that now follows it (one past the
+/// last line for deletions at the end of the file).
+#[tauri::command]
+pub fn git_discard_lines(
+    repo_path: String,
+    path: String,
+    start_line: u32,
+    end_line: u32,
+) -> Result<DiscardBackup, String> {
+    journaled_worktree(
+        &repo_path,
+        "discard_lines",
+        |_| vec![path.clone()],
+        || discard_lines(&repo_path, path.clone(), start_line, end_line),
+    )
+}
+
+fn discard_lines(
+    repo_path: &str,
+    path: String,
+    start_line: u32,
+    end_line: u32,
+) -> Result<DiscardBackup, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "repository has no working directory".to_string())?;
+    let full = workdir.join(&path);
+    let current = std::fs::read(&full).map_err(|e| format!("Failed to read {path}: {e}"))?;
+
+    let index = repo.index().map_err(|e| e.message().to_string())?;
+    let original = match index.get_path(Path::new(&path), 0) {
+        Some(entry) => repo
+            .find_blob(entry.id)
+            .map_err(|e| e.message().to_string())?
+            .content()
+            .to_vec(),
+        None => Vec::new(),
+    };
+
+    let mut opts = git2::DiffOptions::new();
+    opts.context_lines(0);
+    let patch = git2::Patch::from_buffers(
+        &original,
+        Some(Path::new(&path)),
+        &current,
+        Some(Path::new(&path)),
+        Some(&mut opts),
+    )
+    .map_err(|e| e.message().to_string())?;
+    if patch.delta().flags().is_binary() {
+        return Err(format!("{path} is binary; discard the whole file instead"));
+    }
+
+    let current_lines: Vec<&[u8]> = current.split_inclusive(|&b| b == b'\n').collect();
+    let selected = |line: u32| line >= start_line && line <= end_line;
+    let mut result: Vec<u8> = Vec::with_capacity(current.len());
+    // Next worktree line (1-based) not yet copied to `result`.
+    let mut next = 1usize;
+    let mut changed = false;
+    let copy_until = |result: &mut Vec<u8>, next: &mut usize, until: usize| {
+        while *next <= until && *next <= current_lines.len() {
+            result.extend_from_slice(current_lines[*next - 1]);
+            *next += 1;
+        }
+    };
+
+    for hunk_idx in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_idx).map_err(|e| e.message().to_string())?;
+        // Pure deletions sit after `new_start`; other hunks begin at it.
+        let before = if hunk.new_lines() == 0 {
+            hunk.new_start() as usize
+        } else {
+            hunk.new_start() as usize - 1
+        };
+        copy_until(&mut result, &mut next, before);
+
+        for line_idx in 0..line_count {
+            let line = patch
+                .line_in_hunk(hunk_idx, line_idx)
+                .map_err(|e| e.message().to_string())?;
+            match line.origin() {
+                '-' if selected(next as u32) => {
+                    result.extend_from_slice(line.content());
+                    changed = true;
+                }
+                '+' => {
+                    let lineno = line.new_lineno().unwrap_or(0) as usize;
+                    copy_until(&mut result, &mut next, lineno.saturating_sub(1));
+                    if selected(lineno as u32) {
+                        changed = true;
+                    } else if let Some(content) = current_lines.get(lineno - 1) {
+                        result.extend_from_slice(content);
+                    }
+                    next = lineno + 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    copy_until(&mut result, &mut next, current_lines.len());
+
+    if !changed {
+        return Err(format!(
+            "no changes to {path} within lines {start_line}-{end_line}"
+        ));
+    }
+
+    let backup = backup_worktree_paths(
+        &repo,
+        std::slice::from_ref(&path),
+        &format!("{path} lines {start_line}-{end_line}"),
+        None,
+    )?;
+    std::fs::write(&full, result).map_err(|e| format!("Failed to write {path}: {e}"))?;
+    Ok(backup)
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckoutError {
+    pub reason: String,
+    pub blocking_paths: Vec<String>,
+    pub message: String,
+}
+
+impl CheckoutError {
+    fn other(message: impl Into<String>) -> Self {
+        CheckoutError {
+            reason: "other".to_string(),
+            blocking_paths: Vec::new(),
+            message: message.into(),
+        }
+    }
+}
+
+fn safe_checkout(
+    repo: &Repository,
+    target: &git2::Object<'_>,
+    refname: &str,
+) -> Result<(), CheckoutError> {
+    let blocking = std::cell::RefCell::new(Vec::new());
+    let mut builder = CheckoutBuilder::new();
+    builder
+        .safe()
+        .notify_on(git2::CheckoutNotificationType::CONFLICT)
+        .notify(|_, path, _, _, _| {
+            if let Some(path) = path {
+                blocking
+                    .borrow_mut()
+                    .push(path.to_string_lossy().replace('\\', "/"));
+            }
+            true
+        });
+    let result = repo.checkout_tree(target, Some(&mut builder));
+    drop(builder);
+
+    if let Err(e) = result {
+        let blocking_paths = blocking.into_inner();
+        if blocking_paths.is_empty() {
+            return Err(CheckoutError::other(e.message()));
+        }
+        return Err(CheckoutError {
+            reason: "dirty_worktree".to_string(),
+            message: format!(
+                "local changes would be overwritten by checkout: {}",
+                blocking_paths.join(", ")
+            ),
+            blocking_paths,
+        });
+    }
+
+    repo.set_head(refname)
+        .map_err(|e| CheckoutError::other(e.message()))
+}
+
+fn ensure_not_checked_out_elsewhere(repo: &Repository, refname: &str) -> Result<(), CheckoutError> {
+    let own_path = repo
+        .workdir()
+        .map(|p| p.to_string_lossy().trim_end_matches('/').to_string());
+    match checked_out_branches(repo).get(refname) {
+        Some(path) if Some(path) != own_path.as_ref() => Err(CheckoutError {
+            reason: "checked_out_elsewhere".to_string(),
+            blocking_paths: Vec::new(),
+            message: format!("{refname} is already checked out at {path}"),
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub fn git_create_branch(
+    repo_path: String,
+    branch_name: String,
+    start_point: Option<String>,
+    checkout: Option<bool>,
+) -> Result<(), CheckoutError> {
+    let spec = start_point.clone().unwrap_or_else(|| "HEAD".to_string());
+    let touched = |repo: &Repository| match checkout.unwrap_or(true) {
+        true => checkout_paths(repo, &spec),
+        false => Vec::new(),
+    };
+    journaled_worktree(&repo_path, "create_branch", touched, || {
+        create_branch(&repo_path, branch_name, start_point, checkout)
+    })
+}
+
+fn create_branch(
+    repo_path: &str,
+    branch_name: String,
+    start_point: Option<String>,
+    checkout: Option<bool>,
+) -> Result<(), CheckoutError> {
+    let repo = Repository::open(repo_path).map_err(|e| CheckoutError::other(e.message()))?;
+
+    let spec = start_point.as_deref().unwrap_or("HEAD");
+    let commit = repo
+        .revparse_single(spec)
+        .and_then(|obj| obj.peel_to_commit())
+        .map_err(|e| CheckoutError::other(e.message()))?;
+
+    let mut branch = repo
+        .branch(&branch_name, &commit, false)
+        .map_err(|e| CheckoutError::other(e.message()))?;
+
+    if let Some(spec) = start_point.as_deref() {
+        if repo.find_branch(spec, BranchType::Remote).is_ok() {
+            branch
+                .set_upstream(Some(spec))
+                .map_err(|e| CheckoutError::other(e.message()))?;
+        }
+    }
+
+    if checkout.unwrap_or(true) {
+        let refname = format!("refs/heads/{branch_name}");
+        if let Err(e) = safe_checkout(&repo, commit.as_object(), &refname) {
+            // Like `git checkout -b`, leave no branch behind when the switch fails.
+            let _ = branch.delete();
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn git_checkout_branch(repo_path: String, name: String) -> Result<(), CheckoutError> {
+    journaled_worktree(
+        &repo_path,
+        "checkout_branch",
+        |repo| checkout_paths(repo, &name),
+        || checkout_branch(&repo_path, name.clone()),
+    )
+}
+
+fn checkout_branch(repo_path: &str, name: String) -> Result<(), CheckoutError> {
+    let repo = Repository::open(repo_path).map_err(|e| CheckoutError::other(e.message()))?;
+
+    let (branch, created) = match repo.find_branch(&name, BranchType::Local) {
+        Ok(branch) => (branch, false),
+        Err(e) if e.code() == ErrorCode::NotFound => {
+            let remote = repo
+                .find_branch(&name, BranchType::Remote)
+                .map_err(|_| CheckoutError::other(format!("branch '{name}' not found")))?;
+            let local_name = name
+                .split_once('/')
+                .map(|(_, rest)| rest)
+                .unwrap_or(&name)
+                .to_string();
+            match repo.find_branch(&local_name, BranchType::Local) {
+                Ok(existing) => (existing, false),
+                Err(_) => {
+                    let commit = remote
+                        .get()
+                        .peel_to_commit()
+                        .map_err(|e| CheckoutError::other(e.message()))?;
+                    let mut created = repo
+                        .branch(&local_name, &commit, false)
+                        .map_err(|e| CheckoutError::other(e.message()))?;
+                    if let Err(e) = created.set_upstream(Some(&name)) {
+                        let _ = created.delete();
+                        return Err(CheckoutError::other(e.message()));
+                    }
+                    (created, true)
+                }
+            }
+        }
+        Err(e) => return Err(CheckoutError::other(e.message())),
+    };
+
+    let mut reference = branch.into_reference();
+    let refname = reference
+        .name()
+        .ok_or_else(|| CheckoutError::other("invalid branch name encoding"))?
+        .to_string();
+    let result = ensure_not_checked_out_elsewhere(&repo, &refname).and_then(|()| {
+        let target = reference
+            .peel(git2::ObjectType::Commit)
+            .map_err(|e| CheckoutError::other(e.message()))?;
+        safe_checkout(&repo, &target, &refname)
+    });
+    if result.is_err() && created {
+        // As in `create_branch`, don't leave the new local branch behind.
+        let _ = reference.delete();
+    }
+    result
+}
+
+#[tauri::command]
+pub fn git_delete_branch(repo_path: String, name: String, force: bool) -> Result<(), String> {
+    journaled(&repo_path, "delete_branch", || {
+        delete_branch(&repo_path, name, force)
+    })
+}
+
+fn delete_branch(repo_path: &str, name: String, force: bool) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let mut branch = repo
+        .find_branch(&name, BranchType::Local)
+        .map_err(|e| e.message().to_string())?;
+
+    if let Some(refname) = branch.get().name() {
+        if let Some(path) = checked_out_branches(&repo).get(refname) {
+            return Err(format!(
+                "cannot delete branch '{name}' checked out at {path}"
+            ));
+        }
+    }
+
+    if !force {
+        let tip = branch
+            .get()
+            .target()
+            .ok_or_else(|| format!("branch '{name}' has no target"))?;
+        let base = match branch.upstream() {
+            Ok(upstream) => upstream.get().target(),
+            Err(_) => repo.head().ok().and_then(|h| h.target()),
+        };
+        let merged = match base {
+            Some(base) => {
+                base == tip
+                    || repo
+                        .graph_descendant_of(base, tip)
+                        .map_err(|e| e.message().to_string())?
+            }
+            None => false,
+        };
+        if !merged {
+            return Err(format!("branch '{name}' is not fully merged"));
+        }
+    }
+
+    branch.delete().map_err(|e| e.message().to_string())
+}
+
+#[tauri::command]
+pub fn git_rename_branch(
+    repo_path: String,
+    old_name: String,
+    new_name: String,
+    force: bool,
+) -> Result<(), String> {
+    journaled(&repo_path, "rename_branch", || {
+        rename_branch(&repo_path, old_name, new_name, force)
+    })
+}
+
+fn rename_branch(
+    repo_path: &str,
+    old_name: String,
+    new_name: String,
+    force: bool,
+) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let mut branch = repo
+        .find_branch(&old_name, BranchType::Local)
+        .map_err(|e| e.message().to_string())?;
+    branch
+        .rename(&new_name, force)
+        .map_err(|e| e.message().to_string())?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct WorktreeInfo {
+    pub name: Option<String>,
+    pub path: String,
+    pub is_main: bool,
+    pub branch: Option<String>,
+    pub head: Option<String>,
+    pub locked: bool,
+    pub lock_reason: Option<String>,
+    pub prunable: bool,
+}
+
+fn worktree_head(repo: &Repository) -> (Option<String>, Option<String>) {
+    match repo.head() {
+        Ok(head) => {
+            let branch = head
+                .is_branch()
+                .then(|| head.shorthand().map(|s| s.to_string()))
+                .flatten();
+            (branch, head.target().map(|oid| oid.to_string()))
+        }
+        Err(_) => (None, None),
+    }
+}
+
+fn worktree_info(worktree: &git2::Worktree) -> Result<WorktreeInfo, String> {
+    let (locked, lock_reason) = match worktree.is_locked().map_err(|e| e.message().to_string())? {
+        git2::WorktreeLockStatus::Unlocked => (false, None),
+        git2::WorktreeLockStatus::Locked(reason) => (true, reason.filter(|r| !r.is_empty())),
+    };
+    let prunable = worktree.validate().is_err();
+    let (branch, head) = if prunable {
+        (None, None)
+    } else {
+        Repository::open_from_worktree(worktree)
+            .map(|repo| worktree_head(&repo))
+            .unwrap_or((None, None))
+    };
+
+    Ok(WorktreeInfo {
+        name: worktree.name().map(|s| s.to_string()),
+        path: worktree
+            .path()
+            .to_string_lossy()
+            .trim_end_matches('/')
+            .to_string(),
+        is_main: false,
+        branch,
+        head,
+        locked,
+        lock_reason,
+        prunable,
+    })
+}
+
+fn main_workdir(repo: &Repository) -> Option<std::path::PathBuf> {
+    if !repo.is_worktree() {
+        return repo.workdir().map(|p| p.to_path_buf());
+    }
+    // Linked worktrees keep a `commondir` file pointing at the shared git dir.
+    let common = std::fs::read_to_string(repo.path().join("commondir")).ok()?;
+    let common_dir = repo.path().join(common.trim()).canonicalize().ok()?;
+    let main_repo = Repository::open(&common_dir).ok()?;
+    main_repo.workdir().map(|p| p.to_path_buf())
+}
+
+fn find_worktree(repo: &Repository, name: &str) -> Result<git2::Worktree, String> {
+    repo.find_worktree(name)
+        .map_err(|e| e.message().to_string())
+}
+
+#[tauri::command]
+pub fn list_worktrees(repo_path: String) -> Result<Vec<WorktreeInfo>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+
+    let mut result = Vec::new();
+    if let Some(main_path) = main_workdir(&repo) {
+        let main_repo = Repository::open(&main_path).map_err(|e| e.message().to_string())?;
+        let (branch, head) = worktree_head(&main_repo);
+        result.push(WorktreeInfo {
+            name: None,
+            path: main_path
+                .to_string_lossy()
+                .trim_end_matches('/')
+                .to_string(),
+            is_main: true,
+            branch,
+            head,
+            locked: false,
+            lock_reason: None,
+            prunable: false,
+        });
+    }
+
+    let names = repo.worktrees().map_err(|e| e.message().to_string())?;
+    for name in names.iter().flatten() {
+        result.push(worktree_info(&find_worktree(&repo, name)?)?);
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn git_add_worktree(
+    repo_path: String,
+    name: String,
+    path: String,
+    new_branch: Option<String>,
+    git_ref: Option<String>,
+) -> Result<WorktreeInfo, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+
+    let target_commit = |spec: Option<&str>| -> Result<git2::Commit<'_>, String> {
+        repo.revparse_single(spec.unwrap_or("HEAD"))
+            .and_then(|obj| obj.peel_to_commit())
+            .map_err(|e| e.message().to_string())
+    };
+
+    let (branch, created) = match (new_branch, git_ref.as_deref()) {
+        (Some(branch_name), spec) => {
+            let commit = target_commit(spec)?;
+            let branch = repo
+                .branch(&branch_name, &commit, false)
+                .map_err(|e| e.message().to_string())?;
+            (branch, true)
+        }
+        (None, Some(spec)) => match repo.find_branch(spec, BranchType::Local) {
+            Ok(branch) => (branch, false),
+            Err(e) if e.code() == ErrorCode::NotFound => {
+                let commit = target_commit(Some(spec))?;
+                let branch = repo
+                    .branch(&name, &commit, false)
+                    .map_err(|e| e.message().to_string())?;
+                (branch, true)
+            }
+            Err(e) => return Err(e.message().to_string()),
+        },
+        (None, None) => {
+            let commit = target_commit(None)?;
+            let branch = repo
+                .branch(&name, &commit, false)
+                .map_err(|e| e.message().to_string())?;
+            (branch, true)
+        }
+    };
+
+    let mut reference = branch.into_reference();
+    let mut opts = git2::WorktreeAddOptions::new();
+    opts.reference(Some(&reference));
+    let worktree = match repo.worktree(&name, Path::new(&path), Some(&opts)) {
+        Ok(worktree) => worktree,
+        Err(e) => {
+            if created {
+                let _ = reference.delete();
+            }
+            return Err(e.message().to_string());
+        }
+    };
+
+    worktree_info(&worktree)
+}
+
+#[tauri::command]
+pub fn git_lock_worktree(
+    repo_path: String,
+    name: String,
+    reason: Option<String>,
+) -> Result<(), String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    find_worktree(&repo, &name)?
+        .lock(reason.as_deref())
+        .map_err(|e| e.message().to_string())
+}
+
+#[tauri::command]
+pub fn git_unlock_worktree(repo_path: String, name: String) -> Result<(), String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    find_worktree(&repo, &name)?
+        .unlock()
+        .map_err(|e| e.message().to_string())
+}
+
+#[tauri::command]
+pub fn git_prune_worktrees(repo_path: String) -> Result<Vec<String>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+
+    let mut pruned = Vec::new();
+    let names = repo.worktrees().map_err(|e| e.message().to_string())?;
+    for name in names.iter().flatten() {
+        let worktree = find_worktree(&repo, name)?;
+        if worktree
+            .is_prunable(None)
+            .map_err(|e| e.message().to_string())?
+        {
+            worktree.prune(None).map_err(|e| e.message().to_string())?;
+            pruned.push(name.to_string());
+        }
+    }
+
+    Ok(pruned)
+}
+
+#[tauri::command]
+pub fn git_remove_worktree(repo_path: String, name: String, force: bool) -> Result<(), String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let worktree = find_worktree(&repo, &name)?;
+
+    if !force {
+        if let git2::WorktreeLockStatus::Locked(_) =
+            worktree.is_locked().map_err(|e| e.message().to_string())?
+        {
+            return Err(format!("worktree '{name}' is locked"));
+        }
+        if worktree.validate().is_ok() {
+            let wt_repo =
+                Repository::open_from_worktree(&worktree).map_err(|e| e.message().to_string())?;
+            let mut opts = StatusOptions::new();
+            opts.include_untracked(true).recurse_untracked_dirs(true);
+            let statuses = wt_repo
+                .statuses(Some(&mut opts))
+                .map_err(|e| e.message().to_string())?;
+            if !statuses.is_empty() {
+                return Err(format!("worktree '{name}' has uncommitted changes"));
+            }
+        }
+    }
+
+    let mut opts = git2::WorktreePruneOptions::new();
+    opts.valid(true).locked(force).working_tree(true);
+    worktree
+        .prune(Some(&mut opts))
+        .map_err(|e| e.message().to_string())
+}
+
+#[derive(Serialize)]
+pub struct StashEntry {
+    pub index: usize,
+    pub message: String,
+    pub oid: String,
+    pub base: Option<String>,
+    pub time: i64,
+    pub has_untracked: bool,
+}
+
+fn stash_entries(repo: &mut Repository) -> Result<Vec<StashEntry>, String> {
+    let mut raw = Vec::new();
+    repo.stash_foreach(|index, message, oid| {
+        raw.push((index, message.to_string(), *oid));
+        true
+    })
+    .map_err(|e| e.message().to_string())?;
+
+    raw.into_iter()
+        .map(|(index, message, oid)| {
+            let commit = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
+            Ok(StashEntry {
+                index,
+                message,
+                oid: oid.to_string(),
+                base: commit.parent_id(0).ok().map(|id| id.to_string()),
+                time: commit.time().seconds(),
+                has_untracked: commit.parent_count() > 2,
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn list_stashes(repo_path: String) -> Result<Vec<StashEntry>, String> {
+    let mut repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    stash_entries(&mut repo)
+}
+
+#[tauri::command]
+pub fn git_stash_save(
+    repo_path: String,
+    message: Option<String>,
+    keep_index: bool,
+    include_untracked: bool,
+) -> Result<StashEntry, String> {
+    let touched = |repo: &Repository| dirty_paths(repo, include_untracked);
+    journaled_worktree(&repo_path, "stash_save", touched, || {
+        stash_save(&repo_path, message, keep_index, include_untracked)
+    })
+}
+
+fn dirty_paths(repo: &Repository, include_untracked: bool) -> Vec<String> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(include_untracked)
+        .recurse_untracked_dirs(include_untracked);
+    repo.statuses(Some(&mut opts))
+        .map(|statuses| {
+            statuses
+                .iter()
+                .filter_map(|entry| entry.path().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn stash_save(
+    repo_path: &str,
+    message: Option<String>,
+    keep_index: bool,
+    include_untracked: bool,
+) -> Result<StashEntry, String> {
+    let mut repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let sig = repo.signature().map_err(|e| e.message().to_string())?;
+
+    let mut flags = git2::StashFlags::DEFAULT;
+    if keep_index {
+        flags |= git2::StashFlags::KEEP_INDEX;
+    }
+    if include_untracked {
+        flags |= git2::StashFlags::INCLUDE_UNTRACKED;
+    }
+
+    repo.stash_save2(&sig, message.as_deref(), Some(flags))
+        .map_err(|e| e.message().to_string())?;
+
+    stash_entries(&mut repo)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "stash was not recorded".to_string())
+}
+
+fn stash_apply_options<'cb>(reinstate_index: bool) -> git2::StashApplyOptions<'cb> {
+    let mut opts = git2::StashApplyOptions::new();
+    if reinstate_index {
+        opts.reinstantiate_index();
+    }
+    opts
+}
+
+#[tauri::command]
+pub fn git_stash_apply(
+    repo_path: String,
+    index: usize,
+    reinstate_index: bool,
+) -> Result<(), String> {
+    let touched = |repo: &Repository| stash_paths(repo, index);
+    journaled_worktree(&repo_path, "stash_apply", touched, || {
+        let mut repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+        repo.stash_apply(index, Some(&mut stash_apply_options(reinstate_index)))
+            .map_err(|e| e.message().to_string())
+    })
+}
+
+#[tauri::command]
+pub fn git_stash_pop(repo_path: String, index: usize, reinstate_index: bool) -> Result<(), String> {
+    let touched = |repo: &Repository| stash_paths(repo, index);
+    journaled_worktree(&repo_path, "stash_pop", touched, || {
+        let mut repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+        repo.stash_pop(index, Some(&mut stash_apply_options(reinstate_index)))
+            .map_err(|e| e.message().to_string())
+    })
+}
+
+#[tauri::command]
+pub fn git_stash_drop(repo_path: String, index: usize) -> Result<(), String> {
+    journaled(&repo_path, "stash_drop", || {
+        let mut repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+        repo.stash_drop(index).map_err(|e| e.message().to_string())
+    })
+}
+
+fn stash_paths(repo: &Repository, index: usize) -> Vec<String> {
+    let Some(stash) = repo
+        .reflog("refs/stash")
+        .ok()
+        .and_then(|log| log.get(index).map(|entry| entry.id_new()))
+        .and_then(|id| repo.find_commit(id).ok())
+    else {
+        return Vec::new();
+    };
+    let mut paths = Vec::new();
+    if let (Ok(base), Ok(tree)) = (stash.parent(0).and_then(|p| p.tree()), stash.tree()) {
+        if let Ok(diff) = repo.diff_tree_to_tree(Some(&base), Some(&tree), None) {
+            paths.extend(delta_paths(&diff));
+        }
+    }
+    if let Ok(untracked) = stash.parent(2).and_then(|p| p.tree()) {
+        if let Ok(diff) = repo.diff_tree_to_tree(None, Some(&untracked), None) {
+            paths.extend(delta_paths(&diff));
+        }
+    }
+    paths
+}
+
+#[tauri::command]
+pub fn get_stash_diff(
+    repo_path: String,
+    index: usize,
+    context_lines: Option<u32>,
+) -> Result<Vec<FileDiff>, String> {
+    let mut repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let entry = stash_entries(&mut repo)?
+        .into_iter()
+        .find(|e| e.index == index)
+        .ok_or_else(|| format!("no stash entry at index {index}"))?;
+
+    let oid = git2::Oid::from_str(&entry.oid).map_err(|e| e.message().to_string())?;
+    let stash = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
+    let base = stash.parent(0).map_err(|e| e.message().to_string())?;
+    let base_tree = base.tree().map_err(|e| e.message().to_string())?;
+    let stash_tree = stash.tree().map_err(|e| e.message().to_string())?;
+
+    let mut opts = git2::DiffOptions::new();
+    opts.context_lines(context_lines.unwrap_or(3));
+    let mut diff = repo
+        .diff_tree_to_tree(Some(&base_tree), Some(&stash_tree), Some(&mut opts))
+        .map_err(|e| e.message().to_string())?;
+    detect_renames(&mut diff, false)?;
+
+    // The third parent holds untracked files, stored as a root tree.
+    if let Ok(untracked) = stash.parent(2) {
+        let untracked_tree = untracked.tree().map_err(|e| e.message().to_string())?;
+        let untracked_diff = repo
+            .diff_tree_to_tree(None, Some(&untracked_tree), Some(&mut opts))
+            .map_err(|e| e.message().to_string())?;
+        diff.merge(&untracked_diff)
+            .map_err(|e| e.message().to_string())?;
+    }
+
+    file_diffs_from_diff(&diff)
+}
+
+/// The commit's tree is the worktree, untracked files included; its first
+/// parent holds the index tree and its second, if any, is HEAD at the time.
+#[derive(Clone, Serialize)]
+pub struct CheckpointInfo {
+    pub id: String,
+    pub session: String,
+    pub number: u64,
+    pub oid: String,
+    pub label: String,
+    pub timestamp: i64,
+    pub head: Option<String>,
+}
+
+pub const CHECKPOINT_EVENT: &str = "git-checkpoint-created";
+const CHECKPOINT_PREFIX: &str = "refs/releash/checkpoints/";
+
+fn checkpoint_refs(
+    repo: &Repository,
+    session: Option<&str>,
+) -> Result<Vec<(String, u64, git2::Oid)>, String> {
+    let refs = repo
+        .references_glob(&format!("{CHECKPOINT_PREFIX}*"))
+        .map_err(|e| e.message().to_string())?;
+    Ok(refs
+        .flatten()
+        .filter_map(|r| {
+            let (name, number) = r.name()?.strip_prefix(CHECKPOINT_PREFIX)?.split_once('/')?;
+            if session.is_some_and(|s| s != name) {
+                return None;
+            }
+            Some((name.to_string(), number.parse().ok()?, r.target()?))
+        })
+        .collect())
+}
+
+fn checkpoint_info(session: &str, number: u64, commit: &git2::Commit<'_>) -> CheckpointInfo {
+    let message = commit.message().unwrap_or("");
+    CheckpointInfo {
+        id: format!("{session}/{number}"),
+        session: session.to_string(),
+        number,
+        oid: commit.id().to_string(),
+        label: message
+            .strip_prefix("checkpoint: ")
+            .unwrap_or(message)
+            .trim_end()
+            .to_string(),
+        timestamp: commit.time().seconds(),
+        head: commit.parent_id(1).ok().map(|id| id.to_string()),
+    }
+}
+
+fn find_checkpoint<'r>(repo: &'r Repository, id: &str) -> Result<git2::Commit<'r>, String> {
+    repo.find_reference(&format!("{CHECKPOINT_PREFIX}{id}"))
+        .and_then(|r| r.peel_to_commit())
+        .map_err(|_| format!("no checkpoint {id}"))
+}
+
+/// A conflicted path is recorded as "ours" (stage 2) in the index tree and
+/// with its worktree bytes, markers included, in the worktree tree.
+fn snapshot_trees(repo: &Repository) -> Result<(git2::Oid, git2::Oid), String> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "repository has no working directory".to_string())?;
+    let repo_index = repo.index().map_err(|e| e.message().to_string())?;
+    let mut snapshot = git2::Index::new().map_err(|e| e.message().to_string())?;
+    for entry in repo_index.iter() {
+        let stage = (entry.flags >> 12) & 0x3;
+        if stage == 0 || stage == 2 {
+            snapshot
+                .add(&git2::IndexEntry {
+                    flags: entry.flags & !0x3000,
+                    ..entry
+                })
+                .map_err(|e| e.message().to_string())?;
+        }
+    }
+    let index_tree = snapshot
+        .write_tree_to(repo)
+        .map_err(|e| e.message().to_string())?;
+
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .exclude_submodules(true);
+    let statuses = repo
+        .statuses(Some(&mut opts))
+        .map_err(|e| e.message().to_string())?;
+    for entry in statuses.iter() {
+        let Some(path) = entry.path() else { continue };
+        let status = entry.status();
+        if status.is_conflicted() {
+            match worktree_index_entry(repo, workdir, path)? {
+                Some(index_entry) => snapshot.add(&index_entry),
+                None => snapshot.remove_path(Path::new(path)),
+            }
+            .map_err(|e| e.message().to_string())?;
+        } else if status.is_wt_deleted() {
+            snapshot
+                .remove_path(Path::new(path))
+                .map_err(|e| e.message().to_string())?;
+        } else if status.intersects(
+            git2::Status::WT_NEW | git2::Status::WT_MODIFIED | git2::Status::WT_TYPECHANGE,
+        ) {
+            if let Some(index_entry) = worktree_index_entry(repo, workdir, path)? {
+                snapshot
+                    .add(&index_entry)
+                    .map_err(|e| e.message().to_string())?;
+            }
+        }
+    }
+
+    let worktree_tree = snapshot
+        .write_tree_to(repo)
+        .map_err(|e| e.message().to_string())?;
+    Ok((worktree_tree, index_tree))
+}
+
+fn create_checkpoint(
+    repo: &Repository,
+    session: &str,
+    label: &str,
+    skip_unchanged: bool,
+) -> Result<Option<CheckpointInfo>, String> {
+    if session.contains('/')
+        || !git2::Reference::is_valid_name(&format!("{CHECKPOINT_PREFIX}{session}/1"))
+    {
+        return Err(format!("invalid checkpoint session name: {session}"));
+    }
+
+    let (worktree_tree, index_tree) = snapshot_trees(repo)?;
+    let head = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit().map_err(|e| e.message().to_string())?),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(e.message().to_string()),
+    };
+
+    let existing = checkpoint_refs(repo, Some(session))?;
+    let latest = existing.iter().max_by_key(|(_, number, _)| *number);
+    if skip_unchanged {
+        if let Some((_, _, oid)) = latest {
+            let previous = repo
+                .find_commit(*oid)
+                .map_err(|e| e.message().to_string())?;
+            let previous_index = previous.parent(0).map(|p| p.tree_id()).ok();
+            if previous.tree_id() == worktree_tree
+                && previous_index == Some(index_tree)
+                && previous.parent_id(1).ok() == head.as_ref().map(|h| h.id())
+            {
+                return Ok(None);
+            }
+        }
+    }
+
+    let sig = releash_signature(repo)?;
+    let head_parents: Vec<&git2::Commit<'_>> = head.iter().collect();
+    let index_commit = repo
+        .commit(
+            None,
+            &sig,
+            &sig,
+            &format!("index at checkpoint: {label}\n"),
+            &repo
+                .find_tree(index_tree)
+                .map_err(|e| e.message().to_string())?,
+            &head_parents,
+        )
+        .and_then(|oid| repo.find_commit(oid))
+        .map_err(|e| e.message().to_string())?;
+    let mut parents = vec![&index_commit];
+    parents.extend(head.iter());
+    let oid = repo
+        .commit(
+            None,
+            &sig,
+            &sig,
+            &format!("checkpoint: {label}\n"),
+            &repo
+                .find_tree(worktree_tree)
+                .map_err(|e| e.message().to_string())?,
+            &parents,
+        )
+        .map_err(|e| e.message().to_string())?;
+
+    let number = latest.map(|(_, number, _)| number + 1).unwrap_or(1);
+    repo.reference(
+        &format!("{CHECKPOINT_PREFIX}{session}/{number}"),
+        oid,
+        false,
+        &format!("checkpoint: {label}"),
+    )
+    .map_err(|e| e.message().to_string())?;
+
+    let commit = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
+    Ok(Some(checkpoint_info(session, number, &commit)))
+}
+
+pub fn auto_checkpoint(cwd: &str, session: &str) -> Result<Option<CheckpointInfo>, String> {
+    let repo = Repository::discover(cwd).map_err(|e| e.message().to_string())?;
+    create_checkpoint(&repo, session, "auto", true)
+}
+
+#[tauri::command]
+pub fn git_create_checkpoint(
+    repo_path: String,
+    session: String,
+    label: Option<String>,
+) -> Result<CheckpointInfo, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let label = label.unwrap_or_else(|| "manual".to_string());
+    create_checkpoint(&repo, &session, &label, false)?
+        .ok_or_else(|| "checkpoint was not created".to_string())
+}
+
+#[tauri::command]
+pub fn list_checkpoints(
+    repo_path: String,
+    session: Option<String>,
+) -> Result<Vec<CheckpointInfo>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let mut checkpoints = Vec::new();
+    for (session, number, oid) in checkpoint_refs(&repo, session.as_deref())? {
+        let commit = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
+        checkpoints.push(checkpoint_info(&session, number, &commit));
+    }
+    checkpoints.sort_by(|a, b| {
+        (b.timestamp, &b.session, b.number).cmp(&(a.timestamp, &a.session, a.number))
+    });
+    Ok(checkpoints)
+}
+
+#[tauri::command]
+pub fn get_checkpoint_diff(
+    repo_path: String,
+    from: String,
+    to: Option<String>,
+    context_lines: Option<u32>,
+) -> Result<Vec<FileDiff>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let from_tree = find_checkpoint(&repo, &from)?
+        .tree()
+        .map_err(|e| e.message().to_string())?;
+    let to_tree = match &to {
+        Some(id) => find_checkpoint(&repo, id)?.tree(),
+        None => {
+            let (worktree_tree, _) = snapshot_trees(&repo)?;
+            repo.find_tree(worktree_tree)
+        }
+    }
+    .map_err(|e| e.message().to_string())?;
+
+    let mut opts = git2::DiffOptions::new();
+    opts.context_lines(context_lines.unwrap_or(3));
+    let mut diff = repo
+        .diff_tree_to_tree(Some(&from_tree), Some(&to_tree), Some(&mut opts))
+        .map_err(|e| e.message().to_string())?;
+    detect_renames(&mut diff, false)?;
+    file_diffs_from_diff(&diff)
+}
+
+/// The current state is checkpointed first in the same session, and that
+/// checkpoint is returned so the restore itself can be undone.
+#[tauri::command]
+pub fn git_restore_checkpoint(repo_path: String, id: String) -> Result<CheckpointInfo, String> {
+    let touched = |repo: &Repository| checkpoint_paths(repo, &id);
+    journaled_worktree(&repo_path, "restore_checkpoint", touched, || {
+        restore_checkpoint(&repo_path, &id)
+    })
+}
+
+fn checkpoint_paths(repo: &Repository, id: &str) -> Vec<String> {
+    let Ok(tree) =
+        find_checkpoint(repo, id).and_then(|c| c.tree().map_err(|e| e.message().to_string()))
+    else {
+        return Vec::new();
+    };
+    let mut opts = git2::DiffOptions::new();
+    opts.include_untracked(true).recurse_untracked_dirs(true);
+    repo.diff_tree_to_workdir(Some(&tree), Some(&mut opts))
+        .map(|diff| delta_paths(&diff))
+        .unwrap_or_default()
+}
+
+fn restore_checkpoint(repo_path: &str, id: &str) -> Result<CheckpointInfo, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let checkpoint = find_checkpoint(&repo, id)?;
+    let session = id.split_once('/').map(|(s, _)| s).unwrap_or(id);
+    let index_tree = checkpoint
+        .parent(0)
+        .and_then(|p| p.tree())
+        .map_err(|e| e.message().to_string())?;
+
+    let backup = create_checkpoint(&repo, session, &format!("before restoring {id}"), false)?
+        .ok_or_else(|| "checkpoint was not created".to_string())?;
+
+    let tree = checkpoint.tree().map_err(|e| e.message().to_string())?;
+    let mut checkout = CheckoutBuilder::new();
+    checkout.force().remove_untracked(true);
+    repo.checkout_tree(tree.as_object(), Some(&mut checkout))
+        .map_err(|e| e.message().to_string())?;
+
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+    index
+        .read_tree(&index_tree)
+        .and_then(|_| index.write())
+        .map_err(|e| e.message().to_string())?;
+
+    Ok(backup)
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ReviewStore {
+    files: HashMap<String, ReviewedFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReviewedFile {
+    blob: Option<String>,
+    mode: Option<u32>,
+    reviewed_at: i64,
+}
+
+const REVIEW_STORE_FILE: &str = "reviewed.json";
+
+fn releash_state_path(repo: &Repository, name: &str) -> std::path::PathBuf {
+    repo.path().join("releash").join(name)
+}
+
+fn load_json<T: serde::de::DeserializeOwned>(repo: &Repository, name: &str) -> Option<T> {
+    std::fs::read(releash_state_path(repo, name))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+}
+
+fn save_json<T: Serialize + ?Sized>(
+    repo: &Repository,
+    name: &str,
+    value: &T,
+) -> Result<(), String> {
+    let path = releash_state_path(repo, name);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to save {name}: {e}"))?;
+    }
+    let json = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to save {name}: {e}"))
+}
+
+fn worktree_blob_id(workdir: &Path, rel: &str) -> Option<git2::Oid> {
+    let (content, _) = read_worktree_content(&workdir.join(rel)).ok()??;
+    git2::Oid::hash_object(git2::ObjectType::Blob, &content).ok()
+}
+
+fn review_state(store: &ReviewStore, workdir: Option<&Path>, path: &str) -> &'static str {
+    let Some(reviewed) = store.files.get(path) else {
+        return "never_reviewed";
+    };
+    let current = workdir
+        .and_then(|w| worktree_blob_id(w, path))
+        .map(|id| id.to_string());
+    if current == reviewed.blob {
+        "reviewed"
+    } else {
+        "changed_since_review"
+    }
+}
+
+#[tauri::command]
+pub fn git_mark_reviewed(repo_path: String, paths: Vec<String>) -> Result<(), String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "repository has no working directory".to_string())?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    let mut store = load_json::<ReviewStore>(&repo, REVIEW_STORE_FILE).unwrap_or_default();
+    for path in paths {
+        let entry = worktree_index_entry(&repo, workdir, &path)?;
+        store.files.insert(
+            path,
+            ReviewedFile {
+                blob: entry.as_ref().map(|e| e.id.to_string()),
+                mode: entry.map(|e| e.mode),
+                reviewed_at: now,
+            },
+        );
+    }
+    save_json(&repo, REVIEW_STORE_FILE, &store)
+}
+
+#[tauri::command]
+pub fn git_clear_reviewed(repo_path: String, paths: Option<Vec<String>>) -> Result<(), String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let mut store = load_json::<ReviewStore>(&repo, REVIEW_STORE_FILE).unwrap_or_default();
+    match paths {
+        Some(paths) => {
+            for path in paths {
+                store.files.remove(&path);
+            }
+        }
+        None => store.files.clear(),
+    }
+    save_json(&repo, REVIEW_STORE_FILE, &store)
+}
+
+/// Hunk patches are relative to the reviewed content, so they cannot be
+/// passed to `git_stage_hunk`.
+#[tauri::command]
+pub fn get_review_diff(
+    repo_path: String,
+    context_lines: Option<u32>,
+) -> Result<Vec<FileDiff>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "repository has no working directory".to_string())?;
+    let store = load_json::<ReviewStore>(&repo, REVIEW_STORE_FILE).unwrap_or_default();
+
+    let mut status_opts = StatusOptions::new();
+    status_opts
+        .include_untracked(true)
+        .recurse_untracked_dirs(true);
+    let statuses = repo
+        .statuses(Some(&mut status_opts))
+        .map_err(|e| e.message().to_string())?;
+    let mut candidates: Vec<String> = statuses
+        .iter()
+        .filter(|e| !e.status().is_ignored())
+        .filter_map(|e| e.path().map(|p| p.to_string()))
+        .chain(store.files.keys().cloned())
+        .filter(|path| review_state(&store, Some(workdir), path) != "reviewed")
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    if candidates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // One tree holding each candidate's base version, diffed to the worktree.
+    let head = head_tree(&repo)?;
+    let mut base = git2::Index::new().map_err(|e| e.message().to_string())?;
+    for path in &candidates {
+        let reviewed = store.files.get(path).and_then(|r| {
+            let id = git2::Oid::from_str(r.blob.as_deref()?).ok()?;
+            repo.find_blob(id)
+                .ok()
+                .map(|_| (id, r.mode.unwrap_or(0o100644)))
+        });
+        let from_head = || {
+            let entry = head.as_ref()?.get_path(Path::new(path)).ok()?;
+            Some((entry.id(), entry.filemode() as u32))
+        };
+        // A reviewed deletion has no base; a reviewed blob that has been
+        // garbage collected falls back to HEAD.
+        let reviewed_deletion = store.files.get(path).is_some_and(|r| r.blob.is_none());
+        let Some((id, mode)) = (if reviewed_deletion {
+            None
+        } else {
+            reviewed.or_else(from_head)
+        }) else {
+            continue;
+        };
+        base.add(&blob_index_entry(path, id, mode))
+            .map_err(|e| e.message().to_string())?;
+    }
+    let base_tree = repo
+        .find_tree(
+            base.write_tree_to(&repo)
+                .map_err(|e| e.message().to_string())?,
+        )
+        .map_err(|e| e.message().to_string())?;
+
+    let mut opts = git2::DiffOptions::new();
+    opts.context_lines(context_lines.unwrap_or(3))
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true)
+        .disable_pathspec_match(true);
+    for path in &candidates {
+        opts.pathspec(path);
+    }
+    let mut diff = repo
+        .diff_tree_to_workdir(Some(&base_tree), Some(&mut opts))
+        .map_err(|e| e.message().to_string())?;
+    detect_renames(&mut diff, true)?;
+    file_diffs_from_diff(&diff)
+}
+
+fn range_commits<'r>(repo: &'r Repository, range: &str) -> Result<Vec<git2::Commit<'r>>, String> {
+    let mut revwalk = repo.revwalk().map_err(|e| e.message().to_string())?;
+    let (from, to) = range_endpoints(repo, range)?;
+    revwalk.push(to.id()).map_err(|e| e.message().to_string())?;
+    if let Some(from) = from {
+        revwalk
+            .hide(from.id())
+            .map_err(|e| e.message().to_string())?;
+    }
+    revwalk
+        .set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)
+        .map_err(|e| e.message().to_string())?;
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        let oid = oid.map_err(|e| e.message().to_string())?;
+        let commit = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
+        if commit.parent_count() <= 1 {
+            commits.push(commit);
+        }
+    }
+    Ok(commits)
+}
+
+fn commit_diff<'r>(
+    repo: &'r Repository,
+    commit: &git2::Commit<'_>,
+    opts: &mut git2::DiffOptions,
+) -> Result<git2::Diff<'r>, String> {
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().map_err(|e| e.message().to_string())?),
+        Err(_) => None,
+    };
+    let tree = commit.tree().map_err(|e| e.message().to_string())?;
+    let mut diff = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(opts))
+        .map_err(|e| e.message().to_string())?;
+    detect_renames(&mut diff, false)?;
+    Ok(diff)
+}
+
+fn range_endpoints<'r>(
+    repo: &'r Repository,
+    spec: &str,
+) -> Result<(Option<git2::Commit<'r>>, git2::Commit<'r>), String> {
+    let revspec = repo
+        .revparse(spec)
+        .map_err(|e| format!("Invalid revision '{spec}': {}", e.message()))?;
+    let peel = |o: Option<&git2::Object<'r>>| -> Result<Option<git2::Commit<'r>>, String> {
+        o.map(|o| o.peel_to_commit())
+            .transpose()
+            .map_err(|e| e.message().to_string())
+    };
+    let from = peel(revspec.from())?;
+    let to = peel(revspec.to())?;
+    match (from, to) {
+        (Some(from), Some(to)) if revspec.mode().contains(git2::RevparseMode::MERGE_BASE) => {
+            let base = repo
+                .merge_base(from.id(), to.id())
+                .and_then(|oid| repo.find_commit(oid))
+                .map_err(|e| e.message().to_string())?;
+            Ok((Some(base), to))
+        }
+        (Some(from), Some(to)) => Ok((Some(from), to)),
+        (Some(single), None) | (None, Some(single)) => Ok((single.parent(0).ok(), single)),
+        (None, None) => Err(format!("Invalid revision '{spec}'")),
+    }
+}
+
+fn format_patch_file_name(number: usize, summary: &str) -> String {
+    let mut slug = String::new();
+    for c in summary.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let mut slug: String = slug.trim_end_matches(['-', '.']).chars().take(52).collect();
+    while slug.ends_with(['-', '.']) {
+        slug.pop();
+    }
+    format!("{number:04}-{slug}.patch")
+}
+
+#[tauri::command]
+pub fn git_export_patch(
+    repo_path: String,
+    source: String,
+    range: Option<String>,
+    output_path: String,
+) -> Result<(), String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let mut opts = git2::DiffOptions::new();
+    opts.show_binary(true);
+
+    let mut diff = match source.as_str() {
+        "staged" => {
+            let tree = head_tree(&repo)?;
+            repo.diff_tree_to_index(tree.as_ref(), None, Some(&mut opts))
+        }
+        "unstaged" => {
+            opts.include_untracked(true)
+                .recurse_untracked_dirs(true)
+                .show_untracked_content(true);
+            repo.diff_index_to_workdir(None, Some(&mut opts))
+        }
+        "range" => {
+            let range = range.ok_or("a commit range is required")?;
+            let (from, to) = range_endpoints(&repo, &range)?;
+            let from_tree = from
+                .map(|c| c.tree())
+                .transpose()
+                .map_err(|e| e.message().to_string())?;
+            let to_tree = to.tree().map_err(|e| e.message().to_string())?;
+            repo.diff_tree_to_tree(from_tree.as_ref(), Some(&to_tree), Some(&mut opts))
+        }
+        other => return Err(format!("unknown patch source: {other}")),
+    }
+    .map_err(|e| e.message().to_string())?;
+    detect_renames(&mut diff, source == "unstaged")?;
+
+    if diff.deltas().len() == 0 {
+        return Err("Nothing to export".to_string());
+    }
+    let mut patch = Vec::new();
+    diff.print(git2::DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            patch.push(line.origin() as u8);
+        }
+        patch.extend_from_slice(line.content());
+        true
+    })
+    .map_err(|e| e.message().to_string())?;
+    std::fs::write(&output_path, patch).map_err(|e| format!("Failed to write {output_path}: {e}"))
+}
+
+#[tauri::command]
+pub fn git_export_format_patch(
+    repo_path: String,
+    range: String,
+    output_dir: String,
+) -> Result<Vec<String>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let commits = range_commits(&repo, &range)?;
+    if commits.is_empty() {
+        return Err("Nothing to export".to_string());
+    }
+    std::fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create {output_dir}: {e}"))?;
+
+    let mut written = Vec::new();
+    for (idx, commit) in commits.iter().enumerate() {
+        let mut email_opts = git2::EmailCreateOptions::new();
+        email_opts.diff_options().show_binary(true);
+        let diff = commit_diff(&repo, commit, email_opts.diff_options())?;
+        let summary = commit.summary().unwrap_or("");
+        let email = git2::Email::from_diff(
+            &diff,
+            idx + 1,
+            commits.len(),
+            &commit.id(),
+            summary,
+            commit.body().unwrap_or(""),
+            &commit.author(),
+            &mut email_opts,
+        )
+        .map_err(|e| e.message().to_string())?;
+
+        let path = Path::new(&output_dir).join(format_patch_file_name(idx + 1, summary));
+        std::fs::write(&path, email.as_slice())
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+        written.push(path.to_string_lossy().to_string());
+    }
+    Ok(written)
+}
+
+/// The tip must be a branch or tag name so the bundle carries a ref.
+#[tauri::command]
+pub fn git_export_bundle(
+    repo_path: String,
+    range: String,
+    output_path: String,
+) -> Result<(), String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let (prerequisite_spec, tip_spec) = match range.split_once("..") {
+        Some((from, to)) => (Some(from), to.trim_start_matches('.')),
+        None => (None, range.as_str()),
+    };
+    let tip_spec = if tip_spec.is_empty() {
+        "HEAD"
+    } else {
+        tip_spec
+    };
+    let tip_ref = repo
+        .resolve_reference_from_short_name(tip_spec)
+        .map_err(|_| format!("'{tip_spec}' is not a branch or tag"))?;
+    let refname = match tip_ref.symbolic_target() {
+        Some(target) => target.to_string(),
+        None => tip_ref.name().ok_or("invalid ref name")?.to_string(),
+    };
+    let tip = tip_ref
+        .peel_to_commit()
+        .map_err(|e| e.message().to_string())?;
+
+    let mut revwalk = repo.revwalk().map_err(|e| e.message().to_string())?;
+    revwalk
+        .push(tip.id())
+        .map_err(|e| e.message().to_string())?;
+    let mut prerequisites = Vec::new();
+    if let Some(spec) = prerequisite_spec.filter(|s| !s.is_empty()) {
+        let from = resolve_commit(&repo, spec)?;
+        let boundary = if range.contains("...") {
+            repo.find_commit(
+                repo.merge_base(from.id(), tip.id())
+                    .map_err(|e| e.message().to_string())?,
+            )
+            .map_err(|e| e.message().to_string())?
+        } else {
+            from
+        };
+        revwalk
+            .hide(boundary.id())
+            .map_err(|e| e.message().to_string())?;
+        prerequisites.push(boundary);
+    }
+
+    let mut builder = repo.packbuilder().map_err(|e| e.message().to_string())?;
+    builder
+        .insert_walk(&mut revwalk)
+        .map_err(|e| e.message().to_string())?;
+    if builder.object_count() == 0 {
+        return Err("Nothing to export".to_string());
+    }
+    let mut pack = git2::Buf::new();
+    builder
+        .write_buf(&mut pack)
+        .map_err(|e| e.message().to_string())?;
+
+    let mut bundle = b"# v2 git bundle\n".to_vec();
+    for commit in &prerequisites {
+        bundle.extend_from_slice(
+            format!("-{} {}\n", commit.id(), commit.summary().unwrap_or("")).as_bytes(),
+        );
+    }
+    bundle.extend_from_slice(format!("{} {refname}\n\n", tip.id()).as_bytes());
+    bundle.extend_from_slice(&pack);
+    std::fs::write(&output_path, bundle).map_err(|e| format!("Failed to write {output_path}: {e}"))
+}
+
+#[derive(Serialize)]
+pub struct PatchHunkPreview {
+    pub header: String,
+    pub applies: bool,
+}
+
+#[derive(Serialize)]
+pub struct PatchFilePreview {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub status: String,
+    pub binary: bool,
+    pub applies: bool,
+    pub has_base: bool,
+    pub hunks: Vec<PatchHunkPreview>,
+}
+
+#[derive(Serialize)]
+pub struct PatchMessagePreview {
+    pub subject: Option<String>,
+    pub files: Vec<PatchFilePreview>,
+}
+
+#[derive(Serialize)]
+pub struct PatchPreview {
+    pub messages: Vec<PatchMessagePreview>,
+}
+
+#[derive(Serialize)]
+pub struct PatchApplyResult {
+    pub applied: Vec<String>,
+    pub conflicted: Vec<String>,
+    pub messages_applied: usize,
+    pub messages_total: usize,
+    pub failed: Option<HunkApplyError>,
+}
+
+struct PatchMessage {
+    subject: Option<String>,
+    text: String,
+}
+
+fn patch_location(target: &str) -> Result<git2::ApplyLocation, String> {
+    match target {
+        "worktree" => Ok(git2::ApplyLocation::WorkDir),
+        "index" => Ok(git2::ApplyLocation::Index),
+        "both" => Ok(git2::ApplyLocation::Both),
+        other => Err(format!("unknown apply target: {other}")),
+    }
+}
+
+fn split_patch_messages(patch: &str) -> Vec<PatchMessage> {
+    if !patch.starts_with("From ") {
+        return vec![PatchMessage {
+            subject: None,
+            text: patch.to_string(),
+        }];
+    }
+
+    let mut messages: Vec<String> = Vec::new();
+    for line in patch.split_inclusive('\n') {
+        match messages.last_mut() {
+            Some(current) if !line.starts_with("From ") => current.push_str(line),
+            _ => messages.push(line.to_string()),
+        }
+    }
+
+    let prefix = regex::Regex::new(r"^\[[^\]]*PATCH[^\]]*\]\s*").expect("valid regex");
+    messages
+        .into_iter()
+        .map(|text| {
+            let mut subject: Option<String> = None;
+            for line in text.lines().skip(1) {
+                if line.is_empty() {
+                    break;
+                }
+                if let Some(s) = subject.as_mut().filter(|_| line.starts_with([' ', '\t'])) {
+                    s.push(' ');
+                    s.push_str(line.trim());
+                } else if subject.is_some() {
+                    break;
+                } else if let Some(rest) = line.strip_prefix("Subject:") {
+                    subject = Some(rest.trim().to_string());
+                }
+            }
+            PatchMessage {
+                subject: subject.map(|s| prefix.replace(&s, "").to_string()),
+                text,
+            }
+        })
+        .collect()
+}
+
+fn split_file_sections(text: &str) -> Vec<String> {
+    let normalized = normalize_patch(text);
+    let mut sections: Vec<String> = Vec::new();
+    for line in normalized.split_inclusive('\n') {
+        if line.starts_with("diff --git ") {
+            sections.push(String::new());
+        }
+        if let Some(current) = sections.last_mut() {
+            current.push_str(line);
+        }
+    }
+    if sections.is_empty() {
+        sections.push(normalized);
+    }
+    sections
+}
+
+fn section_preimage(section: &str) -> Option<(String, u32)> {
+    let line = section.lines().find_map(|l| l.strip_prefix("index "))?;
+    let mut parts = line.split_whitespace();
+    let (old, _) = parts.next()?.split_once("..")?;
+    if old.is_empty() || old.bytes().all(|b| b == b'0') {
+        return None;
+    }
+    let mode = parts
+        .next()
+        .and_then(|m| u32::from_str_radix(m, 8).ok())
+        .unwrap_or(0o100644);
+    Some((old.to_string(), mode))
+}
+
+fn find_preimage<'r>(repo: &'r Repository, section: &str) -> Option<(git2::Blob<'r>, u32)> {
+    let (prefix, mode) = section_preimage(section)?;
+    let object = repo
+        .find_object_by_prefix(&prefix, Some(git2::ObjectType::Blob))
+        .ok()?;
+    Some((object.into_blob().ok()?, mode))
+}
+
+fn applies_to_tree(repo: &Repository, tree: &git2::Tree<'_>, patch: &[u8]) -> bool {
+    git2::Diff::from_buffer(patch)
+        .and_then(|diff| repo.apply_to_tree(tree, &diff, None))
+        .is_ok()
+}
+
+fn invalid_patch(message: impl Into<String>) -> HunkApplyError {
+    HunkApplyError {
+        reason: "invalid_patch".to_string(),
+        path: None,
+        hunk_index: None,
+        hunk_header: None,
+        message: message.into(),
+    }
+}
+
+fn patch_preview_tree(
+    repo: &Repository,
+    location: git2::ApplyLocation,
+) -> Result<git2::Oid, String> {
+    match location {
+        git2::ApplyLocation::Index => repo
+            .index()
+            .and_then(|mut index| index.write_tree())
+            .map_err(|e| e.message().to_string()),
+        _ => snapshot_trees(repo).map(|(worktree, _)| worktree),
+    }
+}
+
+#[tauri::command]
+pub fn preview_patch(
+    repo_path: String,
+    patch: String,
+    target: String,
+) -> Result<PatchPreview, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let location = patch_location(&target)?;
+    let mut tree_id = patch_preview_tree(&repo, location)?;
+
+    let mut messages = Vec::new();
+    for message in split_patch_messages(&patch) {
+        let normalized = normalize_patch(&message.text);
+        let diff = git2::Diff::from_buffer(normalized.as_bytes())
+            .map_err(|e| format!("Invalid patch: {}", e.message()))?;
+        let tree = repo
+            .find_tree(tree_id)
+            .map_err(|e| e.message().to_string())?;
+        let sections = split_file_sections(&message.text);
+
+        let mut files = Vec::new();
+        for (idx, file) in file_diffs_from_diff(&diff)?.into_iter().enumerate() {
+            let section = sections.get(idx).map(String::as_str).unwrap_or_default();
+            files.push(PatchFilePreview {
+                applies: applies_to_tree(&repo, &tree, section.as_bytes()),
+                has_base: find_preimage(&repo, section).is_some(),
+                hunks: file
+                    .hunks
+                    .iter()
+                    .map(|hunk| PatchHunkPreview {
+                        header: hunk.header.clone(),
+                        applies: decode_patch(&hunk.patch_base64)
+                            .is_ok_and(|patch| applies_to_tree(&repo, &tree, &patch)),
+                    })
+                    .collect(),
+                old_path: file.old_path,
+                new_path: file.new_path,
+                status: file.status,
+                binary: file.binary,
+            });
+        }
+
+        // Later messages of a series are checked on top of the earlier ones.
+        if let Ok(mut index) = repo.apply_to_tree(&tree, &diff, None) {
+            tree_id = index
+                .write_tree_to(&repo)
+                .map_err(|e| e.message().to_string())?;
+        }
+        messages.push(PatchMessagePreview {
+            subject: message.subject,
+            files,
+        });
+    }
+
+    Ok(PatchPreview { messages })
+}
+
+struct ThreeWayFile {
+    path: String,
+    merged: git2::Index,
+    conflicted: bool,
+}
+
+fn single_file_tree<'r>(
+    repo: &'r Repository,
+    path: &str,
+    id: git2::Oid,
+    mode: u32,
+) -> Result<git2::Tree<'r>, git2::Error> {
+    let mut index = git2::Index::new()?;
+    index.add(&blob_index_entry(path, id, mode))?;
+    let tree = index.write_tree_to(repo)?;
+    repo.find_tree(tree)
+}
+
+fn three_way_file(repo: &Repository, section: &str) -> Result<ThreeWayFile, HunkApplyError> {
+    let diff =
+        git2::Diff::from_buffer(section.as_bytes()).map_err(|e| invalid_patch(e.message()))?;
+    let delta = diff
+        .deltas()
+        .next()
+        .ok_or_else(|| invalid_patch("patch contains no file changes"))?;
+    let (Some(old_path), Some(path)) = (delta_path(delta.old_file()), delta_path(delta.new_file()))
+    else {
+        return Err(invalid_patch("patch has no file path"));
+    };
+    let failed = |reason: &str, message: String| HunkApplyError {
+        reason: reason.to_string(),
+        path: Some(path.clone()),
+        hunk_index: None,
+        hunk_header: None,
+        message,
+    };
+    if delta.flags().is_binary() {
+        return Err(failed(
+            "binary",
+            format!("{path}: binary patch does not apply"),
+        ));
+    }
+    if !matches!(delta.status(), git2::Delta::Modified | git2::Delta::Renamed) {
+        return Err(failed(
+            "context_mismatch",
+            format!("{path}: patch does not apply"),
+        ));
+    }
+    let Some((base, mode)) = find_preimage(repo, section) else {
+        return Err(failed(
+            "context_mismatch",
+            format!("{path}: patch does not apply and its base version is not in this repository"),
+        ));
+    };
+    let err = |e: git2::Error| failed("other", e.message().to_string());
+
+    // Their side: the patch applied to the exact version it was made against.
+    let mut preimage = git2::Index::new().map_err(err)?;
+    preimage
+        .add(&blob_index_entry(&old_path, base.id(), mode))
+        .map_err(err)?;
+    let preimage_tree = preimage.write_tree_to(repo).map_err(err)?;
+    let preimage_tree = repo.find_tree(preimage_tree).map_err(err)?;
+    let theirs_index = repo
+        .apply_to_tree(&preimage_tree, &diff, None)
+        .map_err(err)?;
+    let theirs = theirs_index
+        .get_path(Path::new(&path), 0)
+        .ok_or_else(|| failed("other", format!("{path}: patch produced no content")))?;
+
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| HunkApplyError::other("repository has no working directory"))?;
+    let Some((ours, ours_mode)) =
+        read_worktree_content(&workdir.join(&old_path)).map_err(|e| failed("other", e))?
+    else {
+        return Err(failed(
+            "missing_file",
+            format!("{old_path} does not exist in the worktree"),
+        ));
+    };
+
+    let ours = repo.blob(&ours).map_err(err)?;
+    let merged = repo
+        .merge_trees(
+            &single_file_tree(repo, &path, base.id(), mode).map_err(err)?,
+            &single_file_tree(repo, &path, ours, ours_mode).map_err(err)?,
+            &single_file_tree(repo, &path, theirs.id, theirs.mode).map_err(err)?,
+            None,
+        )
+        .map_err(err)?;
+    Ok(ThreeWayFile {
+        conflicted: merged.has_conflicts(),
+        merged,
+        path,
+    })
+}
+
+fn write_three_way_file(
+    repo: &Repository,
+    index: &mut git2::Index,
+    file: &mut ThreeWayFile,
+    old_path: &str,
+    location: git2::ApplyLocation,
+) -> Result<(), HunkApplyError> {
+    let err = |e: git2::Error| HunkApplyError::other(e.message());
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| HunkApplyError::other("repository has no working directory"))?;
+    if old_path != file.path {
+        std::fs::remove_file(workdir.join(old_path))
+            .map_err(|e| HunkApplyError::other(e.to_string()))?;
+    }
+    if file.conflicted {
+        let mut checkout = CheckoutBuilder::new();
+        checkout
+            .force()
+            .allow_conflicts(true)
+            .conflict_style_merge(true)
+            .our_label("ours")
+            .their_label("theirs")
+            .update_index(false)
+            .path(&file.path);
+        repo.checkout_index(Some(&mut file.merged), Some(&mut checkout))
+            .map_err(err)?;
+    } else {
+        let entry = file
+            .merged
+            .get_path(Path::new(&file.path), 0)
+            .ok_or_else(|| {
+                HunkApplyError::other(format!("{}: merge produced no content", file.path))
+            })?;
+        let blob = repo.find_blob(entry.id).map_err(err)?;
+        write_worktree_file(&workdir.join(&file.path), blob.content(), entry.mode)
+            .map_err(HunkApplyError::other)?;
+    }
+    if !matches!(location, git2::ApplyLocation::Both) {
+        return Ok(());
+    }
+
+    if old_path != file.path {
+        index.remove_path(Path::new(old_path)).map_err(err)?;
+    }
+    if !file.conflicted {
+        return index.add_path(Path::new(&file.path)).map_err(err);
+    }
+    index.remove_path(Path::new(&file.path)).map_err(err)?;
+    for entry in file.merged.iter() {
+        index.add(&entry).map_err(err)?;
+    }
+    Ok(())
+}
+
+/// When a later message of a series fails, the earlier ones stay applied and
+/// the error comes back in `failed`.
+#[tauri::command]
+pub fn git_apply_patch(
+    repo_path: String,
+    patch: String,
+    target: String,
+    three_way: Option<bool>,
+) -> Result<PatchApplyResult, HunkApplyError> {
+    let touched = |_: &Repository| patch_paths(patch.as_bytes());
+    journaled_worktree(&repo_path, "apply_patch", touched, || {
+        apply_patch_series(&repo_path, patch.clone(), target, three_way)
+    })
+}
+
+fn apply_patch_message(
+    repo: &Repository,
+    message: &PatchMessage,
+    location: git2::ApplyLocation,
+    three_way: bool,
+    result: &mut PatchApplyResult,
+) -> Result<bool, HunkApplyError> {
+    let normalized = normalize_patch(&message.text);
+    let diff =
+        git2::Diff::from_buffer(normalized.as_bytes()).map_err(|e| invalid_patch(e.message()))?;
+    let files = file_diffs_from_diff(&diff).map_err(HunkApplyError::other)?;
+    if files.is_empty() {
+        return Err(invalid_patch("patch contains no file changes"));
+    }
+    let paths = || {
+        files
+            .iter()
+            .filter_map(|f| f.new_path.clone().or(f.old_path.clone()))
+    };
+
+    let mut check = git2::ApplyOptions::new();
+    check.check(true);
+    if !three_way || repo.apply(&diff, location, Some(&mut check)).is_ok() {
+        apply_patch(repo, message.text.as_bytes(), location, false)?;
+        result.applied.extend(paths());
+        result.messages_applied += 1;
+        return Ok(false);
+    }
+
+    let mut clean = Vec::new();
+    let mut merged = Vec::new();
+    for (file, section) in files.iter().zip(split_file_sections(&message.text)) {
+        let section_diff =
+            git2::Diff::from_buffer(section.as_bytes()).map_err(|e| invalid_patch(e.message()))?;
+        if repo
+            .apply(&section_diff, location, Some(&mut check))
+            .is_ok()
+        {
+            clean.push(section);
+        } else {
+            let old_path = file.old_path.clone().unwrap_or_default();
+            merged.push((three_way_file(repo, &section)?, old_path));
+        }
+    }
+
+    for section in &clean {
+        apply_patch(repo, section.as_bytes(), location, false)?;
+    }
+    let mut index = repo
+        .index()
+        .map_err(|e| HunkApplyError::other(e.message()))?;
+    for (file, old_path) in &mut merged {
+        write_three_way_file(repo, &mut index, file, old_path, location)?;
+    }
+    index
+        .write()
+        .map_err(|e| HunkApplyError::other(e.message()))?;
+
+    let mut stop = false;
+    for path in paths() {
+        match merged.iter().find(|(f, _)| f.path == path) {
+            Some((file, _)) if file.conflicted => {
+                stop = true;
+                result.conflicted.push(path);
+            }
+            _ => result.applied.push(path),
+        }
+    }
+    result.messages_applied += 1;
+    Ok(stop)
+}
+
+fn apply_patch_series(
+    repo_path: &str,
+    patch: String,
+    target: String,
+    three_way: Option<bool>,
+) -> Result<PatchApplyResult, HunkApplyError> {
+    let repo = Repository::open(repo_path).map_err(|e| HunkApplyError::other(e.message()))?;
+    let location = patch_location(&target).map_err(HunkApplyError::other)?;
+    let three_way = three_way.unwrap_or(true) && !matches!(location, git2::ApplyLocation::Index);
+
+    let messages = split_patch_messages(&patch);
+    let mut result = PatchApplyResult {
+        applied: Vec::new(),
+        conflicted: Vec::new(),
+        messages_applied: 0,
+        messages_total: messages.len(),
+        failed: None,
+    };
+
+    for message in &messages {
+        match apply_patch_message(&repo, message, location, three_way, &mut result) {
+            Ok(false) => {}
+            Ok(true) => break,
+            Err(e) if result.messages_applied == 0 => return Err(e),
+            Err(e) => {
+                result.failed = Some(e);
+                break;
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+#[derive(Serialize)]
+pub struct PickResult {
+    pub commit: Option<String>,
+    pub conflicts: Vec<String>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct RepoState {
+    pub state: String,
+    pub head: Option<String>,
+    pub message: Option<String>,
+}
+
+fn conflicted_paths(index: &git2::Index) -> Result<Vec<String>, String> {
+    let mut paths = Vec::new();
+    for conflict in index.conflicts().map_err(|e| e.message().to_string())? {
+        let conflict = conflict.map_err(|e| e.message().to_string())?;
+        if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+            paths.push(String::from_utf8_lossy(&entry.path).to_string());
+        }
+    }
+    Ok(paths)
+}
+
+fn pick_commit(
+    repo_path: &str,
+    hash: &str,
+    revert: bool,
+    no_commit: bool,
+    mainline: Option<u32>,
+    emit: &dyn Fn(HookOutput),
+) -> Result<PickResult, CommitError> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    if repo.state() != git2::RepositoryState::Clean {
+        return Err("another operation is in progress; commit or abort it first"
+            .to_string()
+            .into());
+    }
+    let commit = resolve_commit(&repo, hash)?;
+    if commit.parent_count() > 1 && mainline.is_none() {
+        return Err(format!("{hash} is a merge commit; choose a mainline parent").into());
+    }
+    let head_tree = head_tree(&repo)?.ok_or_else(|| "HEAD has no commit yet".to_string())?;
+
+    if revert {
+        let mut opts = git2::RevertOptions::new();
+        if let Some(mainline) = mainline {
+            opts.mainline(mainline);
+        }
+        repo.revert(&commit, Some(&mut opts))
+    } else {
+        let mut opts = git2::CherrypickOptions::new();
+        if let Some(mainline) = mainline {
+            opts.mainline(mainline);
+        }
+        repo.cherrypick(&commit, Some(&mut opts))
+    }
+    .map_err(|e| e.message().to_string())?;
+
+    let message = std::fs::read_to_string(repo.path().join("MERGE_MSG")).unwrap_or_default();
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+    let conflicts = conflicted_paths(&index)?;
+    if !conflicts.is_empty() {
+        return Ok(PickResult {
+            commit: None,
+            conflicts,
+            message,
+        });
+    }
+
+    let unchanged = index.write_tree().map_err(|e| e.message().to_string())? == head_tree.id();
+    if no_commit || unchanged {
+        // `-n` leaves the change staged without an operation in progress.
+        repo.cleanup_state().map_err(|e| e.message().to_string())?;
+        if unchanged {
+            return Err(format!("{hash} introduces no changes on top of HEAD").into());
+        }
+        return Ok(PickResult {
+            commit: None,
+            conflicts,
+            message,
+        });
+    }
+
+    // Like `git cherry-pick`, only the message hooks run.
+    let options = CommitOptions {
+        skip_hooks: true,
+        ..Default::default()
+    };
+    let oid = create_commit(repo_path, message.clone(), options, emit)?;
+    Ok(PickResult {
+        commit: Some(oid),
+        conflicts,
+        message,
+    })
+}
+
+#[tauri::command(async)]
+pub fn git_cherry_pick(
+    app: AppHandle,
+    repo_path: String,
+    hash: String,
+    no_commit: Option<bool>,
+    mainline: Option<u32>,
+) -> Result<PickResult, CommitError> {
+    let touched = |repo: &Repository| commit_paths(repo, &hash);
+    journaled_worktree(&repo_path, "cherry_pick", touched, || {
+        pick_commit(
+            &repo_path,
+            &hash,
+            false,
+            no_commit.unwrap_or(false),
+            mainline,
+            &|output| {
+                let _ = app.emit(HOOK_OUTPUT_EVENT, output);
+            },
+        )
+    })
+}
+
+#[tauri::command(async)]
+pub fn git_revert(
+    app: AppHandle,
+    repo_path: String,
+    hash: String,
+    no_commit: Option<bool>,
+    mainline: Option<u32>,
+) -> Result<PickResult, CommitError> {
+    let touched = |repo: &Repository| commit_paths(repo, &hash);
+    journaled_worktree(&repo_path, "revert", touched, || {
+        pick_commit(
+            &repo_path,
+            &hash,
+            true,
+            no_commit.unwrap_or(false),
+            mainline,
+            &|output| {
+                let _ = app.emit(HOOK_OUTPUT_EVENT, output);
+            },
+        )
+    })
+}
+
+#[tauri::command]
+pub fn git_abort_pick(repo_path: String) -> Result<(), String> {
+    let touched = |repo: &Repository| pick_paths(repo).unwrap_or_default();
+    journaled_worktree(&repo_path, "abort_pick", touched, || abort_pick(&repo_path))
+}
+
+fn pick_paths(repo: &Repository) -> Result<Vec<String>, String> {
+    let head_tree = repo
+        .head()
+        .and_then(|h| h.peel_to_tree())
+        .map_err(|e| e.message().to_string())?;
+    let index = repo.index().map_err(|e| e.message().to_string())?;
+    let mut paths = conflicted_paths(&index)?;
+    let diff = repo
+        .diff_tree_to_index(Some(&head_tree), None, None)
+        .map_err(|e| e.message().to_string())?;
+    for path in delta_paths(&diff) {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+fn abort_pick(repo_path: &str) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    if !matches!(
+        repo.state(),
+        git2::RepositoryState::CherryPick | git2::RepositoryState::Revert
+    ) {
+        return Err("no cherry-pick or revert in progress".to_string());
+    }
+    let head = repo
+        .head()
+        .and_then(|h| h.peel_to_commit())
+        .map_err(|e| e.message().to_string())?;
+    let paths = pick_paths(&repo)?;
+    if paths.is_empty() {
+        return repo.cleanup_state().map_err(|e| e.message().to_string());
+    }
+    // reset_default rejects conflict stages of paths that HEAD lacks.
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+    for path in conflicted_paths(&index)? {
+        index
+            .remove_path(Path::new(&path))
+            .map_err(|e| e.message().to_string())?;
+    }
+    index.write().map_err(|e| e.message().to_string())?;
+    repo.reset_default(Some(head.as_object()), &paths)
+        .map_err(|e| e.message().to_string())?;
+
+    let mut checkout = CheckoutBuilder::new();
+    checkout.force().remove_untracked(true);
+    for path in &paths {
+        checkout.path(path);
+    }
+    repo.checkout_head(Some(&mut checkout))
+        .map_err(|e| e.message().to_string())?;
+    repo.cleanup_state().map_err(|e| e.message().to_string())
+}
+
+#[tauri::command]
+pub fn get_repo_state(repo_path: String) -> Result<RepoState, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let (state, head_file) = match repo.state() {
+        git2::RepositoryState::Clean => ("clean", None),
+        git2::RepositoryState::Merge => ("merge", Some("MERGE_HEAD")),
+        git2::RepositoryState::CherryPick | git2::RepositoryState::CherryPickSequence => {
+            ("cherry_pick", Some("CHERRY_PICK_HEAD"))
+        }
+        git2::RepositoryState::Revert | git2::RepositoryState::RevertSequence => {
+            ("revert", Some("REVERT_HEAD"))
+        }
+        git2::RepositoryState::Bisect => ("bisect", None),
+        git2::RepositoryState::ApplyMailbox | git2::RepositoryState::ApplyMailboxOrRebase => {
+            ("apply_mailbox", None)
+        }
+        _ => ("rebase", None),
+    };
+    if state == "clean" {
+        if let Some(rewrite) = load_json::<RewriteState>(&repo, REWRITE_STATE_FILE) {
+            return Ok(RepoState {
+                state: "rewrite".to_string(),
+                head: Some(rewrite.stopped.hash),
+                message: None,
+            });
+        }
+    }
+    let read = |name: &str| std::fs::read_to_string(repo.path().join(name)).ok();
+    Ok(RepoState {
+        state: state.to_string(),
+        head: head_file
+            .and_then(read)
+            .and_then(|s| s.lines().next().map(|l| l.trim().to_string())),
+        message: if state == "clean" {
+            None
+        } else {
+            read("MERGE_MSG")
+        },
+    })
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RewriteStep {
+    pub action: String,
+    pub hash: String,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RewriteStatus {
+    pub state: String,
+    pub head: String,
+    pub backup_ref: String,
+    pub stopped_at: Option<String>,
+    pub conflicts: Vec<String>,
+    pub remaining: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+struct RewriteState {
+    branch: String,
+    original: String,
+    backup_ref: String,
+    current: String,
+    stopped: RewriteStep,
+    remaining: Vec<RewriteStep>,
+}
+
+const REWRITE_BACKUP_PREFIX: &str = "refs/releash/rewrites/";
+
+const REWRITE_STATE_FILE: &str = "rewrite.json";
+
+fn timestamped_ref(repo: &Repository, prefix: &str) -> String {
+    let mut id = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    while repo.find_reference(&format!("{prefix}{id}")).is_ok() {
+        id += 1;
+    }
+    format!("{prefix}{id}")
+}
+
+fn rewrite_base(
+    repo: &Repository,
+    tip: &git2::Commit<'_>,
+    steps: &[RewriteStep],
+) -> Result<git2::Oid, String> {
+    let mut planned = std::collections::HashSet::new();
+    let mut first_kept = true;
+    for step in steps {
+        match step.action.as_str() {
+            "pick" | "drop" => {}
+            "reword"
+                if step
+                    .message
+                    .as_deref()
+                    .is_some_and(|m| !m.trim().is_empty()) => {}
+            "reword" => return Err(format!("reword of {} needs a message", step.hash)),
+            "squash" | "fixup" if first_kept => {
+                return Err(format!(
+                    "cannot {} {} without a previous commit",
+                    step.action, step.hash
+                ))
+            }
+            "squash" | "fixup" => {}
+            other => return Err(format!("unknown rewrite action: {other}")),
+        }
+        first_kept &= step.action == "drop";
+        if !planned.insert(resolve_commit(repo, &step.hash)?.id()) {
+            return Err(format!("{} appears twice in the plan", step.hash));
+        }
+    }
+    if planned.is_empty() {
+        return Err("the rewrite plan is empty".to_string());
+    }
+
+    let mut commit = tip.clone();
+    for _ in 0..planned.len() {
+        if !planned.contains(&commit.id()) {
+            return Err(format!(
+                "the plan must list every commit down from HEAD; {} is missing",
+                &commit.id().to_string()[..7]
+            ));
+        }
+        match commit.parent_count() {
+            1 => {}
+            0 => return Err("the root commit cannot be rewritten".to_string()),
+            _ => {
+                return Err(format!(
+                    "{} is a merge commit",
+                    &commit.id().to_string()[..7]
+                ))
+            }
+        }
+        commit = commit.parent(0).map_err(|e| e.message().to_string())?;
+    }
+    Ok(commit.id())
+}
+
+fn commit_rewrite_step(
+    repo: &Repository,
+    sig: &git2::Signature<'_>,
+    current: &git2::Commit<'_>,
+    picked: &git2::Commit<'_>,
+    step: &RewriteStep,
+    tree: &git2::Tree<'_>,
+) -> Result<git2::Oid, String> {
+    let picked_message = picked.message().unwrap_or("");
+    let current_message = current.message().unwrap_or("");
+    let (author, message, parents) = match step.action.as_str() {
+        "squash" | "fixup" => {
+            let message = match (step.action.as_str(), &step.message) {
+                (_, Some(message)) => message.clone(),
+                ("fixup", None) => current_message.to_string(),
+                _ => format!(
+                    "{}\n\n{}\n",
+                    current_message.trim_end(),
+                    picked_message.trim_end()
+                ),
+            };
+            (
+                current.author(),
+                message,
+                current.parents().collect::<Vec<_>>(),
+            )
+        }
+        _ => (
+            picked.author(),
+            step.message
+                .clone()
+                .filter(|_| step.action == "reword")
+                .unwrap_or_else(|| picked_message.to_string()),
+            vec![current.clone()],
+        ),
+    };
+    let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+    repo.commit(None, &author, sig, &message, tree, &parent_refs)
+        .map_err(|e| e.message().to_string())
+}
+
+fn replay_rewrite_steps(
+    repo: &Repository,
+    sig: &git2::Signature<'_>,
+    mut current: git2::Oid,
+    steps: &[RewriteStep],
+) -> Result<(git2::Oid, Option<(usize, git2::Index)>), String> {
+    for (idx, step) in steps.iter().enumerate() {
+        if step.action == "drop" {
+            continue;
+        }
+        let picked = resolve_commit(repo, &step.hash)?;
+        if step.action == "pick" && picked.parent_id(0).ok() == Some(current) {
+            current = picked.id();
+            continue;
+        }
+        let base = repo
+            .find_commit(current)
+            .map_err(|e| e.message().to_string())?;
+        let mut index = repo
+            .cherrypick_commit(&picked, &base, 0, None)
+            .map_err(|e| e.message().to_string())?;
+        if index.has_conflicts() {
+            return Ok((current, Some((idx, index))));
+        }
+        let tree = index
+            .write_tree_to(repo)
+            .and_then(|id| repo.find_tree(id))
+            .map_err(|e| e.message().to_string())?;
+        current = commit_rewrite_step(repo, sig, &base, &picked, step, &tree)?;
+    }
+    Ok((current, None))
+}
+
+fn stop_rewrite(
+    repo: &Repository,
+    current: git2::Oid,
+    mut merged: git2::Index,
+) -> Result<Vec<String>, String> {
+    let commit = repo
+        .find_commit(current)
+        .map_err(|e| e.message().to_string())?;
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))
+        .map_err(|e| e.message().to_string())?;
+    repo.set_head_detached(current)
+        .map_err(|e| e.message().to_string())?;
+
+    let mut checkout = CheckoutBuilder::new();
+    checkout
+        .safe()
+        .allow_conflicts(true)
+        .conflict_style_merge(true);
+    repo.checkout_index(Some(&mut merged), Some(&mut checkout))
+        .map_err(|e| e.message().to_string())?;
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+    index.clear().map_err(|e| e.message().to_string())?;
+    for entry in merged.iter() {
+        index.add(&entry).map_err(|e| e.message().to_string())?;
+    }
+    index.write().map_err(|e| e.message().to_string())?;
+    conflicted_paths(&index)
+}
+
+fn finish_rewrite(repo: &Repository, branch: &str, tip: git2::Oid) -> Result<(), String> {
+    let commit = repo.find_commit(tip).map_err(|e| e.message().to_string())?;
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))
+        .map_err(|e| e.message().to_string())?;
+    repo.reference(branch, tip, true, "releash rewrite: finish")
+        .map_err(|e| e.message().to_string())?;
+    repo.set_head(branch).map_err(|e| e.message().to_string())
+}
+
+fn settle_rewrite(
+    repo: &Repository,
+    mut state: RewriteState,
+    current: git2::Oid,
+    steps: &[RewriteStep],
+) -> Result<RewriteStatus, String> {
+    let sig = repo.signature().map_err(|e| e.message().to_string())?;
+    let (current, stop) = replay_rewrite_steps(repo, &sig, current, steps)?;
+    let Some((idx, merged)) = stop else {
+        finish_rewrite(repo, &state.branch, current)?;
+        let _ = std::fs::remove_file(releash_state_path(repo, REWRITE_STATE_FILE));
+        return Ok(RewriteStatus {
+            state: "done".to_string(),
+            head: current.to_string(),
+            backup_ref: state.backup_ref,
+            stopped_at: None,
+            conflicts: Vec::new(),
+            remaining: 0,
+        });
+    };
+
+    let conflicts = stop_rewrite(repo, current, merged)?;
+    state.current = current.to_string();
+    state.stopped = steps[idx].clone();
+    state.remaining = steps[idx + 1..].to_vec();
+    save_json(repo, REWRITE_STATE_FILE, &state)?;
+    Ok(RewriteStatus {
+        state: "stopped".to_string(),
+        head: state.current,
+        backup_ref: state.backup_ref,
+        stopped_at: Some(state.stopped.hash),
+        conflicts,
+        remaining: state.remaining.len(),
+    })
+}
+
+#[tauri::command]
+pub fn git_rewrite_history(
+    repo_path: String,
+    steps: Vec<RewriteStep>,
+) -> Result<RewriteStatus, String> {
+    let touched = |repo: &Repository| rewrite_step_paths(repo, &steps);
+    journaled_worktree(&repo_path, "rewrite_history", touched, || {
+        rewrite_history(&repo_path, &steps)
+    })
+}
+
+fn rewrite_step_paths(repo: &Repository, steps: &[RewriteStep]) -> Vec<String> {
+    let mut paths: Vec<String> = steps
+        .iter()
+        .flat_map(|step| commit_paths(repo, &step.hash))
+        .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+fn rewrite_state_paths(repo: &Repository) -> Vec<String> {
+    let Some(state) = load_json::<RewriteState>(repo, REWRITE_STATE_FILE) else {
+        return Vec::new();
+    };
+    let mut steps = vec![state.stopped];
+    steps.extend(state.remaining);
+    let mut paths = rewrite_step_paths(repo, &steps);
+    paths.extend(checkout_paths(repo, &state.original));
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+fn rewrite_history(repo_path: &str, steps: &[RewriteStep]) -> Result<RewriteStatus, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    if repo.state() != git2::RepositoryState::Clean
+        || load_json::<RewriteState>(&repo, REWRITE_STATE_FILE).is_some()
+    {
+        return Err("another operation is in progress; finish or abort it first".to_string());
+    }
+    let head = repo.head().map_err(|e| e.message().to_string())?;
+    let branch = head
+        .name()
+        .filter(|_| head.is_branch())
+        .ok_or_else(|| "HEAD is detached; check out a branch first".to_string())?
+        .to_string();
+    let tip = head.peel_to_commit().map_err(|e| e.message().to_string())?;
+    let base = rewrite_base(&repo, &tip, steps)?;
+
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false);
+    let dirty = repo
+        .statuses(Some(&mut opts))
+        .map_err(|e| e.message().to_string())?;
+    if !dirty.is_empty() {
+        return Err("commit or stash your changes before rewriting history".to_string());
+    }
+
+    let backup_ref = timestamped_ref(&repo, REWRITE_BACKUP_PREFIX);
+    repo.reference(
+        &backup_ref,
+        tip.id(),
+        false,
+        &format!("releash rewrite: backup of {branch}"),
+    )
+    .map_err(|e| e.message().to_string())?;
+    let state = RewriteState {
+        branch,
+        original: tip.id().to_string(),
+        backup_ref,
+        current: base.to_string(),
+        stopped: steps[0].clone(),
+        remaining: Vec::new(),
+    };
+    settle_rewrite(&repo, state, base, steps)
+}
+
+#[tauri::command]
+pub fn git_rewrite_continue(
+    repo_path: String,
+    message: Option<String>,
+) -> Result<RewriteStatus, String> {
+    journaled_worktree(&repo_path, "rewrite_continue", rewrite_state_paths, || {
+        rewrite_continue(&repo_path, message)
+    })
+}
+
+fn rewrite_continue(repo_path: &str, message: Option<String>) -> Result<RewriteStatus, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let mut state = load_json::<RewriteState>(&repo, REWRITE_STATE_FILE)
+        .ok_or_else(|| "no history rewrite in progress".to_string())?;
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+    if index.has_conflicts() {
+        return Err("resolve and stage all conflicts before continuing".to_string());
+    }
+
+    let tree = index
+        .write_tree()
+        .and_then(|id| repo.find_tree(id))
+        .map_err(|e| e.message().to_string())?;
+    let current = resolve_commit(&repo, &state.current)?;
+    let picked = resolve_commit(&repo, &state.stopped.hash)?;
+    if message.is_some() {
+        if state.stopped.action == "pick" {
+            state.stopped.action = "reword".to_string();
+        }
+        state.stopped.message = message;
+    }
+    let sig = repo.signature().map_err(|e| e.message().to_string())?;
+    let committed = commit_rewrite_step(&repo, &sig, &current, &picked, &state.stopped, &tree)?;
+    // The worktree now matches this commit, which keeps later checkouts safe.
+    repo.set_head_detached(committed)
+        .map_err(|e| e.message().to_string())?;
+
+    let remaining = std::mem::take(&mut state.remaining);
+    settle_rewrite(&repo, state, committed, &remaining)
+}
+
+#[tauri::command]
+pub fn git_rewrite_abort(repo_path: String) -> Result<(), String> {
+    journaled_worktree(&repo_path, "rewrite_abort", rewrite_state_paths, || {
+        rewrite_abort(&repo_path)
+    })
+}
+
+fn rewrite_abort(repo_path: &str) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let state = load_json::<RewriteState>(&repo, REWRITE_STATE_FILE)
+        .ok_or_else(|| "no history rewrite in progress".to_string())?;
+    let original = resolve_commit(&repo, &state.original)?;
+    repo.reference(&state.branch, original.id(), true, "releash rewrite: abort")
+        .map_err(|e| e.message().to_string())?;
+    repo.set_head(&state.branch)
+        .map_err(|e| e.message().to_string())?;
+    repo.reset(original.as_object(), git2::ResetType::Hard, None)
+        .map_err(|e| e.message().to_string())?;
+    std::fs::remove_file(releash_state_path(&repo, REWRITE_STATE_FILE))
+        .map_err(|e| format!("Failed to clear rewrite state: {e}"))
+}
+
+#[derive(Serialize)]
+pub struct ResetBackup {
+    pub id: String,
+    pub ref_name: String,
+    pub head: String,
+    pub summary: String,
+    pub description: String,
+    pub timestamp: i64,
+}
+
+#[derive(Serialize)]
+pub struct ResetResult {
+    pub backup: ResetBackup,
+    pub checkpoint: Option<CheckpointInfo>,
+}
+
+const RESET_BACKUP_PREFIX: &str = "refs/releash/backup/";
+
+fn reset_backup(repo: &Repository, reference: &git2::Reference<'_>) -> Option<ResetBackup> {
+    let ref_name = reference.name()?;
+    let id = ref_name.strip_prefix(RESET_BACKUP_PREFIX)?;
+    let millis: i64 = id.parse().ok()?;
+    let commit = reference.peel_to_commit().ok()?;
+    let description = repo
+        .reflog(ref_name)
+        .ok()
+        .and_then(|log| log.get(0).and_then(|e| e.message().map(str::to_string)))
+        .unwrap_or_default();
+    Some(ResetBackup {
+        id: id.to_string(),
+        ref_name: ref_name.to_string(),
+        head: commit.id().to_string(),
+        summary: commit.summary().unwrap_or("").to_string(),
+        description,
+        timestamp: millis / 1000,
+    })
+}
+
+fn reset_backups(repo: &Repository) -> Result<Vec<ResetBackup>, String> {
+    let refs = repo
+        .references_glob(&format!("{RESET_BACKUP_PREFIX}*"))
+        .map_err(|e| e.message().to_string())?;
+    let mut backups: Vec<ResetBackup> = refs
+        .flatten()
+        .filter_map(|r| reset_backup(repo, &r))
+        .collect();
+    backups.sort_by_key(|b| std::cmp::Reverse(b.id.parse::<u128>().unwrap_or(0)));
+    Ok(backups)
+}
+
+/// A hard reset over uncommitted changes also checkpoints them.
+#[tauri::command]
+pub fn git_reset(repo_path: String, target: String, mode: String) -> Result<ResetResult, String> {
+    let touched = |repo: &Repository| match mode.as_str() {
+        "hard" => reset_paths(repo, &target),
+        _ => Vec::new(),
+    };
+    journaled_worktree(&repo_path, "reset", touched, || {
+        reset_to(&repo_path, target.clone(), mode.clone())
+    })
+}
+
+fn reset_to(repo_path: &str, target: String, mode: String) -> Result<ResetResult, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.message().to_string())?;
+    let reset_type = match mode.as_str() {
+        "soft" => git2::ResetType::Soft,
+        "mixed" => git2::ResetType::Mixed,
+        "hard" => git2::ResetType::Hard,
+        other => return Err(format!("unknown reset mode: {other}")),
+    };
+    if load_json::<RewriteState>(&repo, REWRITE_STATE_FILE).is_some() {
+        return Err("a history rewrite is in progress; continue or abort it first".to_string());
+    }
+    let target_commit = resolve_commit(&repo, &target)?;
+    let head = repo
+        .head()
+        .and_then(|h| h.peel_to_commit())
+        .map_err(|e| e.message().to_string())?;
+
+    let checkpoint = if matches!(reset_type, git2::ResetType::Hard) {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(false);
+        let dirty = !repo
+            .statuses(Some(&mut opts))
+            .map_err(|e| e.message().to_string())?
+            .is_empty();
+        if dirty {
+            let label = format!(
+                "before hard reset to {}",
+                &target_commit.id().to_string()[..7]
+            );
+            create_checkpoint(&repo, "reset", &label, false)?
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    let ref_name = timestamped_ref(&repo, RESET_BACKUP_PREFIX);
+    let description = format!(
+        "before {mode} reset to {}",
+        &target_commit.id().to_string()[..7]
+    );
+    let reference = repo
+        .reference(&ref_name, head.id(), false, &description)
+        .map_err(|e| e.message().to_string())?;
+    // Refs outside refs/heads only get a reflog with core.logAllRefUpdates=always.
+    let mut reflog = repo
+        .reflog(&ref_name)
+        .map_err(|e| e.message().to_string())?;
+    if reflog.is_empty() {
+        reflog
+            .append(head.id(), &releash_signature(&repo)?, Some(&description))
+            .and_then(|_| reflog.write())
+            .map_err(|e| e.message().to_string())?;
+    }
+    let backup = reset_backup(&repo, &reference).ok_or("failed to read the backup ref")?;
+
+    repo.reset(target_commit.as_object(), reset_type, None)
+        .map_err(|e| e.message().to_string())?;
+    Ok(ResetResult { backup, checkpoint })
+}
+
+#[tauri::command]
+pub fn list_reset_backups(repo_path: String) -> Result<Vec<ResetBackup>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    reset_backups(&repo)
+}
+
+/// `index_tree` is `None` with unresolved conflicts.
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize)]
+pub struct OperationState {
+    pub head: Option<String>,
+    pub head_ref: Option<String>,
+    pub index_tree: Option<String>,
+    pub worktree_tree: Option<String>,
+    pub branches: std::collections::BTreeMap<String, String>,
+    pub stashes: Vec<String>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct OperationEntry {
+    pub id: u64,
+    pub operation: String,
+    pub timestamp: i64,
+    pub before: OperationState,
+    pub after: OperationState,
+    pub worktree_paths: Vec<String>,
+    pub undone: bool,
+}
+
+const MAX_JOURNAL_ENTRIES: usize = 100;
+const JOURNAL_PREFIX: &str = "refs/releash/journal/";
+
+const JOURNAL_FILE: &str = "journal.json";
+
+fn worktree_paths_tree(repo: &Repository, paths: &[String]) -> Result<git2::Oid, String> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "repository has no working directory".to_string())?;
+    let mut snapshot = git2::Index::new().map_err(|e| e.message().to_string())?;
+    for path in paths {
+        if let Some(entry) = worktree_index_entry(repo, workdir, path)? {
+            snapshot.add(&entry).map_err(|e| e.message().to_string())?;
+        }
+    }
+    snapshot
+        .write_tree_to(repo)
+        .map_err(|e| e.message().to_string())
+}
+
+fn stash_ids(repo: &Repository) -> Vec<String> {
+    repo.reflog("refs/stash")
+        .map(|log| log.iter().map(|entry| entry.id_new().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Rebuilds `refs/stash` and its reflog so the stash list is `ids` again.
+fn restore_stashes(repo: &Repository, ids: &[String]) -> Result<(), String> {
+    if let Ok(mut reference) = repo.find_reference("refs/stash") {
+        reference.delete().map_err(|e| e.message().to_string())?;
+    }
+    let Some(top) = ids.first() else {
+        return Ok(());
+    };
+    let oid = |hex: &str| git2::Oid::from_str(hex).map_err(|e| e.message().to_string());
+    repo.reference("refs/stash", oid(top)?, true, "releash undo")
+        .map_err(|e| e.message().to_string())?;
+    let mut reflog = repo
+        .reflog("refs/stash")
+        .map_err(|e| e.message().to_string())?;
+    while !reflog.is_empty() {
+        reflog
+            .remove(0, false)
+            .map_err(|e| e.message().to_string())?;
+    }
+    for id in ids.iter().rev() {
+        let commit = repo
+            .find_commit(oid(id)?)
+            .map_err(|e| e.message().to_string())?;
+        reflog
+            .append(commit.id(), &commit.committer(), commit.message())
+            .map_err(|e| e.message().to_string())?;
+    }
+    reflog.write().map_err(|e| e.message().to_string())
+}
+
+fn operation_state(repo: &Repository, worktree_paths: &[String]) -> OperationState {
+    let head = repo.find_reference("HEAD").ok();
+    let branches = repo
+        .branches(Some(BranchType::Local))
+        .map(|branches| {
+            branches
+                .flatten()
+                .filter_map(|(branch, _)| {
+                    let reference = branch.get();
+                    Some((
+                        reference.name()?.to_string(),
+                        reference.target()?.to_string(),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    OperationState {
+        head: repo
+            .head()
+            .ok()
+            .and_then(|h| h.target())
+            .map(|oid| oid.to_string()),
+        head_ref: head
+            .as_ref()
+            .and_then(|h| h.symbolic_target())
+            .map(str::to_string),
+        index_tree: repo
+            .index()
+            .and_then(|mut index| index.write_tree())
+            .ok()
+            .map(|oid| oid.to_string()),
+        stashes: stash_ids(repo),
+        worktree_tree: if worktree_paths.is_empty() {
+            None
+        } else {
+            worktree_paths_tree(repo, worktree_paths)
+                .ok()
+                .map(|oid| oid.to_string())
+        },
+        branches,
+    }
+}
+
+/// Points `refs/releash/journal/<id>` at a commit holding the entry's trees
+/// and commits, so `git gc` keeps everything an undo needs.
+fn anchor_journal_entry(repo: &Repository, entry: &OperationEntry) -> Result<(), String> {
+    let (before, after) = (&entry.before, &entry.after);
+    let oid = |hex: &str| git2::Oid::from_str(hex).ok();
+    let mut builder = repo
+        .treebuilder(None)
+        .map_err(|e| e.message().to_string())?;
+    for (name, tree) in [
+        ("index-before", &before.index_tree),
+        ("index-after", &after.index_tree),
+        ("worktree-before", &before.worktree_tree),
+        ("worktree-after", &after.worktree_tree),
+    ] {
+        if let Some(id) = tree.as_deref().and_then(oid) {
+            builder
+                .insert(name, id, 0o040000)
+                .map_err(|e| e.message().to_string())?;
+        }
+    }
+    let tree = builder
+        .write()
+        .and_then(|id| repo.find_tree(id))
+        .map_err(|e| e.message().to_string())?;
+
+    let mut parents: Vec<git2::Commit> = Vec::new();
+    let changed_branches = before
+        .branches
+        .iter()
+        .chain(&after.branches)
+        .filter(|(name, _)| before.branches.get(*name) != after.branches.get(*name))
+        .map(|(_, target)| target);
+    let changed_stashes = before
+        .stashes
+        .iter()
+        .chain(&after.stashes)
+        .filter(|id| !(before.stashes.contains(id) && after.stashes.contains(id)));
+    for hex in before
+        .head
+        .iter()
+        .chain(&after.head)
+        .chain(changed_branches)
+        .chain(changed_stashes)
+    {
+        if let Some(commit) = oid(hex).and_then(|id| repo.find_commit(id).ok()) {
+            if !parents.iter().any(|p| p.id() == commit.id()) {
+                parents.push(commit);
+            }
+        }
+    }
+    let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+    let sig = releash_signature(repo)?;
+    let commit = repo
+        .commit(
+            None,
+            &sig,
+            &sig,
+            &format!("journal: {}", entry.operation),
+            &tree,
+            &parent_refs,
+        )
+        .map_err(|e| e.message().to_string())?;
+    repo.reference(
+        &format!("{JOURNAL_PREFIX}{}", entry.id),
+        commit,
+        true,
+        "journal entry",
+    )
+    .map_err(|e| e.message().to_string())?;
+    Ok(())
+}
+
+/// Journal failures never fail the command.
+fn journaled<T, E>(
+    repo_path: &str,
+    operation: &str,
+    run: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    journaled_worktree(repo_path, operation, |_| Vec::new(), run)
+}
+
+fn journaled_worktree<T, E>(
+    repo_path: &str,
+    operation: &str,
+    worktree_paths: impl FnOnce(&Repository) -> Vec<String>,
+    run: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let before = Repository::open(repo_path).ok().map(|repo| {
+        let paths = worktree_paths(&repo);
+        (operation_state(&repo, &paths), paths)
+    });
+    let result = run();
+    if let (Ok(_), Some((before, paths)), Ok(repo)) = (&result, before, Repository::open(repo_path))
+    {
+        let after = operation_state(&repo, &paths);
+        if after != before {
+            let mut journal =
+                load_json::<Vec<OperationEntry>>(&repo, JOURNAL_FILE).unwrap_or_default();
+            journal.push(OperationEntry {
+                id: journal.last().map_or(1, |e| e.id + 1),
+                operation: operation.to_string(),
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0),
+                before,
+                after,
+                worktree_paths: paths,
+                undone: false,
+            });
+            if let Some(entry) = journal.last() {
+                let _ = anchor_journal_entry(&repo, entry);
+            }
+            let excess = journal.len().saturating_sub(MAX_JOURNAL_ENTRIES);
+            for dropped in journal.drain(..excess) {
+                if let Ok(mut reference) =
+                    repo.find_reference(&format!("{JOURNAL_PREFIX}{}", dropped.id))
+                {
+                    let _ = reference.delete();
+                }
+            }
+            let _ = save_json(&repo, JOURNAL_FILE, &journal);
+        }
+    }
+    result
+}
+
+fn delta_paths(diff: &git2::Diff<'_>) -> Vec<String> {
+    let mut paths: Vec<String> = diff
+        .deltas()
+        .flat_map(|d| [delta_path(d.old_file()), delta_path(d.new_file())])
+        .flatten()
+        .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+fn commit_paths(repo: &Repository, spec: &str) -> Vec<String> {
+    let Ok(commit) = repo.revparse_single(spec).and_then(|o| o.peel_to_commit()) else {
+        return Vec::new();
+    };
+    let parent = commit.parent(0).and_then(|p| p.tree()).ok();
+    commit
+        .tree()
+        .and_then(|tree| repo.diff_tree_to_tree(parent.as_ref(), Some(&tree), None))
+        .map(|diff| delta_paths(&diff))
+        .unwrap_or_default()
+}
+
+fn checkout_paths(repo: &Repository, spec: &str) -> Vec<String> {
+    let target = repo.revparse_single(spec).and_then(|o| o.peel_to_tree());
+    match (head_tree(repo), target) {
+        (Ok(head), Ok(target)) => repo
+            .diff_tree_to_tree(head.as_ref(), Some(&target), None)
+            .map(|diff| delta_paths(&diff))
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+fn reset_paths(repo: &Repository, target: &str) -> Vec<String> {
+    repo.revparse_single(target)
+        .and_then(|o| o.peel_to_tree())
+        .and_then(|tree| repo.diff_tree_to_workdir_with_index(Some(&tree), None))
+        .map(|diff| delta_paths(&diff))
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn list_operations(repo_path: String) -> Result<Vec<OperationEntry>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let mut journal = load_json::<Vec<OperationEntry>>(&repo, JOURNAL_FILE).unwrap_or_default();
+    journal.reverse();
+    Ok(journal)
+}
+
+#[tauri::command]
+pub fn git_undo_operation(repo_path: String) -> Result<OperationEntry, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let mut journal = load_json::<Vec<OperationEntry>>(&repo, JOURNAL_FILE).unwrap_or_default();
+    let position = journal
+        .iter()
+        .rposition(|e| !e.undone)
+        .ok_or_else(|| "nothing to undo".to_string())?;
+    let entry = journal[position].clone();
+    let (before, after) = (&entry.before, &entry.after);
+    let Some(index_tree) = &before.index_tree else {
+        return Err(format!(
+            "cannot undo {}: the index had unresolved conflicts",
+            entry.operation
+        ));
+    };
+
+    let current = operation_state(&repo, &entry.worktree_paths);
+    let worktree_changed = before.worktree_tree != after.worktree_tree;
+    let mut changed_refs: Vec<&String> = before.branches.keys().collect();
+    changed_refs.extend(
+        after
+            .branches
+            .keys()
+            .filter(|name| !before.branches.contains_key(*name)),
+    );
+    changed_refs.retain(|name| before.branches.get(*name) != after.branches.get(*name));
+    let moved_on = current.head != after.head
+        || current.head_ref != after.head_ref
+        || current.index_tree != after.index_tree
+        || (worktree_changed
+            && (after.worktree_tree.is_none() || current.worktree_tree != after.worktree_tree))
+        || current.stashes != after.stashes
+        || changed_refs
+            .iter()
+            .any(|name| current.branches.get(*name) != after.branches.get(*name));
+    if moved_on {
+        return Err(format!(
+            "cannot undo {}: the repository has changed since",
+            entry.operation
+        ));
+    }
+
+    let log_message = format!("releash undo: {}", entry.operation);
+    let oid = |hex: &str| git2::Oid::from_str(hex).map_err(|e| e.message().to_string());
+    for name in &changed_refs {
+        if let Some(target) = before.branches.get(*name) {
+            repo.reference(name, oid(target)?, true, &log_message)
+                .map_err(|e| e.message().to_string())?;
+        }
+    }
+    match (&before.head_ref, &before.head) {
+        (Some(head_ref), _) => repo.set_head(head_ref),
+        (None, Some(head)) => repo.set_head_detached(oid(head)?),
+        (None, None) => Ok(()),
+    }
+    .map_err(|e| e.message().to_string())?;
+    for name in &changed_refs {
+        if !before.branches.contains_key(*name) {
+            repo.find_reference(name)
+                .and_then(|mut r| r.delete())
+                .map_err(|e| e.message().to_string())?;
+        }
+    }
+
+    if before.stashes != after.stashes {
+        restore_stashes(&repo, &before.stashes)?;
+    }
+
+    if let (true, Some(from), Some(to)) = (
+        worktree_changed,
+        &after.worktree_tree,
+        &before.worktree_tree,
+    ) {
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| "repository has no working directory".to_string())?;
+        let from = repo
+            .find_tree(oid(from)?)
+            .map_err(|e| e.message().to_string())?;
+        let to = repo
+            .find_tree(oid(to)?)
+            .map_err(|e| e.message().to_string())?;
+        let diff = repo
+            .diff_tree_to_tree(Some(&from), Some(&to), None)
+            .map_err(|e| e.message().to_string())?;
+        for delta in diff.deltas() {
+            let Some(path) = delta_path(delta.new_file()).or_else(|| delta_path(delta.old_file()))
+            else {
+                continue;
+            };
+            let full = workdir.join(&path);
+            if delta.status() == git2::Delta::Deleted {
+                std::fs::remove_file(&full).map_err(|e| format!("Failed to remove {path}: {e}"))?;
+            } else {
+                let blob = repo
+                    .find_blob(delta.new_file().id())
+                    .map_err(|e| e.message().to_string())?;
+                write_worktree_file(&full, blob.content(), u32::from(delta.new_file().mode()))?;
+            }
+        }
+    }
+
+    let tree = repo
+        .find_tree(oid(index_tree)?)
+        .map_err(|e| e.message().to_string())?;
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+    index
+        .read_tree(&tree)
+        .and_then(|_| index.write())
+        .map_err(|e| e.message().to_string())?;
+
+    journal[position].undone = true;
+    save_json(&repo, JOURNAL_FILE, &journal)?;
+    Ok(journal[position].clone())
+}
+
+#[derive(Serialize)]
+pub struct ReflogEntry {
+    pub old_id: String,
+    pub new_id: String,
+    pub message: String,
+    pub committer_name: String,
+    pub committer_email: String,
+    pub timestamp: i64,
+}
+
+#[tauri::command]
+pub fn get_reflog(
+    repo_path: String,
+    reference: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ReflogEntry>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    let reflog = repo
+        .reflog(reference.as_deref().unwrap_or("HEAD"))
+        .map_err(|e| e.message().to_string())?;
+    Ok(reflog
+        .iter()
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|entry| {
+            let committer = entry.committer();
+            ReflogEntry {
+                old_id: entry.id_old().to_string(),
+                new_id: entry.id_new().to_string(),
+                message: entry.message().unwrap_or("").to_string(),
+                committer_name: committer.name().unwrap_or("").to_string(),
+                committer_email: committer.email().unwrap_or("").to_string(),
+                timestamp: committer.when().seconds(),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{Repository, Signature};
+    use std::cell::RefCell;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_repo() -> (TempDir, Repository) {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+
+        (dir, repo)
+    }
+
+    fn create_initial_commit(repo: &Repository) -> git2::Oid {
+        let sig = Signature::now("Test User", "test@example.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "initial commit", &tree, &[])
+            .unwrap()
+    }
+
+    fn add_and_commit(repo: &Repository, path: &str, content: &str, message: &str) -> git2::Oid {
+        let workdir = repo.workdir().unwrap();
+        fs::write(workdir.join(path), content).unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+
+        let sig = Signature::now("Test User", "test@example.com").unwrap();
+        let tree_id = index.write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &[&parent])
+            .unwrap()
+    }
+
+    fn add_and_commit_bytes(repo: &Repository, path: &str, content: &[u8]) {
+        let workdir = repo.workdir().unwrap();
+        fs::write(workdir.join(path), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+        let sig = Signature::now("Test User", "test@example.com").unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "add bytes", &tree, &[&parent])
+            .unwrap();
+    }
+
+    fn blob_at_head(dir: &TempDir, path: &str) -> BlobContent {
+        get_file_at_ref(
+            dir.path().join(path).to_str().unwrap().to_string(),
+            "HEAD".to_string(),
+        )
+        .unwrap()
+    }
+
+    // --- blob decoding tests ---
+
+    #[test]
+    fn test_get_file_at_ref_utf8() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "héllo\n", "add file");
+
+        let blob = blob_at_head(&dir, "file.txt");
+        assert!(!blob.binary);
+        assert_eq!(blob.encoding.as_deref(), Some("UTF-8"));
+        assert_eq!(blob.content, "héllo\n");
+        assert_eq!(blob.size, "héllo\n".len());
+        assert!(!blob.truncated);
+        assert!(blob.image.is_none());
+    }
+
+    #[test]
+    fn test_get_file_at_ref_legacy_encodings() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let (sjis, _, _) =
+            encoding_rs::SHIFT_JIS.encode("こんにちは、世界。日本語のテキストです。\n");
+        add_and_commit_bytes(&repo, "sjis.txt", &sjis);
+        add_and_commit_bytes(&repo, "latin1.txt", b"caf\xe9 cr\xe8me br\xfbl\xe9e\n");
+
+        let blob = blob_at_head(&dir, "sjis.txt");
+        assert!(!blob.binary);
+        assert_eq!(blob.encoding.as_deref(), Some("Shift_JIS"));
+        assert_eq!(blob.content, "こんにちは、世界。日本語のテキストです。\n");
+
+        let blob = blob_at_head(&dir, "latin1.txt");
+        assert!(!blob.binary);
+        assert_eq!(blob.encoding.as_deref(), Some("windows-1252"));
+        assert_eq!(blob.content, "café crème brûlée\n");
+    }
+
+    #[test]
+    fn test_get_file_at_ref_utf16_bom() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let mut bytes = vec![0xff, 0xfe];
+        for unit in "hi\n".encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        add_and_commit_bytes(&repo, "wide.txt", &bytes);
+
+        let blob = blob_at_head(&dir, "wide.txt");
+        assert!(!blob.binary);
+        assert_eq!(blob.encoding.as_deref(), Some("UTF-16LE"));
+        assert_eq!(blob.content, "hi\n");
+    }
+
+    #[test]
+    fn test_get_file_at_ref_binary_and_images() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit_bytes(&repo, "data.bin", &[0, 1, 2, 3, 0xff]);
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        add_and_commit_bytes(&repo, "icon.png", png);
+        add_and_commit(
+            &repo,
+            "logo.svg",
+            "<svg xmlns=\"http://www.w3.org/2000/svg\"/>\n",
+            "svg",
+        );
+
+        let blob = blob_at_head(&dir, "data.bin");
+        assert!(blob.binary);
+        assert_eq!(blob.encoding, None);
+        assert!(blob.content.is_empty());
+        assert_eq!(blob.size, 5);
+        assert!(blob.image.is_none());
+
+        let blob = blob_at_head(&dir, "icon.png");
+        assert!(blob.binary);
+        let image = blob.image.unwrap();
+        assert_eq!(image.mime_type, "image/png");
+        assert_eq!(image.data_base64, "iVBORw0KGgoAAAANSUhEUg==");
+
+        let blob = blob_at_head(&dir, "logo.svg");
+        assert!(!blob.binary);
+        assert!(blob.content.starts_with("<svg"));
+        assert_eq!(blob.image.unwrap().mime_type, "image/svg+xml");
+    }
+
+    #[test]
+    fn test_get_file_at_ref_truncates_large_text() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let line = "0123456789abcdé\n";
+        let big = line.repeat(MAX_TEXT_BLOB_BYTES / line.len() + 10);
+        add_and_commit(&repo, "big.txt", &big, "big");
+
+        let blob = blob_at_head(&dir, "big.txt");
+        assert!(!blob.binary);
+        assert!(blob.truncated);
+        assert_eq!(blob.size, big.len());
+        assert!(blob.content.len() <= MAX_TEXT_BLOB_BYTES);
+        assert!(big.starts_with(&blob.content));
+    }
+
+    #[test]
+    fn test_get_git_status_untracked() {
+        let (dir, _repo) = create_test_repo();
+        create_initial_commit(&_repo);
+        fs::write(dir.path().join("new_file.txt"), "hello").unwrap();
+
+        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "new_file.txt");
+        assert_eq!(result[0].worktree_status, "new");
+        assert_eq!(result[0].index_status, "none");
+    }
+
+    #[test]
+    fn test_get_git_status_staged() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+
+        fs::write(dir.path().join("staged.txt"), "content").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("staged.txt")).unwrap();
+        index.write().unwrap();
+
+        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "staged.txt");
+        assert_eq!(result[0].index_status, "new");
+    }
+
+    #[test]
+    fn test_get_git_status_modified() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "original", "add file");
+
+        fs::write(dir.path().join("file.txt"), "modified content").unwrap();
+
+        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "file.txt");
+        assert_eq!(result[0].worktree_status, "modified");
+    }
+
+    #[test]
+    fn test_get_git_status_empty_repo() {
+        let (dir, _repo) = create_test_repo();
+
+        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_get_git_log() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "a.txt", "a", "second commit");
+        add_and_commit(&repo, "b.txt", "b", "third commit");
+
+        let result = get_git_log(dir.path().to_str().unwrap().to_string(), None, None).unwrap();
+        assert_eq!(result.len(), 3);
+
+        let messages: Vec<&str> = result.iter().map(|c| c.message.as_str()).collect();
+        assert!(messages.contains(&"initial commit"));
+        assert!(messages.contains(&"second commit"));
+        assert!(messages.contains(&"third commit"));
+
+        assert_eq!(result[0].author_name, "Test User");
+        assert_eq!(result[0].short_hash.len(), 7);
+    }
+
+    #[test]
+    fn test_get_git_log_with_limit() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "a.txt", "a", "second");
+
+        let result = get_git_log(dir.path().to_str().unwrap().to_string(), Some(1), None).unwrap();
+        assert_eq!(result.len(), 1);
+    }
+
+    #[test]
+    fn test_get_git_log_empty_repo() {
+        let (dir, _repo) = create_test_repo();
+
+        let result = get_git_log(dir.path().to_str().unwrap().to_string(), None, None).unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_get_git_log_parents_and_stats() {
+        let (dir, repo) = create_test_repo();
+        let root = create_initial_commit(&repo);
+        add_and_commit(&repo, "a.txt", "one\ntwo\n", "add a");
+        let second = add_and_commit(&repo, "a.txt", "one\nTWO\nthree\n", "edit a");
+
+        let log = get_git_log(dir.path().to_str().unwrap().to_string(), None, None).unwrap();
+        assert_eq!(log[0].hash, second.to_string());
+        assert_eq!(log[0].parents, vec![log[1].hash.clone()]);
+        assert_eq!(log[0].files_changed, 1);
+        assert_eq!(log[0].insertions, 2);
+        assert_eq!(log[0].deletions, 1);
+        assert_eq!(log[1].insertions, 2);
+        assert_eq!(log[2].hash, root.to_string());
+        assert!(log[2].parents.is_empty());
+        assert_eq!(log[2].files_changed, 0);
+    }
+
+    #[test]
+    fn test_get_git_log_cursor_pagination() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        for i in 0..4 {
+            add_and_commit(&repo, "a.txt", &i.to_string(), &format!("commit {i}"));
+        }
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let all: Vec<String> = get_git_log(repo_path.clone(), None, None)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.hash)
+            .collect();
+
+        let mut paged = Vec::new();
+        let mut cursor = None;
+        loop {
+            let filter = LogFilter {
+                cursor: cursor.clone(),
+                ..Default::default()
+            };
+            let page = get_git_log(repo_path.clone(), Some(2), Some(filter)).unwrap();
+            cursor = page.last().map(|c| c.hash.clone());
+            let done = page.len() < 2;
+            paged.extend(page.into_iter().map(|c| c.hash));
+            if done {
+                break;
+            }
+        }
+        assert_eq!(paged, all);
+
+        let bad = LogFilter {
+            cursor: Some("0".repeat(40)),
+            ..Default::default()
+        };
+        assert!(get_git_log(repo_path, None, Some(bad)).is_err());
+    }
+
+    #[test]
+    fn test_get_git_log_cursor_pagination_with_filter() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let main = current_branch_name(&repo).unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        for i in 0..3 {
+            add_and_commit(&repo, "src/main.txt", &i.to_string(), &format!("main {i}"));
+            add_and_commit(&repo, "other.txt", &i.to_string(), &format!("other {i}"));
+        }
+        git_create_branch(repo_path.clone(), "side".to_string(), None, None).unwrap();
+        add_and_commit(&repo, "src/side.txt", "1", "side 1");
+        add_and_commit(&repo, "other.txt", "side", "side other");
+        add_and_commit(&repo, "src/side.txt", "2", "side 2");
+        git_checkout_branch(repo_path.clone(), main).unwrap();
+        let repo = Repository::open(dir.path()).unwrap();
+        add_and_commit(&repo, "src/main.txt", "after", "main after");
+
+        let ours = repo.head().unwrap().peel_to_commit().unwrap();
+        let theirs = repo
+            .find_branch("side", BranchType::Local)
+            .unwrap()
+            .get()
+            .peel_to_commit()
+            .unwrap();
+        let mut merged = repo.merge_commits(&ours, &theirs, None).unwrap();
+        let tree = repo
+            .find_tree(merged.write_tree_to(&repo).unwrap())
+            .unwrap();
+        let sig = repo.signature().unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &sig,
+            &sig,
+            "merge side",
+            &tree,
+            &[&ours, &theirs],
+        )
+        .unwrap();
+
+        let filter = |cursor: Option<String>| LogFilter {
+            cursor,
+            path: Some("src".to_string()),
+            ..Default::default()
+        };
+        let all: Vec<String> = get_git_log(repo_path.clone(), None, Some(filter(None)))
+            .unwrap()
+            .into_iter()
+            .map(|c| c.message)
+            .collect();
+        assert_eq!(all.len(), 7);
+        assert!(all.contains(&"side 1".to_string()));
+        assert!(!all.iter().any(|m| m.contains("other")));
+
+        let mut paged = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = get_git_log(repo_path.clone(), Some(2), Some(filter(cursor))).unwrap();
+            cursor = page.last().map(|c| c.hash.clone());
+            let done = page.len() < 2;
+            paged.extend(page.into_iter().map(|c| c.message));
+            if done {
+                break;
+            }
+        }
+        assert_eq!(paged, all);
+    }
+
+    #[test]
+    fn test_get_git_log_filters() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        fs::create_dir(dir.path().join("src")).unwrap();
+        add_and_commit(&repo, "src/a.txt", "a", "feat: add a");
+        add_and_commit(&repo, "b.txt", "b", "fix: add b");
+
+        let other = Signature::new(
+            "Agent Smith",
+            "agent@example.com",
+            &git2::Time::new(1_000, 0),
+        )
+        .unwrap();
+        fs::write(dir.path().join("src/a.txt"), "a2").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("src/a.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &other,
+            &other,
+            "feat: agent edit",
+            &tree,
+            &[&parent],
+        )
+        .unwrap();
+
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let messages = |filter: LogFilter| -> Vec<String> {
+            get_git_log(repo_path.clone(), None, Some(filter))
+                .unwrap()
+                .into_iter()
+                .map(|c| c.message)
+                .collect()
+        };
+
+        let by_path = messages(LogFilter {
+            path: Some("src".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(by_path, vec!["feat: agent edit", "feat: add a"]);
+
+        let by_author = messages(LogFilter {
+            author: Some("SMITH".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(by_author, vec!["feat: agent edit"]);
+
+        let by_message = messages(LogFilter {
+            message_regex: Some("^feat:".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(by_message, vec!["feat: agent edit", "feat: add a"]);
+
+        let by_date = messages(LogFilter {
+            since: Some(500),
+            until: Some(2_000),
+            ..Default::default()
+        });
+        assert_eq!(by_date, vec!["feat: agent edit"]);
+
+        let bad = LogFilter {
+            message_regex: Some("(".to_string()),
+            ..Default::default()
+        };
+        assert!(get_git_log(repo_path, None, Some(bad)).is_err());
+    }
+
+    #[test]
+    fn test_get_git_log_revision_range() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "a.txt", "a", "on main");
+        let main = repo.head().unwrap().shorthand().unwrap().to_string();
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("agent", &base, false).unwrap();
+        repo.set_head("refs/heads/agent").unwrap();
+        add_and_commit(&repo, "b.txt", "b", "agent one");
+        add_and_commit(&repo, "c.txt", "c", "agent two");
+        repo.set_head(&format!("refs/heads/{main}")).unwrap();
+        add_and_commit(&repo, "d.txt", "d", "main moved on");
+
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let messages = |spec: String| -> Vec<String> {
+            let filter = LogFilter {
+                revision: Some(spec),
+                ..Default::default()
+            };
+            get_git_log(repo_path.clone(), None, Some(filter))
+                .unwrap()
+                .into_iter()
+                .map(|c| c.message)
+                .collect()
+        };
+
+        assert_eq!(
+            messages(format!("{main}..agent")),
+            vec!["agent two", "agent one"]
+        );
+        assert_eq!(messages("agent".to_string()).len(), 4);
+        let mut symmetric = messages(format!("{main}...agent"));
+        symmetric.sort();
+        assert_eq!(symmetric, vec!["agent one", "agent two", "main moved on"]);
+
+        let bad = LogFilter {
+            revision: Some("no-such-branch".to_string()),
+            ..Default::default()
+        };
+        assert!(get_git_log(repo_path, None, Some(bad)).is_err());
+    }
+
+    #[test]
+    fn test_get_commit_detail_metadata_and_files() {
+        let (dir, repo) = create_test_repo();
+        let root = create_initial_commit(&repo);
+        let oid = add_and_commit(
+            &repo,
+            "a.txt",
+            "one\n",
+            "feat: add a\n\nBody.\n\nSigned-off-by: Test User <test@example.com>\nCo-authored-by: Agent <agent@example.com>\n",
+        );
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let detail = get_commit_detail(repo_path.clone(), oid.to_string(), None, None).unwrap();
+        assert_eq!(detail.hash, oid.to_string());
+        assert_eq!(detail.committer_name, "Test User");
+        assert_eq!(detail.committer_email, "test@example.com");
+        assert_eq!(detail.parents, vec![root.to_string()]);
+        assert_eq!(detail.compared_parent, Some(root.to_string()));
+        assert!(detail.signature.is_none());
+        let trailers: Vec<(&str, &str)> = detail
+            .trailers
+            .iter()
+            .map(|t| (t.key.as_str(), t.value.as_str()))
+            .collect();
+        assert_eq!(
+            trailers,
+            vec![
+                ("Signed-off-by", "Test User <test@example.com>"),
+                ("Co-authored-by", "Agent <agent@example.com>"),
+            ]
+        );
+        assert_eq!(detail.files.len(), 1);
+        assert_eq!(detail.files[0].new_path.as_deref(), Some("a.txt"));
+        assert_eq!(detail.files[0].status, "new");
+        assert_eq!(detail.files[0].hunks.len(), 1);
+
+        let root_detail = get_commit_detail(repo_path, root.to_string(), None, None).unwrap();
+        assert!(root_detail.compared_parent.is_none());
+        assert!(root_detail.files.is_empty());
+    }
+
+    #[test]
+    fn test_get_commit_detail_merge_parent_choice() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+        let main_tip = add_and_commit(&repo, "a.txt", "a", "main");
+        let main = repo.head().unwrap().name().unwrap().to_string();
+        repo.branch("side", &base, false).unwrap();
+        repo.set_head("refs/heads/side").unwrap();
+        repo.reset(base.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+        let side_tip = add_and_commit(&repo, "b.txt", "b", "side");
+        repo.set_head(&main).unwrap();
+
+        let main_commit = repo.find_commit(main_tip).unwrap();
+        let side_commit = repo.find_commit(side_tip).unwrap();
+        let mut merged = repo
+            .merge_commits(&main_commit, &side_commit, None)
+            .unwrap();
+        let tree = repo
+            .find_tree(merged.write_tree_to(&repo).unwrap())
+            .unwrap();
+        let sig = Signature::now("Test User", "test@example.com").unwrap();
+        let merge = repo
+            .commit(
+                Some("HEAD"),
+                &sig,
+                &sig,
+                "merge side",
+                &tree,
+                &[&main_commit, &side_commit],
+            )
+            .unwrap();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let paths = |parent: Option<usize>| -> Vec<String> {
+            get_commit_detail(repo_path.clone(), merge.to_string(), parent, None)
+                .unwrap()
+                .files
+                .into_iter()
+                .filter_map(|f| f.new_path)
+                .collect()
+        };
+        assert_eq!(paths(None), vec!["b.txt"]);
+        assert_eq!(paths(Some(1)), vec!["a.txt"]);
+        assert!(get_commit_detail(repo_path, merge.to_string(), Some(2), None).is_err());
+    }
+
+    #[test]
+    fn test_get_commit_detail_ssh_signature_status() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let key_dir = TempDir::new().unwrap();
+        let key_path = key_dir.path().join("id_ed25519");
+        let generated = std::process::Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+            .arg(&key_path)
+            .status()
+            .expect("this test needs ssh-keygen on PATH");
+        assert!(generated.success(), "ssh-keygen failed: {generated}");
+        let mut config = repo.config().unwrap();
+        config.set_str("gpg.format", "ssh").unwrap();
+        config
+            .set_str("user.signingkey", key_path.to_str().unwrap())
+            .unwrap();
+
+        let repo_path = dir.path().to_str().unwrap();
+        let options = CommitOptions {
+            sign: Some(true),
+            allow_empty: true,
+            ..Default::default()
+        };
+        let oid = commit_with(repo_path, "signed", options).unwrap();
+
+        let signature = get_commit_detail(repo_path.to_string(), oid.clone(), None, None)
+            .unwrap()
+            .signature
+            .unwrap();
+        assert_eq!(signature.format, "ssh");
+        assert_eq!(signature.status, "untrusted");
+
+        let public_key = fs::read_to_string(key_path.with_extension("pub")).unwrap();
+        let allowed = key_dir.path().join("allowed_signers");
+        fs::write(&allowed, format!("test@example.com {public_key}")).unwrap();
+        config
+            .set_str("gpg.ssh.allowedSignersFile", allowed.to_str().unwrap())
+            .unwrap();
+
+        let signature = get_commit_detail(repo_path.to_string(), oid, None, None)
+            .unwrap()
+            .signature
+            .unwrap();
+        assert_eq!(signature.status, "good");
+        assert_eq!(signature.signer.as_deref(), Some("test@example.com"));
+    }
+
+    #[test]
+    fn test_git_blame_at_ref_and_head() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let first = add_and_commit(&repo, "a.txt", "a\nb\n", "first");
+        let second = add_and_commit(&repo, "a.txt", "a\nB\nc\n", "second");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let lines = git_blame(repo_path.clone(), "a.txt".into(), None, None, None, None).unwrap();
+        let hashes: Vec<Option<String>> = lines.iter().map(|l| l.hash.clone()).collect();
+        assert_eq!(
+            hashes,
+            vec![
+                Some(first.to_string()),
+                Some(second.to_string()),
+                Some(second.to_string())
+            ]
+        );
+        assert_eq!(lines[1].content, "B");
+        assert_eq!(lines[1].summary, "second");
+        assert_eq!(lines[1].author_name, "Test User");
+        assert_eq!(lines[0].original_line, 1);
+
+        let at_first = git_blame(
+            repo_path,
+            "a.txt".into(),
+            Some(first.to_string()),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(at_first.len(), 2);
+        assert!(at_first
+            .iter()
+            .all(|l| l.hash.as_deref() == Some(first.to_string().as_str())));
+    }
+
+    #[test]
+    fn test_git_blame_includes_worktree_lines() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let first = add_and_commit(&repo, "a.txt", "a\nb\n", "first");
+        fs::write(dir.path().join("a.txt"), "a\nagent\nb\n").unwrap();
+        fs::write(dir.path().join("new.txt"), "x\ny\n").unwrap();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let lines = git_blame(
+            repo_path.clone(),
+            "a.txt".into(),
+            None,
+            Some(true),
+            None,
+            None,
+        )
+        .unwrap();
+        let hashes: Vec<Option<String>> = lines.iter().map(|l| l.hash.clone()).collect();
+        assert_eq!(
+            hashes,
+            vec![Some(first.to_string()), None, Some(first.to_string())]
+        );
+        assert_eq!(lines[1].content, "agent");
+        assert_eq!(lines[2].original_line, 2);
+
+        let untracked = git_blame(
+            repo_path.clone(),
+            "new.txt".into(),
+            None,
+            Some(true),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(untracked.len(), 2);
+        assert!(untracked.iter().all(|l| l.hash.is_none()));
+
+        assert!(git_blame(repo_path, "new.txt".into(), None, None, None, None).is_err());
+    }
+
+    #[test]
+    fn test_git_blame_ignore_whitespace() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let first = add_and_commit(&repo, "a.txt", "fn main() {\nbody();\n}\n", "first");
+        let reindent = add_and_commit(&repo, "a.txt", "fn main() {\n    body();\n}\n", "indent");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let plain = git_blame(repo_path.clone(), "a.txt".into(), None, None, None, None).unwrap();
+        assert_eq!(plain[1].hash, Some(reindent.to_string()));
+
+        let ignoring = git_blame(repo_path, "a.txt".into(), None, None, Some(true), None).unwrap();
+        assert_eq!(ignoring[1].hash, Some(first.to_string()));
+    }
+
+    #[test]
+    fn test_git_blame_follow_moves() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let moved = "let configuration = load_configuration_from_disk();";
+        add_and_commit(&repo, "b.txt", &format!("{{\n{moved}\n}}\n"), "add to b");
+        add_and_commit(&repo, "b.txt", "{\n}\n", "drop from b");
+        let readded = add_and_commit(&repo, "c.txt", &format!("{{\n{moved}\n}}\n"), "re-add in c");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let blame = |path: &str, follow: bool| {
+            git_blame(
+                repo_path.clone(),
+                path.into(),
+                None,
+                None,
+                None,
+                Some(follow),
+            )
+            .unwrap()
+        };
+
+        // Removing and re-adding in separate commits is not a move.
+        assert_eq!(blame("c.txt", true)[1].hash, Some(readded.to_string()));
+
+        fs::write(dir.path().join("c.txt"), "{\n}\n").unwrap();
+        fs::write(dir.path().join("d.txt"), format!("{{\n{moved}\n}}\n")).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("c.txt")).unwrap();
+        index.add_path(Path::new("d.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = repo.signature().unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        let moving = repo
+            .commit(Some("HEAD"), &sig, &sig, "move to d", &tree, &[&parent])
+            .unwrap();
+
+        assert_eq!(blame("d.txt", false)[1].hash, Some(moving.to_string()));
+        let following = blame("d.txt", true);
+        assert_eq!(following[1].hash, Some(readded.to_string()));
+        assert_eq!(following[1].summary, "re-add in c");
+        assert_eq!(following[1].original_path.as_deref(), Some("c.txt"));
+        assert_eq!(following[1].original_line, 2);
+        // Lines too short to identify, like the braces, are never followed.
+        assert_eq!(following[2].hash, Some(moving.to_string()));
+    }
+
+    #[test]
+    fn test_get_current_branch() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+
+        let result = get_current_branch(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert!(result == "main" || result == "master");
+    }
+
+    #[test]
+    fn test_get_current_branch_empty_repo() {
+        let (dir, _repo) = create_test_repo();
+
+        let result = get_current_branch(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(result, "(no commits)");
+    }
+
+    #[test]
+    fn test_get_current_branch_detached_head() {
+        let (dir, repo) = create_test_repo();
+        let oid = create_initial_commit(&repo);
+
+        repo.set_head_detached(oid).unwrap();
+
+        let result = get_current_branch(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert!(result.starts_with('('));
+        assert!(result.ends_with(')'));
+        assert_eq!(result.len(), 9); // "(1234567)"
+    }
+
+    // --- git_stage tests ---
+
+    #[test]
+    fn test_stage_specific_file() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        fs::write(dir.path().join("new.txt"), "hello").unwrap();
+
+        git_stage(
+            dir.path().to_str().unwrap().to_string(),
+            vec!["new.txt".to_string()],
+        )
+        .unwrap();
+
+        let statuses = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].index_status, "new");
+        assert_eq!(statuses[0].worktree_status, "none");
+    }
+
+    #[test]
+    fn test_stage_all_files() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        fs::write(dir.path().join("b.txt"), "b").unwrap();
+
+        git_stage(dir.path().to_str().unwrap().to_string(), vec![]).unwrap();
+
+        let statuses = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(statuses.len(), 2);
+        for s in &statuses {
+            assert_eq!(s.index_status, "new");
+            assert_eq!(s.worktree_status, "none");
+        }
+    }
+
+    #[test]
+    fn test_stage_deleted_file() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "content", "add file");
+        fs::remove_file(dir.path().join("file.txt")).unwrap();
+
+        git_stage(
+            dir.path().to_str().unwrap().to_string(),
+            vec!["file.txt".to_string()],
+        )
+        .unwrap();
+
+        let statuses = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].index_status, "deleted");
+    }
+
+    #[test]
+    fn test_stage_untracked_file() {
+        let (dir, _repo) = create_test_repo();
+        create_initial_commit(&_repo);
+        fs::write(dir.path().join("untracked.txt"), "data").unwrap();
+
+        let before = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(before[0].worktree_status, "new");
+
+        git_stage(
+            dir.path().to_str().unwrap().to_string(),
+            vec!["untracked.txt".to_string()],
+        )
+        .unwrap();
+
+        let after = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(after[0].index_status, "new");
+        assert_eq!(after[0].worktree_status, "none");
+    }
+
+    // --- git_unstage tests ---
+
+    #[test]
+    fn test_unstage_specific_file() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        fs::write(dir.path().join("file.txt"), "content").unwrap();
+        git_stage(
+            dir.path().to_str().unwrap().to_string(),
+            vec!["file.txt".to_string()],
+        )
+        .unwrap();
+
+        git_unstage(
+            dir.path().to_str().unwrap().to_string(),
+            vec!["file.txt".to_string()],
+        )
+        .unwrap();
+
+        let statuses = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].worktree_status, "new");
+        assert_eq!(statuses[0].index_status, "none");
+    }
+
+    #[test]
+    fn test_unstage_all_files() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        fs::write(dir.path().join("b.txt"), "b").unwrap();
+        git_stage(dir.path().to_str().unwrap().to_string(), vec![]).unwrap();
+
+        git_unstage(dir.path().to_str().unwrap().to_string(), vec![]).unwrap();
+
+        let statuses = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        for s in &statuses {
+            assert_eq!(s.index_status, "none");
+            assert_eq!(s.worktree_status, "new");
+        }
+    }
+
+    #[test]
+    fn test_unstage_unborn_branch() {
+        let (dir, _repo) = create_test_repo();
+        // No initial commit — unborn branch
+        fs::write(dir.path().join("file.txt"), "content").unwrap();
+        git_stage(
+            dir.path().to_str().unwrap().to_string(),
+            vec!["file.txt".to_string()],
+        )
+        .unwrap();
+
+        // Verify it was staged
+        let before = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(before[0].index_status, "new");
+
+        git_unstage(
+            dir.path().to_str().unwrap().to_string(),
+            vec!["file.txt".to_string()],
+        )
+        .unwrap();
+
+        let after = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(after[0].index_status, "none");
+        assert_eq!(after[0].worktree_status, "new");
+    }
+
+    // --- git_commit tests ---
+
+    #[test]
+    fn test_commit_normal() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        fs::write(dir.path().join("file.txt"), "content").unwrap();
+        git_stage(
+            dir.path().to_str().unwrap().to_string(),
+            vec!["file.txt".to_string()],
+        )
+        .unwrap();
+
+        let hash = create_commit(
+            dir.path().to_str().unwrap(),
+            "test commit".to_string(),
+            CommitOptions::default(),
+            &|_| {},
+        )
+        .unwrap();
+        assert_eq!(hash.len(), 40);
+
+        let log = get_git_log(dir.path().to_str().unwrap().to_string(), Some(1), None).unwrap();
+        assert_eq!(log[0].message, "test commit");
+    }
+
+    #[test]
+    fn test_commit_initial() {
+        let (dir, _repo) = create_test_repo();
+        // No initial commit
+        fs::write(dir.path().join("file.txt"), "content").unwrap();
+        git_stage(
+            dir.path().to_str().unwrap().to_string(),
+            vec!["file.txt".to_string()],
+        )
+        .unwrap();
+
+        let hash = create_commit(
+            dir.path().to_str().unwrap(),
+            "first commit".to_string(),
+            CommitOptions::default(),
+            &|_| {},
+        )
+        .unwrap();
+        assert_eq!(hash.len(), 40);
+
+        let log = get_git_log(dir.path().to_str().unwrap().to_string(), None, None).unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].message, "first commit");
+    }
+
+    // --- commit option tests ---
+
+    fn commit_with(
+        repo_path: &str,
+        message: &str,
+        options: CommitOptions,
+    ) -> Result<String, CommitError> {
+        create_commit(repo_path, message.to_string(), options, &|_| {})
+    }
+
+    #[test]
+    fn test_commit_refuses_empty_unless_allowed() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap();
+
+        let err = commit_with(repo_path, "nothing", CommitOptions::default()).unwrap_err();
+        assert!(err.message.contains("nothing to commit"));
+
+        let options = CommitOptions {
+            allow_empty: true,
+            ..Default::default()
+        };
+        commit_with(repo_path, "empty on purpose", options).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.summary(), Some("empty on purpose"));
+    }
+
+    #[test]
+    fn test_commit_amend_keeps_or_replaces_message() {
+        let (dir, repo) = create_test_repo();
+        let root = create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap();
+        add_and_commit(&repo, "file.txt", "one\n", "add file");
+
+        fs::write(dir.path().join("file.txt"), "two\n").unwrap();
+        git_stage(repo_path.to_string(), vec!["file.txt".to_string()]).unwrap();
+        let amend = || CommitOptions {
+            amend: true,
+            ..Default::default()
+        };
+        let oid = commit_with(repo_path, "", amend()).unwrap();
+
+        let reopened = Repository::open(dir.path()).unwrap();
+        let head = reopened.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.id().to_string(), oid);
+        assert_eq!(head.message(), Some("add file"));
+        assert_eq!(head.parent_id(0).unwrap(), root);
+        assert_eq!(blob_at_head(&dir, "file.txt").content, "two\n");
+
+        commit_with(repo_path, "reworded", amend()).unwrap();
+        let head = reopened.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.message(), Some("reworded"));
+        assert_eq!(head.parent_id(0).unwrap(), root);
+        let log = get_git_log(repo_path.to_string(), None, None).unwrap();
+        assert_eq!(log.len(), 2);
+    }
+
+    #[test]
+    fn test_commit_sign_off_and_author_override() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap();
+        fs::write(dir.path().join("file.txt"), "content\n").unwrap();
+        git_stage(repo_path.to_string(), vec!["file.txt".to_string()]).unwrap();
+
+        let options = CommitOptions {
+            sign_off: true,
+            author_name: Some("Agent".to_string()),
+            author_email: Some("agent@example.com".to_string()),
+            ..Default::default()
+        };
+        commit_with(repo_path, "add file\n\nBody text.", options).unwrap();
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(
+            head.message(),
+            Some("add file\n\nBody text.\n\nSigned-off-by: Test User <test@example.com>\n")
+        );
+        assert_eq!(head.author().name(), Some("Agent"));
+        assert_eq!(head.author().email(), Some("agent@example.com"));
+        assert_eq!(head.committer().name(), Some("Test User"));
+    }
+
+    #[test]
+    fn test_add_sign_off_joins_trailer_block() {
+        let sig = Signature::now("Test User", "test@example.com").unwrap();
+        assert_eq!(
+            add_sign_off("fix\n\nCo-authored-by: A <a@example.com>\n", &sig),
+            "fix\n\nCo-authored-by: A <a@example.com>\nSigned-off-by: Test User <test@example.com>\n"
+        );
+        let signed = "fix\n\nSigned-off-by: Test User <test@example.com>\n";
+        assert_eq!(add_sign_off(signed, &sig), signed);
+        assert_eq!(
+            add_sign_off("Subject: colon", &sig),
+            "Subject: colon\n\nSigned-off-by: Test User <test@example.com>\n"
+        );
+    }
+
+    #[test]
+    fn test_commit_ssh_signing_from_config() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let key_dir = TempDir::new().unwrap();
+        let key_path = key_dir.path().join("id_ed25519");
+        let generated = std::process::Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+            .arg(&key_path)
+            .status()
+            .expect("this test needs ssh-keygen on PATH");
+        assert!(generated.success(), "ssh-keygen failed: {generated}");
+        let mut config = repo.config().unwrap();
+        config.set_bool("commit.gpgsign", true).unwrap();
+        config.set_str("gpg.format", "ssh").unwrap();
+        config
+            .set_str("user.signingkey", key_path.to_str().unwrap())
+            .unwrap();
+
+        fs::write(dir.path().join("file.txt"), "content\n").unwrap();
+        let repo_path = dir.path().to_str().unwrap();
+        git_stage(repo_path.to_string(), vec!["file.txt".to_string()]).unwrap();
+        let oid = commit_with(repo_path, "signed", CommitOptions::default()).unwrap();
+
+        let oid = git2::Oid::from_str(&oid).unwrap();
+        let (signature, _) = repo.extract_signature(&oid, None).unwrap();
+        assert!(signature
+            .as_str()
+            .unwrap()
+            .starts_with("-----BEGIN SSH SIGNATURE-----"));
+        assert_eq!(repo.head().unwrap().target(), Some(oid));
+
+        // An explicit `sign: false` overrides commit.gpgsign.
+        let options = CommitOptions {
+            sign: Some(false),
+            allow_empty: true,
+            ..Default::default()
+        };
+        let oid = commit_with(repo_path, "unsigned", options).unwrap();
+        let oid = git2::Oid::from_str(&oid).unwrap();
+        assert!(repo.extract_signature(&oid, None).is_err());
+    }
+
+    // --- commit hook tests ---
+
+    #[cfg(unix)]
+    fn write_hook(hooks_dir: &Path, name: &str, script: &str) {
+        use std::os::unix::fs::PermissionsExt;
+        fs::create_dir_all(hooks_dir).unwrap();
+        let path = hooks_dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commit_blocked_by_failing_pre_commit_hook() {
+        let (dir, repo) = create_test_repo();
+        let root = create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap();
+        write_hook(
+            &repo.path().join("hooks"),
+            "pre-commit",
+            "echo checking\necho 'lint failed' >&2\nexit 3",
+        );
+        fs::write(dir.path().join("file.txt"), "content\n").unwrap();
+        git_stage(repo_path.to_string(), vec!["file.txt".to_string()]).unwrap();
+
+        let lines = RefCell::new(Vec::new());
+        let err = create_commit(
+            repo_path,
+            "blocked".to_string(),
+            CommitOptions::default(),
+            &|out: HookOutput| lines.borrow_mut().push((out.hook, out.stream, out.line)),
+        )
+        .unwrap_err();
+        assert_eq!(err.reason, "hook_failed");
+        assert_eq!(err.hook.as_deref(), Some("pre-commit"));
+        assert_eq!(err.exit_code, Some(3));
+        assert!(err.output.contains("lint failed"));
+        assert!(lines.borrow().contains(&(
+            "pre-commit".to_string(),
+            "stderr".to_string(),
+            "lint failed".to_string()
+        )));
+        assert_eq!(repo.head().unwrap().target(), Some(root));
+
+        let options = CommitOptions {
+            skip_hooks: true,
+            ..Default::default()
+        };
+        commit_with(repo_path, "no verify", options).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.summary(), Some("no verify"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commit_msg_hooks_edit_message() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap();
+        let hooks = repo.path().join("hooks");
+        write_hook(&hooks, "prepare-commit-msg", "echo \"source=$2\" >> \"$1\"");
+        write_hook(&hooks, "commit-msg", "echo 'Change-Id: I123' >> \"$1\"");
+        write_hook(&hooks, "post-commit", "touch post-commit-ran");
+        fs::write(dir.path().join("file.txt"), "content\n").unwrap();
+        git_stage(repo_path.to_string(), vec!["file.txt".to_string()]).unwrap();
+
+        commit_with(repo_path, "add file\n", CommitOptions::default()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(
+            head.message(),
+            Some("add file\nsource=message\nChange-Id: I123\n")
+        );
+        assert!(dir.path().join("post-commit-ran").exists());
+
+        // --no-verify skips commit-msg but still runs prepare-commit-msg.
+        let options = CommitOptions {
+            skip_hooks: true,
+            allow_empty: true,
+            ..Default::default()
+        };
+        commit_with(repo_path, "second\n", options).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.message(), Some("second\nsource=message\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commit_failing_commit_msg_hook_and_hooks_path() {
+        let (dir, repo) = create_test_repo();
+        let root = create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap();
+        // A hook in .git/hooks is ignored once core.hooksPath is set.
+        write_hook(&repo.path().join("hooks"), "commit-msg", "exit 0");
+        write_hook(
+            &dir.path().join("ci-hooks"),
+            "commit-msg",
+            "grep -q '^feat:' \"$1\" || { echo 'use a conventional prefix'; exit 1; }",
+        );
+        repo.config()
+            .unwrap()
+            .set_str("core.hooksPath", "ci-hooks")
+            .unwrap();
+        fs::write(dir.path().join("file.txt"), "content\n").unwrap();
+        git_stage(repo_path.to_string(), vec!["file.txt".to_string()]).unwrap();
+
+        let err = commit_with(repo_path, "wip", CommitOptions::default()).unwrap_err();
+        assert_eq!(err.hook.as_deref(), Some("commit-msg"));
+        assert!(err.output.contains("conventional prefix"));
+        assert_eq!(repo.head().unwrap().target(), Some(root));
+
+        commit_with(repo_path, "feat: add file", CommitOptions::default()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.summary(), Some("feat: add file"));
+    }
+
+    // --- discard tests ---
+
+    #[test]
+    fn test_discard_files_from_index_with_backup() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "committed\n", "add file");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        fs::write(dir.path().join("file.txt"), "staged\n").unwrap();
+        git_stage(repo_path.clone(), vec!["file.txt".to_string()]).unwrap();
+        fs::write(dir.path().join("file.txt"), "agent edit\n").unwrap();
+        fs::write(dir.path().join("scratch.txt"), "untracked\n").unwrap();
+        fs::create_dir(dir.path().join("tmp")).unwrap();
+        fs::write(dir.path().join("tmp/out.log"), "log\n").unwrap();
+
+        let backup = git_discard_files(
+            repo_path.clone(),
+            vec![
+                "file.txt".to_string(),
+                "scratch.txt".to_string(),
+                "tmp/".to_string(),
+            ],
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            "staged\n"
+        );
+        assert!(!dir.path().join("scratch.txt").exists());
+        assert!(!dir.path().join("tmp").exists());
+        assert_eq!(
+            backup.paths,
+            vec![
+                "file.txt".to_string(),
+                "scratch.txt".to_string(),
+                "tmp/out.log".to_string()
+            ]
+        );
+
+        let backups = list_discard_backups(repo_path.clone()).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].id, backup.id);
+
+        git_restore_discard(repo_path, backup.id).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            "agent edit\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("tmp/out.log")).unwrap(),
+            "log\n"
+        );
+        assert!(dir.path().join("scratch.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discard_backs_up_symlinks_without_following_them() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("secret.txt"), "outside\n").unwrap();
+        fs::create_dir(dir.path().join("tmp")).unwrap();
+        std::os::unix::fs::symlink(".", dir.path().join("tmp/loop")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("tmp/ext")).unwrap();
+
+        let backup = git_discard_files(repo_path.clone(), vec!["tmp/".to_string()], None).unwrap();
+        assert_eq!(
+            backup.paths,
+            vec!["tmp/ext".to_string(), "tmp/loop".to_string()]
+        );
+        assert!(!dir.path().join("tmp").exists());
+        assert!(outside.path().join("secret.txt").exists());
+
+        git_restore_discard(repo_path, backup.id).unwrap();
+        assert_eq!(
+            fs::read_link(dir.path().join("tmp/loop")).unwrap(),
+            Path::new(".")
+        );
+    }
+
+    #[test]
+    fn test_discard_files_from_head() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "committed\n", "add file");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        fs::write(dir.path().join("file.txt"), "staged\n").unwrap();
+        fs::write(dir.path().join("new.txt"), "new\n").unwrap();
+        git_stage(
+            repo_path.clone(),
+            vec!["file.txt".to_string(), "new.txt".to_string()],
+        )
+        .unwrap();
+        fs::remove_file(dir.path().join("file.txt")).unwrap();
+
+        let backup = git_discard_files(
+            repo_path.clone(),
+            vec!["file.txt".to_string(), "new.txt".to_string()],
+            Some("head".to_string()),
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            "committed\n"
+        );
+        assert!(!dir.path().join("new.txt").exists());
+        let status = get_git_status(repo_path.clone()).unwrap();
+        assert!(status.is_empty(), "expected clean status");
+
+        // The deleted file was absent when backed up, so restoring removes it again.
+        git_restore_discard(repo_path, backup.id).unwrap();
+        assert!(!dir.path().join("file.txt").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("new.txt")).unwrap(),
+            "new\n"
+        );
+
+        // The staged versions come back as well.
+        let repo = Repository::open(dir.path()).unwrap();
+        let index = repo.index().unwrap();
+        let staged = |path: &str| {
+            let entry = index.get_path(Path::new(path), 0).unwrap();
+            repo.find_blob(entry.id).unwrap().content().to_vec()
+        };
+        assert_eq!(staged("file.txt"), b"staged\n");
+        assert_eq!(staged("new.txt"), b"new\n");
+    }
+
+    #[test]
+    fn test_discard_hunk_reverts_worktree_only() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let original: String = (1..=20).map(|i| format!("line {i}\n")).collect();
+        add_and_commit(&repo, "file.txt", &original, "add file");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let edited = original
+            .replace("line 2\n", "line 2 edited\n")
+            .replace("line 18\n", "line 18 edited\n");
+        fs::write(dir.path().join("file.txt"), &edited).unwrap();
+
+        let diffs = get_file_diff(
+            repo_path.clone(),
+            "index_to_worktree".to_string(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(diffs[0].hunks.len(), 2);
+        git_discard_hunk(repo_path.clone(), diffs[0].hunks[0].patch_base64.clone()).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            original.replace("line 18\n", "line 18 edited\n")
+        );
+        let status = get_git_status(repo_path).unwrap();
+        assert_eq!(status[0].index_status, "none");
+    }
+
+    #[test]
+    fn test_discard_lines_selects_changes_in_range() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let original: String = (1..=10).map(|i| format!("line {i}\n")).collect();
+        add_and_commit(&repo, "file.txt", &original, "add file");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        // Edit line 2, delete line 5, and append a line at the end.
+        let edited = "line 1\nline 2 edited\nline 3\nline 4\nline 6\nline 7\nline 8\nline 9\nline 10\nextra\n";
+        fs::write(dir.path().join("file.txt"), edited).unwrap();
+
+        let discard =
+            |start, end| git_discard_lines(repo_path.clone(), "file.txt".to_string(), start, end);
+
+        // Worktree line 5 ("line 6") follows the deleted block.
+        discard(5, 5).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            "line 1\nline 2 edited\nline 3\nline 4\nline 5\nline 6\nline 7\nline 8\nline 9\nline 10\nextra\n"
+        );
+
+        discard(11, 11).unwrap();
+        discard(2, 2).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            original
+        );
+
+        let err = discard(1, 3).err().unwrap();
+        assert!(err.contains("no changes"));
+        assert_eq!(list_discard_backups(repo_path).unwrap().len(), 3);
+    }
+
+    // --- git_create_branch tests ---
+
+    #[test]
+    fn test_create_branch() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+
+        git_create_branch(
+            dir.path().to_str().unwrap().to_string(),
+            "feature".to_string(),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let branch = get_current_branch(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(branch, "feature");
+    }
+
+    #[test]
+    fn test_get_git_status_ignored_file() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+
+        fs::write(dir.path().join(".gitignore"), "ignored.txt\nbuild/\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(".gitignore")).unwrap();
+        index.write().unwrap();
+        let sig = Signature::now("Test User", "test@example.com").unwrap();
+        let tree_id = index.write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "add gitignore", &tree, &[&parent])
+            .unwrap();
+
+        fs::write(dir.path().join("ignored.txt"), "should be ignored").unwrap();
+        fs::create_dir(dir.path().join("build")).unwrap();
+        fs::write(dir.path().join("build").join("output.js"), "built").unwrap();
+
+        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+
+        let ignored_file = result.iter().find(|e| e.path == "ignored.txt");
+        assert!(
+            ignored_file.is_some(),
+            "ignored.txt should appear in status"
+        );
+        assert_eq!(ignored_file.unwrap().worktree_status, "ignored");
+        assert_eq!(ignored_file.unwrap().index_status, "none");
+
+        let ignored_dir = result.iter().find(|e| e.path == "build");
+        assert!(ignored_dir.is_some(), "build dir should appear in status");
+        assert_eq!(ignored_dir.unwrap().worktree_status, "ignored");
+    }
+
+    fn encode_patch(patch: &str) -> String {
+        use base64::Engine;
+        base64::engine::general_purpose::STANDARD.encode(patch)
+    }
+
+    fn hunk_patch_text(hunk: &DiffHunkInfo) -> String {
+        String::from_utf8_lossy(&decode_patch(&hunk.patch_base64).unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_stage_hunk() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "line1\nline2\nline3\n", "add file");
+
+        // Modify the file
+        fs::write(dir.path().join("file.txt"), "line1\nmodified\nline3\n").unwrap();
+
+        // Generate a patch for the modification
+        let patch =
+            "--- a/file.txt\n+++ b/file.txt\n@@ -1,3 +1,3 @@\n line1\n-line2\n+modified\n line3\n";
+
+        git_stage_hunk(
+            dir.path().to_str().unwrap().to_string(),
+            encode_patch(patch),
+        )
+        .unwrap();
+
+        let statuses = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert!(statuses.iter().any(|s| s.index_status == "modified"));
+    }
+
+    #[test]
+    fn test_unstage_hunk() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "line1\nline2\nline3\n", "add file");
+
+        // Stage the modified file first
+        fs::write(dir.path().join("file.txt"), "line1\nmodified\nline3\n").unwrap();
+        git_stage(
+            dir.path().to_str().unwrap().to_string(),
+            vec!["file.txt".to_string()],
+        )
+        .unwrap();
+
+        // Verify staged
+        let before = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert!(before.iter().any(|s| s.index_status == "modified"));
+
+        // Unstage via patch
+        let patch =
+            "--- a/file.txt\n+++ b/file.txt\n@@ -1,3 +1,3 @@\n line1\n-line2\n+modified\n line3\n";
+        git_unstage_hunk(
+            dir.path().to_str().unwrap().to_string(),
+            encode_patch(patch),
+        )
+        .unwrap();
+
+        let after = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        // After unstage, file should show worktree modified but not index modified
+        let file_status = after.iter().find(|s| s.path == "file.txt").unwrap();
+        assert_eq!(file_status.worktree_status, "modified");
+        assert_eq!(file_status.index_status, "none");
+    }
+
+    #[test]
+    fn test_stage_hunk_second_of_two() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let original: String = (1..=20).map(|i| format!("line{i}\n")).collect();
+        add_and_commit(&repo, "file.txt", &original, "add file");
+        let modified = original
+            .replace("line2\n", "changed2\n")
+            .replace("line18\n", "changed18\n");
+        fs::write(dir.path().join("file.txt"), &modified).unwrap();
+
+        let patch = "--- a/file.txt\n+++ b/file.txt\n@@ -15,6 +15,6 @@\n line15\n line16\n line17\n-line18\n+changed18\n line19\n line20\n";
+        git_stage_hunk(
+            dir.path().to_str().unwrap().to_string(),
+            encode_patch(patch),
+        )
+        .unwrap();
+
+        let staged =
+            get_staged_content(dir.path().join("file.txt").to_str().unwrap().to_string()).unwrap();
+        assert_eq!(staged.content, original.replace("line18\n", "changed18\n"));
+    }
+
+    #[test]
+    fn test_stage_hunk_context_mismatch() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "line1\nline2\nline3\n", "add file");
+
+        let patch =
+            "--- a/file.txt\n+++ b/file.txt\n@@ -1,3 +1,3 @@\n line1\n-other\n+modified\n line3\n";
+        let err = git_stage_hunk(
+            dir.path().to_str().unwrap().to_string(),
+            encode_patch(patch),
+        )
+        .unwrap_err();
+        assert_eq!(err.reason, "context_mismatch");
+        assert_eq!(err.path.as_deref(), Some("file.txt"));
+        assert_eq!(err.hunk_index, Some(0));
+        assert_eq!(err.hunk_header.as_deref(), Some("@@ -1,3 +1,3 @@"));
+    }
+
+    #[test]
+    fn test_stage_hunk_missing_file() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+
+        let patch =
+            "--- a/gone.txt\n+++ b/gone.txt\n@@ -1,3 +1,3 @@\n line1\n-line2\n+modified\n line3\n";
+        let err = git_stage_hunk(
+            dir.path().to_str().unwrap().to_string(),
+            encode_patch(patch),
+        )
+        .unwrap_err();
+        assert_eq!(err.reason, "missing_file");
+        assert_eq!(err.path.as_deref(), Some("gone.txt"));
+        assert_eq!(err.hunk_index, None);
+    }
+
+    #[test]
+    fn test_stage_hunk_binary() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "img.png", "\u{0}png", "add image");
+
+        let patch = "diff --git a/img.png b/img.png\nindex 1111111..2222222 100644\nBinary files a/img.png and b/img.png differ\n";
+        let err = git_stage_hunk(
+            dir.path().to_str().unwrap().to_string(),
+            encode_patch(patch),
+        )
+        .unwrap_err();
+        assert_eq!(err.reason, "binary");
+        assert_eq!(err.path.as_deref(), Some("img.png"));
+    }
+
+    #[test]
+    fn test_stage_hunk_invalid_patch() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+
+        let err = git_stage_hunk(
+            dir.path().to_str().unwrap().to_string(),
+            encode_patch("not a patch"),
+        )
+        .unwrap_err();
+        assert_eq!(err.reason, "invalid_patch");
+    }
+
+    #[test]
+    fn test_stage_and_unstage_hunk_new_file() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        fs::write(dir.path().join("new.txt"), "a\nb").unwrap();
+
+        let patch = "diff --git a/new.txt b/new.txt\nnew file mode 100644\n--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+a\n+b\n\\ No newline at end of file\n";
+        git_stage_hunk(
+            dir.path().to_str().unwrap().to_string(),
+            encode_patch(patch),
+        )
+        .unwrap();
+        let staged = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(staged[0].index_status, "new");
+        assert_eq!(staged[0].worktree_status, "none");
+
+        git_unstage_hunk(
+            dir.path().to_str().unwrap().to_string(),
+            encode_patch(patch),
+        )
+        .unwrap();
+        let unstaged = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(unstaged[0].index_status, "none");
+        assert_eq!(unstaged[0].worktree_status, "new");
+    }
+
+    #[test]
+    fn test_reverse_patch() {
+        let patch = "diff --git a/f.txt b/f.txt\nindex 1111111..2222222 100644\n--- a/f.txt\n+++ b/f.txt\n@@ -1,3 +1,2 @@\n a\n-b\n--- c\n+d\n\\ No newline at end of file\n";
+        let reversed = reverse_patch(patch);
+        assert_eq!(
+            reversed,
+            "diff --git a/f.txt b/f.txt\nindex 2222222..1111111 100644\n--- a/f.txt\n+++ b/f.txt\n@@ -1,2 +1,3 @@\n a\n-d\n\\ No newline at end of file\n+b\n+-- c\n"
+        );
+    }
+
+    // --- get_file_diff tests ---
+
+    #[test]
+    fn test_get_file_diff_index_to_worktree() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "line1\nline2\nline3\n", "add file");
+        fs::write(dir.path().join("file.txt"), "line1\nmodified\nline3\n").unwrap();
+
+        let result = get_file_diff(
+            dir.path().to_str().unwrap().to_string(),
+            "index_to_worktree".to_string(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.len(), 1);
+        let file = &result[0];
+        assert_eq!(file.status, "modified");
+        assert_eq!(file.new_path.as_deref(), Some("file.txt"));
+        assert_eq!((file.additions, file.deletions), (1, 1));
+        assert_eq!(file.hunks.len(), 1);
+
+        let hunk = &file.hunks[0];
+        assert_eq!(hunk.header, "@@ -1,3 +1,3 @@");
+        let origins: Vec<&str> = hunk.lines.iter().map(|l| l.origin.as_str()).collect();
+        assert_eq!(origins, vec!["context", "deletion", "addition", "context"]);
+        assert_eq!(hunk.lines[1].old_lineno, Some(2));
+        assert_eq!(hunk.lines[1].new_lineno, None);
+        assert_eq!(hunk.lines[2].new_lineno, Some(2));
+        assert_eq!(hunk.lines[2].content, "modified");
+    }
+
+    #[test]
+    fn test_get_file_diff_hunk_patch_round_trip() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "a\nb", "add file");
+        fs::write(dir.path().join("file.txt"), "a\nc").unwrap();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let unstaged = get_file_diff(
+            repo_path.clone(),
+            "index_to_worktree".to_string(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let hunk = &unstaged[0].hunks[0];
+        assert!(hunk.lines.iter().any(|l| l.no_newline_at_eof));
+        assert!(hunk_patch_text(hunk).contains("\\ No newline at end of file"));
+
+        git_stage_hunk(repo_path.clone(), hunk.patch_base64.clone()).unwrap();
+        let staged = get_file_diff(
+            repo_path.clone(),
+            "head_to_index".to_string(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(staged.len(), 1);
+        assert_eq!(staged[0].hunks[0].patch_base64, hunk.patch_base64);
+
+        git_unstage_hunk(repo_path.clone(), staged[0].hunks[0].patch_base64.clone()).unwrap();
+        let staged_after =
+            get_file_diff(repo_path, "head_to_index".to_string(), None, None, None).unwrap();
+        assert!(staged_after.is_empty());
+    }
+
+    #[test]
+    fn test_hunk_patch_round_trip_non_utf8() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let original = b"caf\xe9\r\nna\xefve\n".to_vec();
+        let edited = b"caf\xe9\r\nna\xefve\nd\xe9j\xe0 vu\r\n".to_vec();
+        add_and_commit_bytes(&repo, "file.txt", &original);
+        fs::write(dir.path().join("file.txt"), &edited).unwrap();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let staged_blob = || {
+            let repo = Repository::open(&repo_path).unwrap();
+            let index = repo.index().unwrap();
+            let entry = index.get_path(Path::new("file.txt"), 0).unwrap();
+            let content = repo.find_blob(entry.id).unwrap().content().to_vec();
+            content
+        };
+
+        let unstaged = get_file_diff(
+            repo_path.clone(),
+            "index_to_worktree".to_string(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let patch = unstaged[0].hunks[0].patch_base64.clone();
+        git_stage_hunk(repo_path.clone(), patch.clone()).unwrap();
+        assert_eq!(staged_blob(), edited);
+
+        let staged = get_file_diff(
+            repo_path.clone(),
+            "head_to_index".to_string(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(staged[0].hunks[0].patch_base64, patch);
+        git_unstage_hunk(repo_path.clone(), patch.clone()).unwrap();
+        assert_eq!(staged_blob(), original);
+
+        git_discard_hunk(repo_path.clone(), patch).unwrap();
+        assert_eq!(fs::read(dir.path().join("file.txt")).unwrap(), original);
+    }
+
+    #[test]
+    fn test_stage_zero_context_hunks_bottom_up() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let original: String = (1..=6).map(|i| format!("line{i}\n")).collect();
+        add_and_commit(&repo, "file.txt", &original, "add file");
+        let modified = original
+            .replace("line2\n", "changed2\n")
+            .replace("line4\n", "line4\ninserted\n")
+            .replace("line6\n", "");
+        fs::write(dir.path().join("file.txt"), &modified).unwrap();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let diff = get_file_diff(
+            repo_path.clone(),
+            "index_to_worktree".to_string(),
+            None,
+            Some(0),
+            None,
+        )
+        .unwrap();
+        assert_eq!(diff[0].hunks.len(), 3);
+        git_stage_hunk(repo_path.clone(), diff[0].hunks[2].patch_base64.clone()).unwrap();
+        let staged =
+            get_staged_content(dir.path().join("file.txt").to_str().unwrap().to_string()).unwrap();
+        assert_eq!(staged.content, original.replace("line6\n", ""));
+
+        let diff = get_file_diff(
+            repo_path.clone(),
+            "index_to_worktree".to_string(),
+            None,
+            Some(0),
+            None,
+        )
+        .unwrap();
+        for hunk in diff[0].hunks.iter().rev() {
+            git_stage_hunk(repo_path.clone(), hunk.patch_base64.clone()).unwrap();
+        }
+        let staged =
+            get_staged_content(dir.path().join("file.txt").to_str().unwrap().to_string()).unwrap();
+        assert_eq!(staged.content, modified);
+
+        let diff = get_file_diff(
+            repo_path.clone(),
+            "head_to_index".to_string(),
+            None,
+            Some(0),
+            None,
+        )
+        .unwrap();
+        for hunk in diff[0].hunks.iter().rev() {
+            git_unstage_hunk(repo_path.clone(), hunk.patch_base64.clone()).unwrap();
+        }
+        let staged =
+            get_staged_content(dir.path().join("file.txt").to_str().unwrap().to_string()).unwrap();
+        assert_eq!(staged.content, original);
+    }
+
+    #[test]
+    fn test_get_file_diff_untracked_and_binary() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        fs::write(dir.path().join("new.txt"), "hello\n").unwrap();
+        fs::write(dir.path().join("blob.bin"), [0u8, 1, 2, 3]).unwrap();
+
+        let result = get_file_diff(
+            dir.path().to_str().unwrap().to_string(),
+            "head_to_worktree".to_string(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let text = result
+            .iter()
+            .find(|f| f.new_path.as_deref() == Some("new.txt"))
+            .unwrap();
+        assert_eq!(text.status, "new");
+        assert!(hunk_patch_text(&text.hunks[0]).contains("--- /dev/null"));
+
+        let binary = result
+            .iter()
+            .find(|f| f.new_path.as_deref() == Some("blob.bin"))
+            .unwrap();
+        assert!(binary.binary);
+        assert!(binary.hunks.is_empty());
+    }
+
+    #[test]
+    fn test_get_file_diff_pathspec_and_unknown_mode() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        fs::write(dir.path().join("a.txt"), "a\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "b\n").unwrap();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let result = get_file_diff(
+            repo_path.clone(),
+            "index_to_worktree".to_string(),
+            Some(vec!["b.txt".to_string()]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].new_path.as_deref(), Some("b.txt"));
+
+        assert!(get_file_diff(repo_path, "bogus".to_string(), None, None, None).is_err());
+    }
+
+    // --- ref-to-ref diff tests ---
+
+    // Main gains `main.txt`; branch `agent` (from the shared base) edits
+    // `shared.txt` and adds `agent.txt`.
+    fn create_diverged_branches(repo: &Repository) -> String {
+        create_initial_commit(repo);
+        add_and_commit(repo, "shared.txt", "one\ntwo\n", "base");
+        let main = repo.head().unwrap().name().unwrap().to_string();
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("agent", &base, false).unwrap();
+
+        add_and_commit(repo, "main.txt", "main\n", "main work");
+        repo.set_head("refs/heads/agent").unwrap();
+        repo.reset(base.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+        add_and_commit(repo, "shared.txt", "one\nTWO\nthree\n", "agent edit");
+        add_and_commit(repo, "agent.txt", "agent\n", "agent add");
+        repo.set_head(&main).unwrap();
+        let main_tip = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.reset(main_tip.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+        main
+    }
+
+    #[test]
+    fn test_get_ref_diff_merge_base() {
+        let (dir, repo) = create_test_repo();
+        let main = create_diverged_branches(&repo);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let diff = get_ref_diff(
+            repo_path,
+            main.clone(),
+            Some("agent".to_string()),
+            Some(true),
+            None,
+            None,
+        )
+        .unwrap();
+        let base = repo
+            .find_reference(&main)
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .parent_id(0)
+            .unwrap();
+        assert_eq!(diff.merge_base, Some(base.to_string()));
+        assert_eq!(diff.base, base.to_string());
+        assert_eq!((diff.ahead, diff.behind), (2, 1));
+        let paths: Vec<&str> = diff
+            .files
+            .iter()
+            .filter_map(|f| f.new_path.as_deref())
+            .collect();
+        assert_eq!(paths, vec!["agent.txt", "shared.txt"]);
+        assert_eq!(diff.files_changed, 2);
+        assert_eq!((diff.insertions, diff.deletions), (3, 1));
+        assert_eq!(diff.files[1].hunks.len(), 1);
+    }
+
+    #[test]
+    fn test_get_ref_diff_direct_and_paths() {
+        let (dir, repo) = create_test_repo();
+        let main = create_diverged_branches(&repo);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        // A plain two-dot diff also shows main's own commit, reversed.
+        let diff = get_ref_diff(
+            repo_path.clone(),
+            main.clone(),
+            Some("agent".to_string()),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(diff.merge_base.is_none());
+        let main_file = diff
+            .files
+            .iter()
+            .find(|f| f.old_path.as_deref() == Some("main.txt"))
+            .unwrap();
+        assert_eq!(main_file.status, "deleted");
+        assert_eq!(diff.files_changed, 3);
+
+        let only_shared = get_ref_diff(
+            repo_path.clone(),
+            main,
+            Some("agent".to_string()),
+            Some(true),
+            Some(vec!["shared.txt".to_string()]),
+            Some(0),
+        )
+        .unwrap();
+        assert_eq!(only_shared.files.len(), 1);
+        assert_eq!(only_shared.files_changed, 1);
+
+        assert!(get_ref_diff(repo_path, "nope".to_string(), None, None, None, None).is_err());
+    }
+
+    #[test]
+    fn test_get_file_diff_against_merge_base() {
+        let (dir, repo) = create_test_repo();
+        let main = create_diverged_branches(&repo);
+        repo.set_head("refs/heads/agent").unwrap();
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+        fs::write(dir.path().join("shared.txt"), "one\nTWO\nthree\nfour\n").unwrap();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let base = get_merge_base(repo_path.clone(), main.clone(), None).unwrap();
+        let main_parent = repo
+            .find_reference(&main)
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .parent_id(0)
+            .unwrap();
+        assert_eq!(base, main_parent.to_string());
+
+        let diff = get_file_diff(
+            repo_path.clone(),
+            "commit_to_worktree".to_string(),
+            Some(vec!["shared.txt".to_string()]),
+            None,
+            Some(base),
+        )
+        .unwrap();
+        assert_eq!(diff.len(), 1);
+        let added: Vec<&str> = diff[0].hunks[0]
+            .lines
+            .iter()
+            .filter(|l| l.origin == "addition")
+            .map(|l| l.content.as_str())
+            .collect();
+        assert_eq!(added, vec!["TWO", "three", "four"]);
+
+        assert!(get_file_diff(
+            repo_path,
+            "commit_to_worktree".to_string(),
+            None,
+            None,
+            None
+        )
+        .is_err());
+    }
+
+    // --- rename / copy detection tests ---
+
+    const RENAME_CONTENT: &str = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\n";
+
+    #[test]
+    fn test_get_git_status_staged_rename() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "old.txt", RENAME_CONTENT, "add file");
+        fs::rename(dir.path().join("old.txt"), dir.path().join("new.txt")).unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("old.txt")).unwrap();
+        index.add_path(Path::new("new.txt")).unwrap();
+        index.write().unwrap();
+
+        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "new.txt");
+        assert_eq!(result[0].index_status, "renamed");
+        assert_eq!(result[0].worktree_status, "none");
+        assert_eq!(result[0].old_path.as_deref(), Some("old.txt"));
+        assert_eq!(result[0].similarity, Some(100));
+    }
+
+    #[test]
+    fn test_get_git_status_worktree_rename() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "old.txt", RENAME_CONTENT, "add file");
+        fs::remove_file(dir.path().join("old.txt")).unwrap();
+        fs::write(
+            dir.path().join("new.txt"),
+            RENAME_CONTENT.replace("eight", "EIGHT"),
+        )
+        .unwrap();
+
+        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "new.txt");
+        assert_eq!(result[0].index_status, "none");
+        assert_eq!(result[0].worktree_status, "renamed");
+        assert_eq!(result[0].old_path.as_deref(), Some("old.txt"));
+        let similarity = result[0].similarity.unwrap();
+        assert!(similarity > 50 && similarity < 100);
+    }
+
+    #[test]
+    fn test_get_git_status_staged_copy() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "src.txt", RENAME_CONTENT, "add file");
+        fs::write(
+            dir.path().join("src.txt"),
+            format!("{RENAME_CONTENT}nine\n"),
+        )
+        .unwrap();
+        fs::write(dir.path().join("dup.txt"), RENAME_CONTENT).unwrap();
+        git_stage(dir.path().to_str().unwrap().to_string(), vec![]).unwrap();
+
+        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        let dup = result.iter().find(|s| s.path == "dup.txt").unwrap();
+        assert_eq!(dup.index_status, "copied");
+        assert_eq!(dup.old_path.as_deref(), Some("src.txt"));
+        assert_eq!(dup.similarity, Some(100));
+        let src = result.iter().find(|s| s.path == "src.txt").unwrap();
+        assert_eq!(src.index_status, "modified");
+        assert_eq!(src.old_path, None);
+    }
+
+    #[test]
+    fn test_stage_and_unstage_rename_by_new_path() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "old.txt", RENAME_CONTENT, "add file");
+        fs::rename(dir.path().join("old.txt"), dir.path().join("new.txt")).unwrap();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        git_stage(repo_path.clone(), vec!["new.txt".to_string()]).unwrap();
+        let staged = get_git_status(repo_path.clone()).unwrap();
+        assert_eq!(staged.len(), 1);
+        assert_eq!(staged[0].index_status, "renamed");
+        assert_eq!(staged[0].worktree_status, "none");
+
+        git_unstage(repo_path.clone(), vec!["new.txt".to_string()]).unwrap();
+        let unstaged = get_git_status(repo_path).unwrap();
+        assert_eq!(unstaged.len(), 1);
+        assert_eq!(unstaged[0].index_status, "none");
+        assert_eq!(unstaged[0].worktree_status, "renamed");
+    }
+
+    #[test]
+    fn test_get_file_diff_rename_hunk_stages_pair() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "old.txt", RENAME_CONTENT, "add file");
+        fs::remove_file(dir.path().join("old.txt")).unwrap();
+        fs::write(
+            dir.path().join("new.txt"),
+            RENAME_CONTENT.replace("eight", "EIGHT"),
+        )
+        .unwrap();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let diff = get_file_diff(
+            repo_path.clone(),
+            "index_to_worktree".to_string(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].status, "renamed");
+        assert_eq!(diff[0].old_path.as_deref(), Some("old.txt"));
+        assert_eq!(diff[0].new_path.as_deref(), Some("new.txt"));
+        assert!(diff[0].similarity.is_some());
+        assert!(hunk_patch_text(&diff[0].hunks[0]).contains("rename from old.txt"));
+
+        git_stage_hunk(repo_path.clone(), diff[0].hunks[0].patch_base64.clone()).unwrap();
+        let status = get_git_status(repo_path).unwrap();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].index_status, "renamed");
+        assert_eq!(status[0].worktree_status, "none");
+    }
+
+    // --- merge conflict tests ---
+
+    // Commits `theirs` for `path` on a new branch off HEAD's parent state,
+    // `ours` on the current branch, then merges to leave a conflict.
+    fn create_merge_conflict(repo: &Repository, path: &str, ours: &str, theirs: &str) {
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+        let sig = Signature::now("Test User", "test@example.com").unwrap();
+        let blob = repo.blob(theirs.as_bytes()).unwrap();
+        let mut builder = repo.treebuilder(Some(&base.tree().unwrap())).unwrap();
+        builder.insert(path, blob, 0o100644).unwrap();
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        let theirs_oid = repo
+            .commit(
+                Some("refs/heads/other"),
+                &sig,
+                &sig,
+                "theirs",
+                &tree,
+                &[&base],
+            )
+            .unwrap();
+
+        add_and_commit(repo, path, ours, "ours");
+
+        let annotated = repo.find_annotated_commit(theirs_oid).unwrap();
+        repo.merge(&[&annotated], None, None).unwrap();
+    }
+
+    #[test]
+    fn test_get_git_status_conflicted() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "base\n", "base");
+        create_merge_conflict(&repo, "file.txt", "ours\n", "theirs\n");
+
+        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        let entry = result.iter().find(|s| s.path == "file.txt").unwrap();
+        assert_eq!(entry.index_status, "conflicted");
+        assert_eq!(entry.worktree_status, "conflicted");
+    }
+
+    #[test]
+    fn test_get_conflict_content() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "base\n", "base");
+        create_merge_conflict(&repo, "file.txt", "ours\n", "theirs\n");
+
+        let content = get_conflict_content(
+            dir.path().to_str().unwrap().to_string(),
+            "file.txt".to_string(),
+        )
+        .unwrap();
+        assert_eq!(content.base.unwrap().content, "base\n");
+        assert_eq!(content.ours.unwrap().content, "ours\n");
+        assert_eq!(content.theirs.unwrap().content, "theirs\n");
+
+        let missing = get_conflict_content(
+            dir.path().to_str().unwrap().to_string(),
+            "other.txt".to_string(),
+        );
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_resolve_conflict_theirs_and_commit_merge() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "base\n", "base");
+        create_merge_conflict(&repo, "file.txt", "ours\n", "theirs\n");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let blocked = create_commit(
+            &repo_path,
+            "merge".to_string(),
+            CommitOptions::default(),
+            &|_| {},
+        );
+        assert!(blocked
+            .unwrap_err()
+            .message
+            .contains("unresolved conflicts"));
+
+        git_resolve_conflict(
+            repo_path.clone(),
+            "file.txt".to_string(),
+            "theirs".to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            "theirs\n"
+        );
+        let status = get_git_status(repo_path.clone()).unwrap();
+        assert_eq!(status[0].index_status, "modified");
+        assert_eq!(status[0].worktree_status, "none");
+
+        create_commit(
+            &repo_path,
+            "merge other".to_string(),
+            CommitOptions::default(),
+            &|_| {},
+        )
+        .unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_count(), 2);
+        assert_eq!(repo.state(), git2::RepositoryState::Clean);
+    }
+
+    #[test]
+    fn test_resolve_conflict_mark_resolved() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "base\n", "base");
+        create_merge_conflict(&repo, "file.txt", "ours\n", "theirs\n");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        fs::write(dir.path().join("file.txt"), "ours and theirs\n").unwrap();
+        git_resolve_conflict(
+            repo_path.clone(),
+            "file.txt".to_string(),
+            "mark_resolved".to_string(),
+        )
+        .unwrap();
+
+        let reopened = Repository::open(dir.path()).unwrap();
+        assert!(!reopened.index().unwrap().has_conflicts());
+        let staged =
+            get_staged_content(dir.path().join("file.txt").to_str().unwrap().to_string()).unwrap();
+        assert_eq!(staged.content, "ours and theirs\n");
+
+        let bogus = git_resolve_conflict(repo_path, "file.txt".to_string(), "ours".to_string());
+        assert!(bogus.is_err());
+    }
+
+    // --- worktree tests ---
+
+    #[test]
+    fn test_add_and_list_worktrees() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let wt_dir = TempDir::new().unwrap();
+        let wt_path = wt_dir.path().join("agent-1");
+
+        let info = git_add_worktree(
+            repo_path.clone(),
+            "agent-1".to_string(),
+            wt_path.to_str().unwrap().to_string(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(info.name.as_deref(), Some("agent-1"));
+        assert_eq!(info.branch.as_deref(), Some("agent-1"));
+        assert!(!info.is_main);
+        assert!(wt_path.exists());
+
+        let list = list_worktrees(repo_path).unwrap();
+        assert_eq!(list.len(), 2);
+        assert!(list[0].is_main);
+        assert_eq!(list[1].name.as_deref(), Some("agent-1"));
+        assert_eq!(list[1].head, list[0].head);
+
+        // Listing from inside the linked worktree still reports the main one first.
+        let from_linked = list_worktrees(wt_path.to_str().unwrap().to_string()).unwrap();
+        assert_eq!(from_linked.len(), 2);
+        assert!(from_linked[0].is_main);
+        assert_eq!(
+            Path::new(&from_linked[0].path).canonicalize().unwrap(),
+            dir.path().canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_add_worktree_from_ref() {
+        let (dir, repo) = create_test_repo();
+        let first = create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "content\n", "second");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let wt_dir = TempDir::new().unwrap();
+
+        let info = git_add_worktree(
+            repo_path.clone(),
+            "wt".to_string(),
+            wt_dir.path().join("wt").to_str().unwrap().to_string(),
+            Some("feature".to_string()),
+            Some(first.to_string()),
+        )
+        .unwrap();
+        assert_eq!(info.branch.as_deref(), Some("feature"));
+        assert_eq!(info.head, Some(first.to_string()));
+
+        // An existing local branch is checked out as-is.
+        repo.branch(
+            "existing",
+            &repo.head().unwrap().peel_to_commit().unwrap(),
+            false,
+        )
+        .unwrap();
+        let info = git_add_worktree(
+            repo_path.clone(),
+            "wt2".to_string(),
+            wt_dir.path().join("wt2").to_str().unwrap().to_string(),
+            None,
+            Some("existing".to_string()),
+        )
+        .unwrap();
+        assert_eq!(info.branch.as_deref(), Some("existing"));
+        assert!(wt_dir.path().join("wt2/file.txt").exists());
+
+        // A failed add leaves no new branch behind.
+        fs::write(wt_dir.path().join("taken"), "file\n").unwrap();
+        let result = git_add_worktree(
+            repo_path,
+            "wt3".to_string(),
+            wt_dir.path().join("taken").to_str().unwrap().to_string(),
+            Some("stray".to_string()),
+            None,
+        );
+        assert!(result.is_err());
+        assert!(repo.find_branch("stray", BranchType::Local).is_err());
+    }
+
+    #[test]
+    fn test_lock_and_remove_worktree() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let wt_dir = TempDir::new().unwrap();
+        let wt_path = wt_dir.path().join("wt");
+        git_add_worktree(
+            repo_path.clone(),
+            "wt".to_string(),
+            wt_path.to_str().unwrap().to_string(),
+            None,
+            None,
+        )
+        .unwrap();
+
+        git_lock_worktree(
+            repo_path.clone(),
+            "wt".to_string(),
+            Some("agent running".to_string()),
+        )
+        .unwrap();
+        let list = list_worktrees(repo_path.clone()).unwrap();
+        assert!(list[1].locked);
+        assert_eq!(list[1].lock_reason.as_deref(), Some("agent running"));
+        assert!(git_remove_worktree(repo_path.clone(), "wt".to_string(), false).is_err());
+
+        git_unlock_worktree(repo_path.clone(), "wt".to_string()).unwrap();
+        fs::write(wt_path.join("dirty.txt"), "x").unwrap();
+        let err = git_remove_worktree(repo_path.clone(), "wt".to_string(), false).unwrap_err();
+        assert!(err.contains("uncommitted"));
+
+        git_remove_worktree(repo_path.clone(), "wt".to_string(), true).unwrap();
+        assert!(!wt_path.exists());
+        assert_eq!(list_worktrees(repo_path).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_prune_worktrees() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let wt_dir = TempDir::new().unwrap();
+        for name in ["gone", "kept"] {
+            git_add_worktree(
+                repo_path.clone(),
+                name.to_string(),
+                wt_dir.path().join(name).to_str().unwrap().to_string(),
+                None,
+                None,
+            )
+            .unwrap();
+        }
+        fs::remove_dir_all(wt_dir.path().join("gone")).unwrap();
+
+        let list = list_worktrees(repo_path.clone()).unwrap();
+        let gone = list
+            .iter()
+            .find(|w| w.name.as_deref() == Some("gone"))
+            .unwrap();
+        assert!(gone.prunable);
+
+        let pruned = git_prune_worktrees(repo_path.clone()).unwrap();
+        assert_eq!(pruned, vec!["gone".to_string()]);
+        assert_eq!(list_worktrees(repo_path).unwrap().len(), 2);
+    }
+
+    // --- stash tests ---
+
+    #[test]
+    fn test_stash_save_list_and_pop() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "one\n", "add file");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        fs::write(dir.path().join("file.txt"), "two\n").unwrap();
+
+        let entry =
+            git_stash_save(repo_path.clone(), Some("park".to_string()), false, false).unwrap();
+        assert_eq!(entry.index, 0);
+        assert!(entry.message.contains("park"));
+        assert!(!entry.has_untracked);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            "one\n"
+        );
+        assert_eq!(list_stashes(repo_path.clone()).unwrap().len(), 1);
+
+        git_stash_pop(repo_path.clone(), 0, false).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            "two\n"
+        );
+        assert!(list_stashes(repo_path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stash_nothing_to_stash() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let result = git_stash_save(dir.path().to_str().unwrap().to_string(), None, false, false);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_stash_include_untracked_and_diff() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "one\n", "add file");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        fs::write(dir.path().join("file.txt"), "one\ntwo\n").unwrap();
+        fs::write(dir.path().join("new.txt"), "fresh\n").unwrap();
+
+        let entry = git_stash_save(repo_path.clone(), None, false, true).unwrap();
+        assert!(entry.has_untracked);
+        assert!(!dir.path().join("new.txt").exists());
+
+        let diffs = get_stash_diff(repo_path.clone(), 0, None).unwrap();
+        assert_eq!(diffs.len(), 2);
+        let modified = diffs
+            .iter()
+            .find(|d| d.new_path.as_deref() == Some("file.txt"))
+            .unwrap();
+        assert_eq!(modified.status, "modified");
+        assert_eq!(modified.additions, 1);
+        let added = diffs
+            .iter()
+            .find(|d| d.new_path.as_deref() == Some("new.txt"))
+            .unwrap();
+        assert_eq!(added.status, "new");
+
+        git_stash_apply(repo_path.clone(), 0, false).unwrap();
+        assert!(dir.path().join("new.txt").exists());
+        assert_eq!(list_stashes(repo_path.clone()).unwrap().len(), 1);
+
+        git_stash_drop(repo_path.clone(), 0).unwrap();
+        assert!(list_stashes(repo_path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stash_keep_index_and_reinstate() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "one\n", "add file");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        fs::write(dir.path().join("file.txt"), "staged\n").unwrap();
+        git_stage(repo_path.clone(), vec!["file.txt".to_string()]).unwrap();
+
+        git_stash_save(repo_path.clone(), None, true, false).unwrap();
+        // Staged content stays in both index and worktree with keep_index.
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            "staged\n"
+        );
+
+        let reopened = Repository::open(dir.path()).unwrap();
+        reopened
+            .reset(
+                reopened
+                    .head()
+                    .unwrap()
+                    .peel_to_commit()
+                    .unwrap()
+                    .as_object(),
+                git2::ResetType::Hard,
+                None,
+            )
+            .unwrap();
+
+        git_stash_pop(repo_path.clone(), 0, true).unwrap();
+        let status = get_git_status(repo_path).unwrap();
+        let entry = status.iter().find(|s| s.path == "file.txt").unwrap();
+        assert_eq!(entry.index_status, "modified");
+    }
+
+    // --- remote (push / fetch / pull) tests ---
+
+    fn add_bare_remote(repo: &Repository) -> TempDir {
+        let remote_dir = TempDir::new().unwrap();
+        Repository::init_bare(remote_dir.path()).unwrap();
+        repo.remote("origin", remote_dir.path().to_str().unwrap())
+            .unwrap();
+        remote_dir
+    }
+
+    fn clone_remote(remote_dir: &TempDir) -> (TempDir, Repository) {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::clone(remote_dir.path().to_str().unwrap(), dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Other User").unwrap();
+        config.set_str("user.email", "other@example.com").unwrap();
+        (dir, repo)
+    }
+
+    fn push_current(repo_path: &str) -> Result<PushResult, String> {
+        push_with_progress(repo_path, None, None, false, None, &|_| {})
+    }
+
+    #[test]
+    fn test_push_sets_upstream_and_reports_progress() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let head = add_and_commit(&repo, "file.txt", "content\n", "add file");
+        let remote_dir = add_bare_remote(&repo);
+        let branch = current_branch_name(&repo).unwrap();
+
+        let events = RefCell::new(Vec::new());
+        let result = push_with_progress(
+            dir.path().to_str().unwrap(),
+            None,
+            None,
+            false,
+            None,
+            &|p: RemoteProgress| events.borrow_mut().push(p),
+        )
+        .unwrap();
+        assert_eq!(result.remote, "origin");
+        assert_eq!(result.updated.len(), 1);
+        assert_eq!(result.updated[0].new, Some(head.to_string()));
+
+        let bare = Repository::open_bare(remote_dir.path()).unwrap();
+        assert_eq!(
+            bare.refname_to_id(&format!("refs/heads/{branch}")).unwrap(),
+            head
+        );
+        let config = Repository::open(dir.path()).unwrap().config().unwrap();
+        assert_eq!(
+            config
+                .get_string(&format!("branch.{branch}.remote"))
+                .unwrap(),
+            "origin"
+        );
+        assert!(events
+            .borrow()
+            .iter()
+            .any(|p| p.operation == "push" && p.stage == "ref_updated"));
+    }
+
+    #[test]
+    fn test_push_non_fast_forward_fails_and_lease_checks_tracking_ref() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap();
+        let remote_dir = add_bare_remote(&repo);
+        push_current(repo_path).unwrap();
+
+        let (_other_dir, other) = clone_remote(&remote_dir);
+        add_and_commit(&other, "theirs.txt", "theirs\n", "theirs");
+        push_current(other.workdir().unwrap().to_str().unwrap()).unwrap();
+
+        add_and_commit(&repo, "ours.txt", "ours\n", "ours");
+        assert!(push_current(repo_path).is_err());
+
+        // Our tracking ref is stale, so the lease refuses to overwrite.
+        let err = push_with_progress(repo_path, None, None, true, None, &|_| {})
+            .err()
+            .unwrap();
+        assert!(err.contains("stale info"));
+
+        // After fetching, the lease matches and the force push goes through.
+        let reporter = ProgressReporter::new(repo_path, "fetch", &|_| {});
+        fetch_with_progress(&repo, "origin", &[], false, None, &reporter).unwrap();
+        push_with_progress(repo_path, None, None, true, None, &|_| {}).unwrap();
+        let bare = Repository::open_bare(remote_dir.path()).unwrap();
+        let branch = current_branch_name(&repo).unwrap();
+        assert_eq!(
+            bare.refname_to_id(&format!("refs/heads/{branch}")).unwrap(),
+            repo.head().unwrap().target().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fetch_reports_updated_refs() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let remote_dir = add_bare_remote(&repo);
+        push_current(dir.path().to_str().unwrap()).unwrap();
+
+        let (_other_dir, other) = clone_remote(&remote_dir);
+        let theirs = add_and_commit(&other, "theirs.txt", "theirs\n", "theirs");
+        push_current(other.workdir().unwrap().to_str().unwrap()).unwrap();
+
+        let reporter = ProgressReporter::new(dir.path().to_str().unwrap(), "fetch", &|_| {});
+        let result = fetch_with_progress(&repo, "origin", &[], false, None, &reporter).unwrap();
+        let branch = current_branch_name(&repo).unwrap();
+        let update = result
+            .updated
+            .iter()
+            .find(|u| u.refname == format!("refs/remotes/origin/{branch}"))
+            .unwrap();
+        assert_eq!(update.new, Some(theirs.to_string()));
+        assert!(result.received_objects > 0);
+    }
+
+    #[test]
+    fn test_pull_fast_forward() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap();
+        let remote_dir = add_bare_remote(&repo);
+        push_current(repo_path).unwrap();
 
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for git apply: {e}"))?;
+        let (_other_dir, other) = clone_remote(&remote_dir);
+        let theirs = add_and_commit(&other, "theirs.txt", "theirs\n", "theirs");
+        push_current(other.workdir().unwrap().to_str().unwrap()).unwrap();
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(stderr.trim().to_string())
+        let result = pull_with_progress(repo_path, None, None, false, None, &|_| {}).unwrap();
+        assert_eq!(result.outcome, "fast_forward");
+        assert_eq!(result.head, Some(theirs.to_string()));
+        assert!(dir.path().join("theirs.txt").exists());
+
+        let again = pull_with_progress(repo_path, None, None, false, None, &|_| {}).unwrap();
+        assert_eq!(again.outcome, "up_to_date");
     }
-}
 
-#[tauri::command]
-pub fn git_create_branch(repo_path: String, branch_name: String) -> Result<(), String> {
-    let repo = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    #[test]
+    fn test_pull_diverged_requires_rebase() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap();
+        let remote_dir = add_bare_remote(&repo);
+        push_current(repo_path).unwrap();
 
-    let head = repo.head().map_err(|e| e.message().to_string())?;
-    let commit = head.peel_to_commit().map_err(|e| e.message().to_string())?;
+        let (_other_dir, other) = clone_remote(&remote_dir);
+        let theirs = add_and_commit(&other, "theirs.txt", "theirs\n", "theirs");
+        push_current(other.workdir().unwrap().to_str().unwrap()).unwrap();
+        add_and_commit(&repo, "ours.txt", "ours\n", "ours");
 
-    repo.branch(&branch_name, &commit, false)
-        .map_err(|e| e.message().to_string())?;
+        let err = pull_with_progress(repo_path, None, None, false, None, &|_| {})
+            .err()
+            .unwrap();
+        assert!(err.contains("diverged"));
+
+        let result = pull_with_progress(repo_path, None, None, true, None, &|_| {}).unwrap();
+        assert_eq!(result.outcome, "rebased");
+        let reopened = Repository::open(dir.path()).unwrap();
+        let head = reopened.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.summary(), Some("ours"));
+        assert_eq!(head.parent_id(0).unwrap(), theirs);
+        assert!(dir.path().join("theirs.txt").exists());
+        assert!(dir.path().join("ours.txt").exists());
+    }
 
-    repo.set_head(&format!("refs/heads/{branch_name}"))
-        .map_err(|e| e.message().to_string())?;
+    // --- list_branches tests ---
 
-    repo.checkout_head(Some(CheckoutBuilder::new().safe()))
-        .map_err(|e| e.message().to_string())?;
+    #[test]
+    fn test_list_branches_with_upstream_and_ahead_behind() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap();
+        let remote_dir = add_bare_remote(&repo);
+        push_current(repo_path).unwrap();
+
+        let (_other_dir, other) = clone_remote(&remote_dir);
+        add_and_commit(&other, "theirs.txt", "theirs\n", "theirs");
+        push_current(other.workdir().unwrap().to_str().unwrap()).unwrap();
+
+        let ours = add_and_commit(&repo, "a.txt", "a\n", "ours 1");
+        add_and_commit(&repo, "b.txt", "b\n", "ours 2");
+        let reporter = ProgressReporter::new(repo_path, "fetch", &|_| {});
+        fetch_with_progress(&repo, "origin", &[], false, None, &reporter).unwrap();
+        repo.branch("feature", &repo.find_commit(ours).unwrap(), false)
+            .unwrap();
 
-    Ok(())
-}
+        let branch = current_branch_name(&repo).unwrap();
+        let branches = list_branches(repo_path.to_string()).unwrap();
+
+        let current = branches.iter().find(|b| b.name == branch).unwrap();
+        assert!(current.is_head);
+        assert!(!current.is_remote);
+        assert_eq!(current.upstream, Some(format!("origin/{branch}")));
+        assert_eq!(current.ahead, Some(2));
+        assert_eq!(current.behind, Some(1));
+        assert_eq!(current.last_commit_summary.as_deref(), Some("ours 2"));
+        assert_eq!(
+            Path::new(current.worktree_path.as_ref().unwrap())
+                .canonicalize()
+                .unwrap(),
+            dir.path().canonicalize().unwrap()
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use git2::{Repository, Signature};
-    use std::fs;
-    use tempfile::TempDir;
+        let feature = branches.iter().find(|b| b.name == "feature").unwrap();
+        assert!(!feature.is_head);
+        assert_eq!(feature.upstream, None);
+        assert_eq!(feature.ahead, None);
+        assert_eq!(feature.worktree_path, None);
 
-    fn create_test_repo() -> (TempDir, Repository) {
-        let dir = TempDir::new().unwrap();
-        let repo = Repository::init(dir.path()).unwrap();
+        let remote = branches
+            .iter()
+            .find(|b| b.name == format!("origin/{branch}"))
+            .unwrap();
+        assert!(remote.is_remote);
+        assert_eq!(remote.last_commit_summary.as_deref(), Some("theirs"));
+        // Local branches come first.
+        assert!(!branches.first().unwrap().is_remote);
+        assert!(branches.last().unwrap().is_remote);
+    }
 
-        let mut config = repo.config().unwrap();
-        config.set_str("user.name", "Test User").unwrap();
-        config.set_str("user.email", "test@example.com").unwrap();
+    #[test]
+    fn test_list_branches_reports_linked_worktree() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let wt_dir = TempDir::new().unwrap();
+        let wt_path = wt_dir.path().join("agent");
+        git_add_worktree(
+            repo_path.clone(),
+            "agent".to_string(),
+            wt_path.to_str().unwrap().to_string(),
+            None,
+            None,
+        )
+        .unwrap();
 
-        (dir, repo)
+        let branches = list_branches(repo_path).unwrap();
+        let agent = branches.iter().find(|b| b.name == "agent").unwrap();
+        assert!(!agent.is_head);
+        assert_eq!(
+            Path::new(agent.worktree_path.as_ref().unwrap())
+                .canonicalize()
+                .unwrap(),
+            wt_path.canonicalize().unwrap()
+        );
     }
 
-    fn create_initial_commit(repo: &Repository) -> git2::Oid {
-        let sig = Signature::now("Test User", "test@example.com").unwrap();
-        let tree_id = repo.index().unwrap().write_tree().unwrap();
-        let tree = repo.find_tree(tree_id).unwrap();
-        repo.commit(Some("HEAD"), &sig, &sig, "initial commit", &tree, &[])
-            .unwrap()
-    }
+    // --- branch checkout / delete / rename tests ---
 
-    fn add_and_commit(repo: &Repository, path: &str, content: &str, message: &str) -> git2::Oid {
-        let workdir = repo.workdir().unwrap();
-        fs::write(workdir.join(path), content).unwrap();
+    #[test]
+    fn test_create_branch_from_ref_without_checkout() {
+        let (dir, repo) = create_test_repo();
+        let first = create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "content\n", "second");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let original = current_branch_name(&repo).unwrap();
 
-        let mut index = repo.index().unwrap();
-        index.add_path(Path::new(path)).unwrap();
-        index.write().unwrap();
+        git_create_branch(
+            repo_path.clone(),
+            "old".to_string(),
+            Some(first.to_string()),
+            Some(false),
+        )
+        .unwrap();
+        assert_eq!(get_current_branch(repo_path.clone()).unwrap(), original);
+        assert_eq!(repo.refname_to_id("refs/heads/old").unwrap(), first);
 
-        let sig = Signature::now("Test User", "test@example.com").unwrap();
-        let tree_id = index.write_tree().unwrap();
-        let tree = repo.find_tree(tree_id).unwrap();
-        let parent = repo.head().unwrap().peel_to_commit().unwrap();
-        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &[&parent])
-            .unwrap()
+        git_checkout_branch(repo_path.clone(), "old".to_string()).unwrap();
+        assert_eq!(get_current_branch(repo_path).unwrap(), "old");
+        assert!(!dir.path().join("file.txt").exists());
     }
 
     #[test]
-    fn test_get_git_status_untracked() {
-        let (dir, _repo) = create_test_repo();
-        create_initial_commit(&_repo);
-        fs::write(dir.path().join("new_file.txt"), "hello").unwrap();
+    fn test_create_branch_from_remote_sets_upstream() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap();
+        let _remote_dir = add_bare_remote(&repo);
+        push_current(repo_path).unwrap();
+        let branch = current_branch_name(&repo).unwrap();
 
-        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0].path, "new_file.txt");
-        assert_eq!(result[0].worktree_status, "new");
-        assert_eq!(result[0].index_status, "none");
+        git_create_branch(
+            repo_path.to_string(),
+            "work".to_string(),
+            Some(format!("origin/{branch}")),
+            None,
+        )
+        .unwrap();
+        let reopened = Repository::open(dir.path()).unwrap();
+        let work = reopened.find_branch("work", BranchType::Local).unwrap();
+        assert_eq!(
+            work.upstream().unwrap().name().unwrap(),
+            Some(format!("origin/{branch}").as_str())
+        );
+        assert_eq!(get_current_branch(repo_path.to_string()).unwrap(), "work");
     }
 
     #[test]
-    fn test_get_git_status_staged() {
+    fn test_checkout_branch_reports_blocking_paths() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "base\n", "base");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let original = current_branch_name(&repo).unwrap();
+        git_create_branch(repo_path.clone(), "other".to_string(), None, Some(false)).unwrap();
+        add_and_commit(&repo, "file.txt", "changed on main\n", "change");
+
+        fs::write(dir.path().join("file.txt"), "dirty\n").unwrap();
+        fs::write(dir.path().join("notes.txt"), "untracked\n").unwrap();
+
+        let err = git_checkout_branch(repo_path.clone(), "other".to_string()).unwrap_err();
+        assert_eq!(err.reason, "dirty_worktree");
+        assert_eq!(err.blocking_paths, vec!["file.txt".to_string()]);
+        assert_eq!(get_current_branch(repo_path.clone()).unwrap(), original);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            "dirty\n"
+        );
 
-        fs::write(dir.path().join("staged.txt"), "content").unwrap();
-        let mut index = repo.index().unwrap();
-        index.add_path(Path::new("staged.txt")).unwrap();
-        index.write().unwrap();
+        // Once the conflicting edit is gone, untracked files are carried over.
+        fs::write(dir.path().join("file.txt"), "changed on main\n").unwrap();
+        git_checkout_branch(repo_path.clone(), "other".to_string()).unwrap();
+        assert_eq!(get_current_branch(repo_path).unwrap(), "other");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            "base\n"
+        );
+        assert!(dir.path().join("notes.txt").exists());
+    }
 
-        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0].path, "staged.txt");
-        assert_eq!(result[0].index_status, "new");
+    #[test]
+    fn test_create_branch_checkout_blocked_leaves_no_branch() {
+        let (dir, repo) = create_test_repo();
+        let first = create_initial_commit(&repo);
+        add_and_commit(&repo, "file.txt", "base\n", "base");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        fs::write(dir.path().join("file.txt"), "dirty\n").unwrap();
+
+        let err = git_create_branch(
+            repo_path,
+            "fresh".to_string(),
+            Some(first.to_string()),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.reason, "dirty_worktree");
+        assert!(repo.find_branch("fresh", BranchType::Local).is_err());
     }
 
     #[test]
-    fn test_get_git_status_modified() {
+    fn test_checkout_remote_branch_creates_tracking_branch() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
-        add_and_commit(&repo, "file.txt", "original", "add file");
-
-        fs::write(dir.path().join("file.txt"), "modified content").unwrap();
-
-        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0].path, "file.txt");
-        assert_eq!(result[0].worktree_status, "modified");
+        let repo_path = dir.path().to_str().unwrap();
+        let remote_dir = add_bare_remote(&repo);
+        push_current(repo_path).unwrap();
+
+        let (_other_dir, other) = clone_remote(&remote_dir);
+        let other_path = other.workdir().unwrap().to_str().unwrap().to_string();
+        git_create_branch(other_path.clone(), "agent".to_string(), None, None).unwrap();
+        add_and_commit(&other, "agent.txt", "agent\n", "agent work");
+        push_current(&other_path).unwrap();
+
+        let reporter = ProgressReporter::new(repo_path, "fetch", &|_| {});
+        fetch_with_progress(&repo, "origin", &[], false, None, &reporter).unwrap();
+
+        // A blocked checkout leaves no local branch behind.
+        fs::write(dir.path().join("agent.txt"), "local\n").unwrap();
+        let err =
+            git_checkout_branch(repo_path.to_string(), "origin/agent".to_string()).unwrap_err();
+        assert_eq!(err.reason, "dirty_worktree");
+        assert!(repo.find_branch("agent", BranchType::Local).is_err());
+
+        fs::remove_file(dir.path().join("agent.txt")).unwrap();
+        git_checkout_branch(repo_path.to_string(), "origin/agent".to_string()).unwrap();
+
+        assert_eq!(get_current_branch(repo_path.to_string()).unwrap(), "agent");
+        assert!(dir.path().join("agent.txt").exists());
+        let reopened = Repository::open(dir.path()).unwrap();
+        let agent = reopened.find_branch("agent", BranchType::Local).unwrap();
+        assert_eq!(
+            agent.upstream().unwrap().name().unwrap(),
+            Some("origin/agent")
+        );
     }
 
     #[test]
-    fn test_get_git_status_empty_repo() {
-        let (dir, _repo) = create_test_repo();
+    fn test_checkout_branch_in_other_worktree_refused() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let wt_dir = TempDir::new().unwrap();
+        git_add_worktree(
+            repo_path.clone(),
+            "agent".to_string(),
+            wt_dir.path().join("agent").to_str().unwrap().to_string(),
+            None,
+            None,
+        )
+        .unwrap();
 
-        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert!(result.is_empty());
+        let err = git_checkout_branch(repo_path, "agent".to_string()).unwrap_err();
+        assert_eq!(err.reason, "checked_out_elsewhere");
     }
 
     #[test]
-    fn test_get_git_log() {
+    fn test_delete_branch() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
-        add_and_commit(&repo, "a.txt", "a", "second commit");
-        add_and_commit(&repo, "b.txt", "b", "third commit");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let original = current_branch_name(&repo).unwrap();
 
-        let result = get_git_log(dir.path().to_str().unwrap().to_string(), None).unwrap();
-        assert_eq!(result.len(), 3);
+        git_create_branch(repo_path.clone(), "merged".to_string(), None, Some(false)).unwrap();
+        git_delete_branch(repo_path.clone(), "merged".to_string(), false).unwrap();
+        assert!(repo.find_branch("merged", BranchType::Local).is_err());
 
-        let messages: Vec<&str> = result.iter().map(|c| c.message.as_str()).collect();
-        assert!(messages.contains(&"initial commit"));
-        assert!(messages.contains(&"second commit"));
-        assert!(messages.contains(&"third commit"));
+        git_create_branch(repo_path.clone(), "unmerged".to_string(), None, None).unwrap();
+        add_and_commit(&repo, "file.txt", "work\n", "work");
+        git_checkout_branch(repo_path.clone(), original.clone()).unwrap();
 
-        assert_eq!(result[0].author_name, "Test User");
-        assert_eq!(result[0].short_hash.len(), 7);
+        let err = git_delete_branch(repo_path.clone(), "unmerged".to_string(), false).unwrap_err();
+        assert!(err.contains("not fully merged"));
+        git_delete_branch(repo_path.clone(), "unmerged".to_string(), true).unwrap();
+        assert!(repo.find_branch("unmerged", BranchType::Local).is_err());
+
+        let err = git_delete_branch(repo_path, original, true).unwrap_err();
+        assert!(err.contains("checked out"));
     }
 
     #[test]
-    fn test_get_git_log_with_limit() {
+    fn test_rename_branch() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
-        add_and_commit(&repo, "a.txt", "a", "second");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        git_create_branch(repo_path.clone(), "agent-wip".to_string(), None, None).unwrap();
+        git_create_branch(repo_path.clone(), "taken".to_string(), None, Some(false)).unwrap();
+
+        assert!(git_rename_branch(
+            repo_path.clone(),
+            "agent-wip".to_string(),
+            "taken".to_string(),
+            false
+        )
+        .is_err());
 
-        let result = get_git_log(dir.path().to_str().unwrap().to_string(), Some(1)).unwrap();
-        assert_eq!(result.len(), 1);
+        git_rename_branch(
+            repo_path.clone(),
+            "agent-wip".to_string(),
+            "agent-done".to_string(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(get_current_branch(repo_path).unwrap(), "agent-done");
+        assert!(repo.find_branch("agent-wip", BranchType::Local).is_err());
     }
 
     #[test]
-    fn test_get_git_log_empty_repo() {
-        let (dir, _repo) = create_test_repo();
+    fn test_create_branch_already_exists() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        git_create_branch(
+            dir.path().to_str().unwrap().to_string(),
+            "feature".to_string(),
+            None,
+            None,
+        )
+        .unwrap();
 
-        let result = get_git_log(dir.path().to_str().unwrap().to_string(), None).unwrap();
-        assert!(result.is_empty());
+        let result = git_create_branch(
+            dir.path().to_str().unwrap().to_string(),
+            "feature".to_string(),
+            None,
+            None,
+        );
+        assert!(result.is_err());
     }
 
-    #[test]
-    fn test_get_current_branch() {
-        let (dir, repo) = create_test_repo();
-        create_initial_commit(&repo);
+    // --- checkpoint tests ---
 
-        let result = get_current_branch(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert!(result == "main" || result == "master");
+    fn index_blob(repo: &Repository, path: &str) -> Option<String> {
+        let mut index = repo.index().unwrap();
+        index.read(true).unwrap();
+        let entry = index.get_path(Path::new(path), 0)?;
+        let blob = repo.find_blob(entry.id).unwrap();
+        Some(String::from_utf8_lossy(blob.content()).to_string())
+    }
+
+    fn tree_file(repo: &Repository, oid: &str, path: &str) -> Option<String> {
+        let commit = repo.find_commit(git2::Oid::from_str(oid).unwrap()).unwrap();
+        let entry = commit.tree().unwrap().get_path(Path::new(path)).ok()?;
+        let blob = repo.find_blob(entry.id()).unwrap();
+        Some(String::from_utf8_lossy(blob.content()).to_string())
     }
 
     #[test]
-    fn test_get_current_branch_empty_repo() {
-        let (dir, _repo) = create_test_repo();
+    fn test_create_checkpoint_captures_worktree_without_touching_state() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let head = add_and_commit(&repo, "a.txt", "one\n", "add a");
+        fs::write(dir.path().join("a.txt"), "two\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "staged\n").unwrap();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        git_stage(repo_path.clone(), vec!["b.txt".to_string()]).unwrap();
+        fs::write(dir.path().join("b.txt"), "staged then edited\n").unwrap();
+        fs::write(dir.path().join("new.txt"), "untracked\n").unwrap();
+        fs::write(dir.path().join(".gitignore"), "ignored.txt\n").unwrap();
+        fs::write(dir.path().join("ignored.txt"), "secret\n").unwrap();
+        let status_before: Vec<(String, String, String)> = get_git_status(repo_path.clone())
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.path, s.index_status, s.worktree_status))
+            .collect();
+
+        let checkpoint =
+            git_create_checkpoint(repo_path.clone(), "agent-1".to_string(), None).unwrap();
+        assert_eq!(checkpoint.id, "agent-1/1");
+        assert_eq!(checkpoint.label, "manual");
+        assert_eq!(checkpoint.head, Some(head.to_string()));
+        assert_eq!(
+            tree_file(&repo, &checkpoint.oid, "a.txt").as_deref(),
+            Some("two\n")
+        );
+        assert_eq!(
+            tree_file(&repo, &checkpoint.oid, "b.txt").as_deref(),
+            Some("staged then edited\n")
+        );
+        assert_eq!(
+            tree_file(&repo, &checkpoint.oid, "new.txt").as_deref(),
+            Some("untracked\n")
+        );
+        assert!(tree_file(&repo, &checkpoint.oid, "ignored.txt").is_none());
 
-        let result = get_current_branch(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert_eq!(result, "(no commits)");
+        assert_eq!(repo.head().unwrap().target(), Some(head));
+        assert_eq!(index_blob(&repo, "b.txt").as_deref(), Some("staged\n"));
+        let status_after: Vec<(String, String, String)> = get_git_status(repo_path.clone())
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.path, s.index_status, s.worktree_status))
+            .collect();
+        assert_eq!(status_before, status_after);
+
+        let second =
+            git_create_checkpoint(repo_path.clone(), "agent-1".to_string(), Some("x".into()))
+                .unwrap();
+        assert_eq!(second.number, 2);
+        git_create_checkpoint(repo_path.clone(), "agent-2".to_string(), None).unwrap();
+        let listed: Vec<String> = list_checkpoints(repo_path.clone(), Some("agent-1".into()))
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(listed, vec!["agent-1/2", "agent-1/1"]);
+        assert_eq!(list_checkpoints(repo_path.clone(), None).unwrap().len(), 3);
+
+        assert!(git_create_checkpoint(repo_path, "a/b".to_string(), None).is_err());
     }
 
     #[test]
-    fn test_get_current_branch_detached_head() {
+    fn test_auto_checkpoint_skips_unchanged_state() {
         let (dir, repo) = create_test_repo();
-        let oid = create_initial_commit(&repo);
+        create_initial_commit(&repo);
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("file.txt"), "one\n").unwrap();
+        let cwd = dir.path().join("sub");
+        let cwd = cwd.to_str().unwrap();
 
-        repo.set_head_detached(oid).unwrap();
+        let first = auto_checkpoint(cwd, "pty").unwrap().unwrap();
+        assert_eq!(first.label, "auto");
+        assert!(auto_checkpoint(cwd, "pty").unwrap().is_none());
 
-        let result = get_current_branch(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert!(result.starts_with('('));
-        assert!(result.ends_with(')'));
-        assert_eq!(result.len(), 9); // "(1234567)"
+        fs::write(dir.path().join("file.txt"), "two\n").unwrap();
+        assert_eq!(auto_checkpoint(cwd, "pty").unwrap().unwrap().number, 2);
     }
 
-    // --- git_stage tests ---
+    #[test]
+    fn test_checkpoint_diff_between_checkpoints_and_worktree() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "a.txt", "one\n", "add a");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        git_create_checkpoint(repo_path.clone(), "s".to_string(), None).unwrap();
+
+        fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+        git_create_checkpoint(repo_path.clone(), "s".to_string(), None).unwrap();
+        fs::write(dir.path().join("b.txt"), "new\n").unwrap();
+
+        let between =
+            get_checkpoint_diff(repo_path.clone(), "s/1".into(), Some("s/2".into()), None).unwrap();
+        assert_eq!(between.len(), 1);
+        assert_eq!(between[0].new_path.as_deref(), Some("a.txt"));
+        assert_eq!(between[0].additions, 1);
+
+        let to_worktree = get_checkpoint_diff(repo_path.clone(), "s/2".into(), None, None).unwrap();
+        assert_eq!(to_worktree.len(), 1);
+        assert_eq!(to_worktree[0].status, "new");
+        assert_eq!(to_worktree[0].new_path.as_deref(), Some("b.txt"));
+
+        assert!(get_checkpoint_diff(repo_path, "s/9".into(), None, None).is_err());
+    }
 
     #[test]
-    fn test_stage_specific_file() {
+    fn test_restore_checkpoint_rebuilds_worktree_and_index() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
-        fs::write(dir.path().join("new.txt"), "hello").unwrap();
+        let head = add_and_commit(&repo, "a.txt", "one\n", "add a");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        fs::write(dir.path().join("a.txt"), "edited\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "staged\n").unwrap();
+        git_stage(repo_path.clone(), vec!["b.txt".to_string()]).unwrap();
+        fs::write(dir.path().join("new.txt"), "untracked\n").unwrap();
+        fs::write(dir.path().join(".gitignore"), "ignored.txt\n").unwrap();
+        git_create_checkpoint(repo_path.clone(), "s".to_string(), None).unwrap();
+
+        // The agent wrecks the worktree.
+        fs::write(dir.path().join("a.txt"), "wrecked\n").unwrap();
+        fs::remove_file(dir.path().join("new.txt")).unwrap();
+        fs::write(dir.path().join("junk.txt"), "junk\n").unwrap();
+        fs::write(dir.path().join("ignored.txt"), "keep me\n").unwrap();
+        git_unstage(repo_path.clone(), vec!["b.txt".to_string()]).unwrap();
+        fs::remove_file(dir.path().join("b.txt")).unwrap();
+
+        let backup = git_restore_checkpoint(repo_path.clone(), "s/1".to_string()).unwrap();
+        assert_eq!(backup.id, "s/2");
+        assert_eq!(
+            tree_file(&repo, &backup.oid, "junk.txt").as_deref(),
+            Some("junk\n")
+        );
 
-        git_stage(
-            dir.path().to_str().unwrap().to_string(),
-            vec!["new.txt".to_string()],
-        )
-        .unwrap();
+        let read = |name: &str| fs::read_to_string(dir.path().join(name)).ok();
+        assert_eq!(read("a.txt").as_deref(), Some("edited\n"));
+        assert_eq!(read("b.txt").as_deref(), Some("staged\n"));
+        assert_eq!(read("new.txt").as_deref(), Some("untracked\n"));
+        assert_eq!(read("ignored.txt").as_deref(), Some("keep me\n"));
+        assert!(read("junk.txt").is_none());
+        assert_eq!(repo.head().unwrap().target(), Some(head));
+        assert_eq!(index_blob(&repo, "a.txt").as_deref(), Some("one\n"));
+        assert_eq!(index_blob(&repo, "b.txt").as_deref(), Some("staged\n"));
+        assert!(index_blob(&repo, "new.txt").is_none());
+
+        // Restoring the backup undoes the restore.
+        git_restore_checkpoint(repo_path, backup.id).unwrap();
+        assert_eq!(read("a.txt").as_deref(), Some("wrecked\n"));
+        assert_eq!(read("junk.txt").as_deref(), Some("junk\n"));
+        assert!(read("new.txt").is_none());
+        assert!(index_blob(&repo, "b.txt").is_none());
+    }
 
-        let statuses = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert_eq!(statuses.len(), 1);
-        assert_eq!(statuses[0].index_status, "new");
-        assert_eq!(statuses[0].worktree_status, "none");
+    // --- review tracking tests ---
+
+    fn review_states(repo_path: &str) -> Vec<(String, String)> {
+        get_git_status(repo_path.to_string())
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.path, s.review_state))
+            .collect()
     }
 
     #[test]
-    fn test_stage_all_files() {
+    fn test_review_state_in_status() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
-        fs::write(dir.path().join("a.txt"), "a").unwrap();
-        fs::write(dir.path().join("b.txt"), "b").unwrap();
+        add_and_commit(&repo, "a.txt", "one\n", "add a");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        fs::write(dir.path().join("a.txt"), "two\n").unwrap();
+        let state = |s: &str| vec![("a.txt".to_string(), s.to_string())];
 
-        git_stage(dir.path().to_str().unwrap().to_string(), vec![]).unwrap();
+        assert_eq!(review_states(&repo_path), state("never_reviewed"));
+        git_mark_reviewed(repo_path.clone(), vec!["a.txt".to_string()]).unwrap();
+        assert!(dir.path().join(".git/releash/reviewed.json").is_file());
+        assert_eq!(review_states(&repo_path), state("reviewed"));
 
-        let statuses = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert_eq!(statuses.len(), 2);
-        for s in &statuses {
-            assert_eq!(s.index_status, "new");
-            assert_eq!(s.worktree_status, "none");
-        }
+        fs::write(dir.path().join("a.txt"), "three\n").unwrap();
+        assert_eq!(review_states(&repo_path), state("changed_since_review"));
+
+        git_clear_reviewed(repo_path.clone(), None).unwrap();
+        assert_eq!(review_states(&repo_path), state("never_reviewed"));
     }
 
     #[test]
-    fn test_stage_deleted_file() {
+    fn test_review_diff_shows_only_changes_since_review() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
-        add_and_commit(&repo, "file.txt", "content", "add file");
-        fs::remove_file(dir.path().join("file.txt")).unwrap();
-
-        git_stage(
-            dir.path().to_str().unwrap().to_string(),
-            vec!["file.txt".to_string()],
+        add_and_commit(&repo, "a.txt", "one\n", "add a");
+        add_and_commit(&repo, "c.txt", "c\n", "add c");
+        add_and_commit(&repo, "d.txt", "d\n", "add d");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+        fs::write(dir.path().join("c.txt"), "c edited\n").unwrap();
+        fs::write(dir.path().join("d.txt"), "d edited\n").unwrap();
+        git_mark_reviewed(
+            repo_path.clone(),
+            vec!["a.txt".into(), "c.txt".into(), "d.txt".into()],
         )
         .unwrap();
 
-        let statuses = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert_eq!(statuses.len(), 1);
-        assert_eq!(statuses[0].index_status, "deleted");
+        fs::write(dir.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "new\n").unwrap();
+        // Reverting a reviewed edit is itself a change to review.
+        fs::write(dir.path().join("d.txt"), "d\n").unwrap();
+
+        let diff = get_review_diff(repo_path, None).unwrap();
+        let files: Vec<(&str, &str, usize, usize)> = diff
+            .iter()
+            .map(|f| {
+                (
+                    f.new_path.as_deref().unwrap(),
+                    f.status.as_str(),
+                    f.additions,
+                    f.deletions,
+                )
+            })
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("a.txt", "modified", 1, 0),
+                ("b.txt", "new", 1, 0),
+                ("d.txt", "modified", 1, 1),
+            ]
+        );
+        assert_eq!(diff[0].hunks[0].lines.last().unwrap().content, "three");
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_stage_untracked_file() {
-        let (dir, _repo) = create_test_repo();
-        create_initial_commit(&_repo);
-        fs::write(dir.path().join("untracked.txt"), "data").unwrap();
-
-        let before = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert_eq!(before[0].worktree_status, "new");
+    fn test_review_diff_keeps_reviewed_symlink_mode() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        std::os::unix::fs::symlink("a.txt", dir.path().join("link")).unwrap();
+        git_mark_reviewed(repo_path.clone(), vec!["link".into()]).unwrap();
+
+        fs::remove_file(dir.path().join("link")).unwrap();
+        std::os::unix::fs::symlink("b.txt", dir.path().join("link")).unwrap();
+        let diff = get_review_diff(repo_path, None).unwrap();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].status, "modified");
+        assert_eq!((diff[0].additions, diff[0].deletions), (1, 1));
+    }
 
-        git_stage(
-            dir.path().to_str().unwrap().to_string(),
-            vec!["untracked.txt".to_string()],
-        )
-        .unwrap();
+    // --- export tests ---
 
-        let after = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert_eq!(after[0].index_status, "new");
-        assert_eq!(after[0].worktree_status, "none");
+    fn apply_patch_file(repo: &Repository, path: &Path, location: git2::ApplyLocation) {
+        let content = fs::read(path).unwrap();
+        let diff = git2::Diff::from_buffer(&content).unwrap();
+        repo.apply(&diff, location, None).unwrap();
     }
 
-    // --- git_unstage tests ---
-
     #[test]
-    fn test_unstage_specific_file() {
+    fn test_export_staged_and_unstaged_patches_reapply() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
-        fs::write(dir.path().join("file.txt"), "content").unwrap();
+        add_and_commit(&repo, "a.txt", "one\n", "add a");
+        let (clone_dir, clone) = clone_remote(&dir);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+        fs::write(dir.path().join("image.bin"), [0u8, 1, 2, 255, 0]).unwrap();
         git_stage(
-            dir.path().to_str().unwrap().to_string(),
-            vec!["file.txt".to_string()],
+            repo_path.clone(),
+            vec!["a.txt".to_string(), "image.bin".to_string()],
         )
         .unwrap();
-
-        git_unstage(
-            dir.path().to_str().unwrap().to_string(),
-            vec!["file.txt".to_string()],
+        fs::write(dir.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        fs::write(dir.path().join("new.txt"), "untracked\n").unwrap();
+
+        let out = TempDir::new().unwrap();
+        let staged = out.path().join("staged.patch");
+        let unstaged = out.path().join("unstaged.patch");
+        git_export_patch(
+            repo_path.clone(),
+            "staged".into(),
+            None,
+            staged.to_str().unwrap().into(),
+        )
+        .unwrap();
+        git_export_patch(
+            repo_path.clone(),
+            "unstaged".into(),
+            None,
+            unstaged.to_str().unwrap().into(),
         )
         .unwrap();
 
-        let statuses = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert_eq!(statuses.len(), 1);
-        assert_eq!(statuses[0].worktree_status, "new");
-        assert_eq!(statuses[0].index_status, "none");
+        apply_patch_file(&clone, &staged, git2::ApplyLocation::Both);
+        apply_patch_file(&clone, &unstaged, git2::ApplyLocation::WorkDir);
+        for name in ["a.txt", "image.bin", "new.txt"] {
+            assert_eq!(
+                fs::read(clone_dir.path().join(name)).unwrap(),
+                fs::read(dir.path().join(name)).unwrap(),
+                "{name}"
+            );
+        }
+        let mut clone_index = clone.index().unwrap();
+        clone_index.read(true).unwrap();
+        let staged_a = clone_index.get_path(Path::new("a.txt"), 0).unwrap();
+        assert_eq!(
+            clone.find_blob(staged_a.id).unwrap().content(),
+            b"one\ntwo\n"
+        );
+
+        let (clean_dir, clean) = create_test_repo();
+        create_initial_commit(&clean);
+        let empty = git_export_patch(
+            clean_dir.path().to_str().unwrap().into(),
+            "staged".into(),
+            None,
+            out.path().join("empty.patch").to_str().unwrap().into(),
+        );
+        assert_eq!(empty, Err("Nothing to export".to_string()));
     }
 
     #[test]
-    fn test_unstage_all_files() {
+    fn test_export_range_patch_and_format_patch_reapply() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
-        fs::write(dir.path().join("a.txt"), "a").unwrap();
-        fs::write(dir.path().join("b.txt"), "b").unwrap();
-        git_stage(dir.path().to_str().unwrap().to_string(), vec![]).unwrap();
-
-        git_unstage(dir.path().to_str().unwrap().to_string(), vec![]).unwrap();
-
-        let statuses = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        for s in &statuses {
-            assert_eq!(s.index_status, "none");
-            assert_eq!(s.worktree_status, "new");
+        let base = add_and_commit(&repo, "a.txt", "one\n", "add a");
+        let (clone_dir, clone) = clone_remote(&dir);
+        add_and_commit(&repo, "a.txt", "one\ntwo\n", "Extend a: second line");
+        add_and_commit(&repo, "b.txt", "b\n", "add b\n\nWith a body.");
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let range = format!("{base}..HEAD");
+
+        let out = TempDir::new().unwrap();
+        let range_patch = out.path().join("range.patch");
+        git_export_patch(
+            repo_path.clone(),
+            "range".into(),
+            Some(range.clone()),
+            range_patch.to_str().unwrap().into(),
+        )
+        .unwrap();
+        apply_patch_file(&clone, &range_patch, git2::ApplyLocation::Both);
+        let mut clone_index = clone.index().unwrap();
+        clone_index.read(true).unwrap();
+        assert_eq!(clone_index.write_tree().unwrap(), head.tree_id());
+
+        // Reset the clone and replay the series commit by commit instead.
+        let clone_base = clone.find_commit(base).unwrap();
+        clone
+            .reset(clone_base.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+        let series_dir = out.path().join("series");
+        let files =
+            git_export_format_patch(repo_path, range, series_dir.to_str().unwrap().into()).unwrap();
+        let names: Vec<String> = files
+            .iter()
+            .map(|f| {
+                Path::new(f)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec!["0001-Extend-a-second-line.patch", "0002-add-b.patch"]
+        );
+        let first = fs::read_to_string(&files[1]).unwrap();
+        assert!(first.starts_with(&format!("From {} ", head.id())));
+        assert!(first.contains("Subject: [PATCH 2/2] add b"));
+        assert!(first.contains("From: Test User <test@example.com>"));
+        assert!(first.contains("With a body."));
+
+        let repo_path = clone_dir.path().to_str().unwrap();
+        for file in &files {
+            apply_patch_file(&clone, Path::new(file), git2::ApplyLocation::Both);
+            commit_with(repo_path, "replayed", CommitOptions::default()).unwrap();
         }
+        let replayed = clone.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(replayed.tree_id(), head.tree_id());
+        assert_eq!(replayed.parent(0).unwrap().parent_id(0).unwrap(), base);
     }
 
     #[test]
-    fn test_unstage_unborn_branch() {
-        let (dir, _repo) = create_test_repo();
-        // No initial commit — unborn branch
-        fs::write(dir.path().join("file.txt"), "content").unwrap();
-        git_stage(
-            dir.path().to_str().unwrap().to_string(),
-            vec!["file.txt".to_string()],
-        )
-        .unwrap();
+    fn test_format_patch_file_name() {
+        assert_eq!(
+            format_patch_file_name(3, "fix: handle \"quoted\" paths!"),
+            "0003-fix-handle-quoted-paths.patch"
+        );
+        assert_eq!(format_patch_file_name(12, "v1.2."), "0012-v1.2.patch");
+    }
 
-        // Verify it was staged
-        let before = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert_eq!(before[0].index_status, "new");
+    fn unbundle(repo: &Repository, path: &Path) -> (Vec<(git2::Oid, String)>, Vec<git2::Oid>) {
+        use std::io::Write;
 
-        git_unstage(
-            dir.path().to_str().unwrap().to_string(),
-            vec!["file.txt".to_string()],
-        )
-        .unwrap();
+        let bytes = fs::read(path).unwrap();
+        let header_end = bytes.windows(2).position(|w| w == b"\n\n").unwrap() + 2;
+        let header = String::from_utf8(bytes[..header_end].to_vec()).unwrap();
+        let mut lines = header.lines();
+        assert_eq!(lines.next(), Some("# v2 git bundle"));
+        let mut refs = Vec::new();
+        let mut prerequisites = Vec::new();
+        for line in lines.filter(|l| !l.is_empty()) {
+            if let Some(rest) = line.strip_prefix('-') {
+                let oid = rest.split(' ').next().unwrap();
+                prerequisites.push(git2::Oid::from_str(oid).unwrap());
+            } else {
+                let (oid, name) = line.split_once(' ').unwrap();
+                refs.push((git2::Oid::from_str(oid).unwrap(), name.to_string()));
+            }
+        }
 
-        let after = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert_eq!(after[0].index_status, "none");
-        assert_eq!(after[0].worktree_status, "new");
+        let odb = repo.odb().unwrap();
+        let mut writer = odb.packwriter().unwrap();
+        writer.write_all(&bytes[header_end..]).unwrap();
+        writer.commit().unwrap();
+        (refs, prerequisites)
     }
 
-    // --- git_commit tests ---
-
     #[test]
-    fn test_commit_normal() {
+    fn test_export_bundle_unbundles() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
-        fs::write(dir.path().join("file.txt"), "content").unwrap();
-        git_stage(
-            dir.path().to_str().unwrap().to_string(),
-            vec!["file.txt".to_string()],
+        let base = add_and_commit(&repo, "a.txt", "one\n", "add a");
+        let tip = add_and_commit(&repo, "a.txt", "one\ntwo\n", "extend a");
+        let branch = repo.head().unwrap().name().unwrap().to_string();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let out = TempDir::new().unwrap();
+
+        let full = out.path().join("full.bundle");
+        git_export_bundle(
+            repo_path.clone(),
+            "HEAD".into(),
+            full.to_str().unwrap().into(),
         )
         .unwrap();
+        let (_target_dir, target) = create_test_repo();
+        let (refs, prerequisites) = unbundle(&target, &full);
+        assert_eq!(refs, vec![(tip, branch.clone())]);
+        assert!(prerequisites.is_empty());
+        let unbundled = target.find_commit(tip).unwrap();
+        assert_eq!(
+            unbundled.tree_id(),
+            repo.find_commit(tip).unwrap().tree_id()
+        );
 
-        let hash = git_commit(
-            dir.path().to_str().unwrap().to_string(),
-            "test commit".to_string(),
+        let partial = out.path().join("partial.bundle");
+        git_export_bundle(
+            repo_path.clone(),
+            format!("{base}..{branch}"),
+            partial.to_str().unwrap().into(),
         )
         .unwrap();
-        assert_eq!(hash.len(), 40);
+        let (_partial_dir, partial_target) = create_test_repo();
+        let (refs, prerequisites) = unbundle(&partial_target, &partial);
+        assert_eq!(refs, vec![(tip, branch)]);
+        assert_eq!(prerequisites, vec![base]);
+        assert!(partial_target.find_commit(tip).is_ok());
+        assert!(partial_target.find_commit(base).is_err());
+
+        // Cross-check with git itself when it is installed.
+        let verified = std::process::Command::new("git")
+            .args(["bundle", "verify", "-q"])
+            .arg(&partial)
+            .current_dir(dir.path())
+            .output();
+        if let Ok(output) = verified {
+            assert!(output.status.success(), "{output:?}");
+        }
 
-        let log = get_git_log(dir.path().to_str().unwrap().to_string(), Some(1)).unwrap();
-        assert_eq!(log[0].message, "test commit");
+        let no_ref = git_export_bundle(
+            repo_path,
+            tip.to_string(),
+            out.path().join("x.bundle").to_str().unwrap().into(),
+        );
+        assert!(no_ref.is_err());
     }
 
-    #[test]
-    fn test_commit_initial() {
-        let (dir, _repo) = create_test_repo();
-        // No initial commit
-        fs::write(dir.path().join("file.txt"), "content").unwrap();
-        git_stage(
-            dir.path().to_str().unwrap().to_string(),
-            vec!["file.txt".to_string()],
+    // --- applying external patches ---
+
+    fn export_head_patch(repo: &Repository) -> String {
+        let out = TempDir::new().unwrap();
+        let file = out.path().join("head.patch");
+        git_export_patch(
+            repo.workdir().unwrap().to_str().unwrap().into(),
+            "range".into(),
+            Some("HEAD".into()),
+            file.to_str().unwrap().into(),
         )
         .unwrap();
+        fs::read_to_string(file).unwrap()
+    }
 
-        let hash = git_commit(
-            dir.path().to_str().unwrap().to_string(),
-            "first commit".to_string(),
+    #[test]
+    fn test_preview_and_apply_mbox_series() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let base = add_and_commit(&repo, "a.txt", "one\n", "add a");
+        add_and_commit(&repo, "a.txt", "one\ntwo\n", "extend a");
+        add_and_commit(&repo, "a.txt", "one\ntwo\nthree\n", "extend a again");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let out = TempDir::new().unwrap();
+        let files = git_export_format_patch(
+            repo_path.clone(),
+            format!("{base}..HEAD"),
+            out.path().to_str().unwrap().into(),
         )
         .unwrap();
-        assert_eq!(hash.len(), 40);
+        let mbox: String = files
+            .iter()
+            .map(|f| fs::read_to_string(f).unwrap())
+            .collect();
 
-        let log = get_git_log(dir.path().to_str().unwrap().to_string(), None).unwrap();
-        assert_eq!(log.len(), 1);
-        assert_eq!(log[0].message, "first commit");
-    }
+        let base_commit = repo.find_commit(base).unwrap();
+        repo.reset(base_commit.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
 
-    // --- git_create_branch tests ---
+        let preview = preview_patch(repo_path.clone(), mbox.clone(), "both".into()).unwrap();
+        assert_eq!(preview.messages.len(), 2);
+        assert_eq!(preview.messages[0].subject.as_deref(), Some("extend a"));
+        assert_eq!(
+            preview.messages[1].subject.as_deref(),
+            Some("extend a again")
+        );
+        for message in &preview.messages {
+            assert_eq!(message.files.len(), 1);
+            assert!(message.files[0].applies);
+            assert!(message.files[0].has_base);
+            assert!(message.files[0].hunks.iter().all(|h| h.applies));
+        }
+
+        let result = git_apply_patch(repo_path.clone(), mbox.clone(), "both".into(), None).unwrap();
+        assert_eq!(result.applied, vec!["a.txt", "a.txt"]);
+        assert!(result.conflicted.is_empty());
+        assert_eq!((result.messages_applied, result.messages_total), (2, 2));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "one\ntwo\nthree\n"
+        );
+        let mut index = repo.index().unwrap();
+        index.read(true).unwrap();
+        let staged = index.get_path(Path::new("a.txt"), 0).unwrap();
+        assert_eq!(
+            repo.find_blob(staged.id).unwrap().content(),
+            b"one\ntwo\nthree\n"
+        );
+
+        // Against an unrelated worktree the preview flags each hunk.
+        fs::write(dir.path().join("a.txt"), "other\n").unwrap();
+        let preview = preview_patch(repo_path.clone(), mbox.clone(), "worktree".into()).unwrap();
+        assert!(!preview.messages[0].files[0].applies);
+        assert!(!preview.messages[0].files[0].hunks[0].applies);
+        let err = git_apply_patch(repo_path, mbox, "worktree".into(), Some(false))
+            .err()
+            .unwrap();
+        assert_eq!(err.reason, "context_mismatch");
+        assert_eq!(err.path.as_deref(), Some("a.txt"));
+    }
 
     #[test]
-    fn test_create_branch() {
+    fn test_apply_mbox_series_reports_partial_result() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
-
-        git_create_branch(
-            dir.path().to_str().unwrap().to_string(),
-            "feature".to_string(),
+        add_and_commit(&repo, "b.txt", "x\n", "add b");
+        let base = add_and_commit(&repo, "a.txt", "one\n", "add a");
+        add_and_commit(&repo, "a.txt", "one\ntwo\n", "extend a");
+        add_and_commit(&repo, "b.txt", "y\n", "change b");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let out = TempDir::new().unwrap();
+        let files = git_export_format_patch(
+            repo_path.clone(),
+            format!("{base}..HEAD"),
+            out.path().to_str().unwrap().into(),
         )
         .unwrap();
+        let mbox: String = files
+            .iter()
+            .map(|f| fs::read_to_string(f).unwrap())
+            .collect();
+        let base_commit = repo.find_commit(base).unwrap();
+        repo.reset(base_commit.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+        fs::write(dir.path().join("b.txt"), "z\n").unwrap();
+
+        let result = git_apply_patch(repo_path, mbox, "worktree".into(), Some(false)).unwrap();
+        assert_eq!((result.messages_applied, result.messages_total), (1, 2));
+        assert_eq!(result.applied, vec!["a.txt"]);
+        let failed = result.failed.unwrap();
+        assert_eq!(failed.reason, "context_mismatch");
+        assert_eq!(failed.path.as_deref(), Some("b.txt"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "one\ntwo\n"
+        );
+        assert_eq!(fs::read_to_string(dir.path().join("b.txt")).unwrap(), "z\n");
+    }
 
-        let branch = get_current_branch(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert_eq!(branch, "feature");
+    #[test]
+    fn test_apply_patch_three_way_fallback() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let base = add_and_commit(&repo, "a.txt", "1\n2\n3\n4\n", "add a");
+        add_and_commit(&repo, "a.txt", "one\n2\n3\n4\n", "spell out one");
+        let patch = export_head_patch(&repo);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let base_commit = repo.find_commit(base).unwrap();
+        repo.reset(base_commit.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+
+        // A change inside the patch's context merges cleanly.
+        fs::write(dir.path().join("a.txt"), "1\n2\n3\nfour\n").unwrap();
+        let plain = git_apply_patch(
+            repo_path.clone(),
+            patch.clone(),
+            "worktree".into(),
+            Some(false),
+        );
+        assert_eq!(plain.err().unwrap().reason, "context_mismatch");
+        let result =
+            git_apply_patch(repo_path.clone(), patch.clone(), "worktree".into(), None).unwrap();
+        assert_eq!(result.applied, vec!["a.txt"]);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "one\n2\n3\nfour\n"
+        );
+
+        // An overlapping change is left with markers, and with conflict
+        // stages only when the index is a target.
+        fs::write(dir.path().join("a.txt"), "uno\n2\n3\n4\n").unwrap();
+        let result =
+            git_apply_patch(repo_path.clone(), patch.clone(), "worktree".into(), None).unwrap();
+        assert_eq!(result.conflicted, vec!["a.txt"]);
+        assert!(fs::read_to_string(dir.path().join("a.txt"))
+            .unwrap()
+            .starts_with("<<<<<<< ours\nuno\n"));
+        let mut index = repo.index().unwrap();
+        index.read(true).unwrap();
+        assert!(!index.has_conflicts());
+        fs::write(dir.path().join("a.txt"), "uno\n2\n3\n4\n").unwrap();
+        let result =
+            git_apply_patch(repo_path.clone(), patch.clone(), "both".into(), None).unwrap();
+        assert!(result.applied.is_empty());
+        assert_eq!(result.conflicted, vec!["a.txt"]);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "<<<<<<< ours\nuno\n=======\none\n>>>>>>> theirs\n2\n3\n4\n"
+        );
+        let mut index = repo.index().unwrap();
+        index.read(true).unwrap();
+        assert!(index.has_conflicts());
+        let conflict = index.conflicts().unwrap().next().unwrap().unwrap();
+        let theirs = repo.find_blob(conflict.their.unwrap().id).unwrap();
+        assert_eq!(theirs.content(), b"one\n2\n3\n4\n");
+        let ours = repo.find_blob(conflict.our.unwrap().id).unwrap();
+        assert_eq!(ours.content(), b"uno\n2\n3\n4\n");
     }
 
     #[test]
-    fn test_get_git_status_ignored_file() {
+    fn test_apply_patch_without_base_writes_nothing() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
+        add_and_commit(&repo, "a.txt", "1\n2\n3\n", "add a");
+        add_and_commit(&repo, "b.txt", "x\n", "add b");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let patch = "diff --git a/b.txt b/b.txt\n\
+                     index 1234567..89abcde 100644\n\
+                     --- a/b.txt\n\
+                     +++ b/b.txt\n\
+                     @@ -1 +1 @@\n\
+                     -x\n\
+                     +y\n\
+                     diff --git a/a.txt b/a.txt\n\
+                     index 1234567..89abcde 100644\n\
+                     --- a/a.txt\n\
+                     +++ b/a.txt\n\
+                     @@ -1,3 +1,3 @@\n\
+                     -one\n\
+                     +uno\n\
+                     \x202\n\
+                     \x203\n";
+
+        let preview = preview_patch(repo_path.clone(), patch.into(), "worktree".into()).unwrap();
+        let files = &preview.messages[0].files;
+        assert!(files[0].applies);
+        assert!(!files[1].applies);
+        assert!(!files[1].has_base);
+
+        let err = git_apply_patch(repo_path, patch.into(), "worktree".into(), None)
+            .err()
+            .unwrap();
+        assert_eq!(err.reason, "context_mismatch");
+        assert_eq!(err.path.as_deref(), Some("a.txt"));
+        assert_eq!(fs::read_to_string(dir.path().join("b.txt")).unwrap(), "x\n");
+    }
 
-        fs::write(dir.path().join(".gitignore"), "ignored.txt\nbuild/\n").unwrap();
+    // --- cherry-pick and revert tests ---
+
+    fn commit_as(repo: &Repository, path: &str, content: &str, name: &str) -> git2::Oid {
+        fs::write(repo.workdir().unwrap().join(path), content).unwrap();
         let mut index = repo.index().unwrap();
-        index.add_path(Path::new(".gitignore")).unwrap();
+        index.add_path(Path::new(path)).unwrap();
         index.write().unwrap();
-        let sig = Signature::now("Test User", "test@example.com").unwrap();
-        let tree_id = index.write_tree().unwrap();
-        let tree = repo.find_tree(tree_id).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let author = Signature::new(
+            name,
+            "agent@example.com",
+            &git2::Time::new(1_700_000_000, 0),
+        )
+        .unwrap();
         let parent = repo.head().unwrap().peel_to_commit().unwrap();
-        repo.commit(Some("HEAD"), &sig, &sig, "add gitignore", &tree, &[&parent])
+        repo.commit(
+            Some("HEAD"),
+            &author,
+            &author,
+            &format!("{name} edits {path}"),
+            &tree,
+            &[&parent],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cherry_pick_and_revert() {
+        let (dir, repo) = create_test_repo();
+        let main = create_diverged_branches(&repo);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        repo.set_head("refs/heads/agent").unwrap();
+        let agent_tip = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.reset(agent_tip.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+        let picked = commit_as(&repo, "agent.txt", "agent\nmore\n", "Agent");
+        repo.set_head(&main).unwrap();
+        let main_tip = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.reset(main_tip.as_object(), git2::ResetType::Hard, None)
             .unwrap();
 
-        fs::write(dir.path().join("ignored.txt"), "should be ignored").unwrap();
-        fs::create_dir(dir.path().join("build")).unwrap();
-        fs::write(dir.path().join("build").join("output.js"), "built").unwrap();
+        // agent.txt does not exist on main, so this pick conflicts.
+        let result =
+            pick_commit(&repo_path, &picked.to_string(), false, false, None, &|_| {}).unwrap();
+        assert_eq!(result.commit, None);
+        assert_eq!(result.conflicts, vec!["agent.txt"]);
+        assert!(result.message.starts_with("Agent edits agent.txt"));
+        let state = get_repo_state(repo_path.clone()).unwrap();
+        assert_eq!(state.state, "cherry_pick");
+        assert_eq!(state.head, Some(picked.to_string()));
+        let status = get_git_status(repo_path.clone()).unwrap();
+        assert!(status
+            .iter()
+            .any(|s| s.path == "agent.txt" && s.index_status == "conflicted"));
+
+        let busy = pick_commit(&repo_path, &picked.to_string(), false, false, None, &|_| {});
+        assert!(busy.err().unwrap().message.contains("in progress"));
+
+        git_abort_pick(repo_path.clone()).unwrap();
+        assert_eq!(get_repo_state(repo_path.clone()).unwrap().state, "clean");
+        assert!(!dir.path().join("agent.txt").exists());
+        assert!(get_git_status(repo_path.clone()).unwrap().is_empty());
+
+        // Picking the agent's base commit first makes the second pick clean.
+        let agent_add = agent_tip.id().to_string();
+        let result = pick_commit(&repo_path, &agent_add, false, false, None, &|_| {}).unwrap();
+        assert!(result.commit.is_some());
+        let result =
+            pick_commit(&repo_path, &picked.to_string(), false, false, None, &|_| {}).unwrap();
+        let new_head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(result.commit, Some(new_head.id().to_string()));
+        assert_eq!(new_head.author().name(), Some("Agent"));
+        assert_eq!(new_head.author().when().seconds(), 1_700_000_000);
+        assert_eq!(new_head.committer().name(), Some("Test User"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("agent.txt")).unwrap(),
+            "agent\nmore\n"
+        );
 
-        let result = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
+        let result = pick_commit(&repo_path, "HEAD", true, false, None, &|_| {}).unwrap();
+        let revert = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(result.commit, Some(revert.id().to_string()));
+        assert_eq!(
+            revert.message(),
+            Some(
+                format!(
+                    "Revert \"Agent edits agent.txt\"\n\nThis reverts commit {}.\n",
+                    new_head.id()
+                )
+                .as_str()
+            )
+        );
+        assert_eq!(revert.parent_id(0).unwrap(), new_head.id());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("agent.txt")).unwrap(),
+            "agent\n"
+        );
 
-        let ignored_file = result.iter().find(|e| e.path == "ignored.txt");
-        assert!(
-            ignored_file.is_some(),
-            "ignored.txt should appear in status"
+        // Reverting the revert without committing leaves it staged.
+        let result = pick_commit(&repo_path, "HEAD", true, true, None, &|_| {}).unwrap();
+        assert_eq!(result.commit, None);
+        assert_eq!(
+            repo.head().unwrap().peel_to_commit().unwrap().id(),
+            revert.id()
         );
-        assert_eq!(ignored_file.unwrap().worktree_status, "ignored");
-        assert_eq!(ignored_file.unwrap().index_status, "none");
+        assert_eq!(get_repo_state(repo_path.clone()).unwrap().state, "clean");
+        let status = get_git_status(repo_path.clone()).unwrap();
+        assert!(status
+            .iter()
+            .any(|s| s.path == "agent.txt" && s.index_status == "modified"));
 
-        let ignored_dir = result.iter().find(|e| e.path == "build");
-        assert!(ignored_dir.is_some(), "build dir should appear in status");
-        assert_eq!(ignored_dir.unwrap().worktree_status, "ignored");
+        assert!(pick_commit(&repo_path, "missing", false, false, None, &|_| {}).is_err());
     }
 
     #[test]
-    fn test_stage_hunk() {
+    fn test_hard_reset_clears_conflicted_pick() {
         let (dir, repo) = create_test_repo();
-        create_initial_commit(&repo);
-        add_and_commit(&repo, "file.txt", "line1\nline2\nline3\n", "add file");
+        let main = create_diverged_branches(&repo);
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        repo.set_head("refs/heads/agent").unwrap();
+        let agent_tip = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.reset(agent_tip.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+        let picked = commit_as(&repo, "agent.txt", "agent\nmore\n", "Agent");
+        repo.set_head(&main).unwrap();
+        let main_tip = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.reset(main_tip.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+        let result =
+            pick_commit(&repo_path, &picked.to_string(), false, false, None, &|_| {}).unwrap();
+        assert_eq!(result.conflicts, vec!["agent.txt"]);
+        let conflicted = fs::read(dir.path().join("agent.txt")).unwrap();
+
+        let reset = git_reset(repo_path.clone(), "HEAD".into(), "hard".into()).unwrap();
+        assert_eq!(get_repo_state(repo_path.clone()).unwrap().state, "clean");
+        assert!(!dir.path().join("agent.txt").exists());
+        assert!(get_git_status(repo_path.clone()).unwrap().is_empty());
+
+        let checkpoint = reset.checkpoint.unwrap();
+        let saved = repo.find_commit(checkpoint.oid.parse().unwrap()).unwrap();
+        let entry = saved
+            .tree()
+            .unwrap()
+            .get_path(Path::new("agent.txt"))
+            .unwrap();
+        assert_eq!(repo.find_blob(entry.id()).unwrap().content(), conflicted);
+        let index_tree = saved.parent(0).unwrap().tree().unwrap();
+        assert!(index_tree.get_path(Path::new("agent.txt")).is_err());
+    }
 
-        // Modify the file
-        fs::write(dir.path().join("file.txt"), "line1\nmodified\nline3\n").unwrap();
+    // --- history rewrite tests ---
 
-        // Generate a patch for the modification
-        let patch =
-            "--- a/file.txt\n+++ b/file.txt\n@@ -1,3 +1,3 @@\n line1\n-line2\n+modified\n line3\n";
+    fn rewrite_step(action: &str, hash: git2::Oid, message: Option<&str>) -> RewriteStep {
+        RewriteStep {
+            action: action.to_string(),
+            hash: hash.to_string(),
+            message: message.map(str::to_string),
+        }
+    }
 
-        git_stage_hunk(dir.path().to_str().unwrap().to_string(), patch.to_string()).unwrap();
+    fn head_messages(repo: &Repository, count: usize) -> Vec<String> {
+        let mut commit = repo.head().unwrap().peel_to_commit().unwrap();
+        let mut messages = vec![commit.message().unwrap().to_string()];
+        while messages.len() < count {
+            commit = commit.parent(0).unwrap();
+            messages.push(commit.message().unwrap().to_string());
+        }
+        messages
+    }
 
-        let statuses = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert!(statuses.iter().any(|s| s.index_status == "modified"));
+    #[test]
+    fn test_rewrite_history_squash_reword_drop() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let base = add_and_commit(&repo, "base.txt", "base\n", "base");
+        let c1 = add_and_commit(&repo, "a.txt", "1\n", "wip");
+        let c2 = add_and_commit(&repo, "a.txt", "1\n2\n", "wip 2");
+        let c3 = add_and_commit(&repo, "b.txt", "scratch\n", "wip 3");
+        let c4 = add_and_commit(&repo, "c.txt", "c\n", "Add c");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let status = git_rewrite_history(
+            repo_path.clone(),
+            vec![
+                rewrite_step("reword", c1, Some("Add a\n")),
+                rewrite_step("fixup", c2, None),
+                rewrite_step("drop", c3, None),
+                rewrite_step("pick", c4, None),
+            ],
+        )
+        .unwrap();
+        assert_eq!(status.state, "done");
+        assert!(status.conflicts.is_empty());
+
+        let head = repo.head().unwrap();
+        assert!(head.is_branch());
+        let tip = head.peel_to_commit().unwrap();
+        assert_eq!(status.head, tip.id().to_string());
+        assert_eq!(head_messages(&repo, 3), vec!["Add c", "Add a\n", "base"]);
+        assert_eq!(tip.parent(0).unwrap().parent_id(0).unwrap(), base);
+        let tree = tip.tree().unwrap();
+        assert!(tree.get_path(Path::new("b.txt")).is_err());
+        let a = tree.get_path(Path::new("a.txt")).unwrap();
+        assert_eq!(repo.find_blob(a.id()).unwrap().content(), b"1\n2\n");
+        assert!(!dir.path().join("b.txt").exists());
+        assert!(get_git_status(repo_path.clone()).unwrap().is_empty());
+
+        let backup = repo.find_reference(&status.backup_ref).unwrap();
+        assert!(status.backup_ref.starts_with(REWRITE_BACKUP_PREFIX));
+        assert_eq!(backup.target(), Some(c4));
+
+        // Plans must cover the commits down from HEAD and start with a keeper.
+        let tip = tip.id();
+        let gap = git_rewrite_history(repo_path.clone(), vec![rewrite_step("pick", base, None)]);
+        assert!(gap.err().unwrap().contains("missing"));
+        let squash_first =
+            git_rewrite_history(repo_path.clone(), vec![rewrite_step("squash", tip, None)]);
+        assert!(squash_first.is_err());
+        let reword = git_rewrite_history(repo_path, vec![rewrite_step("reword", tip, None)]);
+        assert!(reword.err().unwrap().contains("needs a message"));
     }
 
     #[test]
-    fn test_unstage_hunk() {
+    fn test_rewrite_history_stops_on_conflict() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
-        add_and_commit(&repo, "file.txt", "line1\nline2\nline3\n", "add file");
+        add_and_commit(&repo, "a.txt", "0\n", "base");
+        let c1 = add_and_commit(&repo, "a.txt", "x\n", "to x");
+        let c2 = add_and_commit(&repo, "a.txt", "y\n", "to y");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        let plan = vec![
+            rewrite_step("pick", c2, None),
+            rewrite_step("pick", c1, None),
+        ];
+
+        let status = git_rewrite_history(repo_path.clone(), plan.clone()).unwrap();
+        assert_eq!(status.state, "stopped");
+        assert_eq!(status.stopped_at, Some(c2.to_string()));
+        assert_eq!(status.conflicts, vec!["a.txt"]);
+        assert_eq!(status.remaining, 1);
+        assert_eq!(get_repo_state(repo_path.clone()).unwrap().state, "rewrite");
+        assert!(fs::read_to_string(dir.path().join("a.txt"))
+            .unwrap()
+            .contains("<<<<<<<"));
+        assert!(git_rewrite_history(repo_path.clone(), plan.clone()).is_err());
+        assert!(git_rewrite_continue(repo_path.clone(), None).is_err());
+
+        git_rewrite_abort(repo_path.clone()).unwrap();
+        assert_eq!(get_repo_state(repo_path.clone()).unwrap().state, "clean");
+        assert!(repo.head().unwrap().is_branch());
+        assert_eq!(repo.head().unwrap().target(), Some(c2));
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "y\n");
+
+        let resolve = |content: &str| {
+            fs::write(dir.path().join("a.txt"), content).unwrap();
+            git_stage(repo_path.clone(), vec!["a.txt".into()]).unwrap();
+        };
+        let status = git_rewrite_history(repo_path.clone(), plan).unwrap();
+        assert_eq!(status.state, "stopped");
+        resolve("y\n");
+        let status = git_rewrite_continue(repo_path.clone(), Some("to y first\n".into())).unwrap();
+        assert_eq!(status.state, "stopped");
+        assert_eq!(status.stopped_at, Some(c1.to_string()));
+        resolve("x\n");
+        let status = git_rewrite_continue(repo_path.clone(), None).unwrap();
+        assert_eq!(status.state, "done");
+        assert_eq!(status.remaining, 0);
+
+        assert!(repo.head().unwrap().is_branch());
+        assert_eq!(
+            head_messages(&repo, 3),
+            vec!["to x", "to y first\n", "base"]
+        );
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "x\n");
+        assert!(get_git_status(repo_path.clone()).unwrap().is_empty());
+        assert_eq!(get_repo_state(repo_path).unwrap().state, "clean");
+    }
 
-        // Stage the modified file first
-        fs::write(dir.path().join("file.txt"), "line1\nmodified\nline3\n").unwrap();
-        git_stage(
-            dir.path().to_str().unwrap().to_string(),
-            vec!["file.txt".to_string()],
+    // --- reset tests ---
+
+    #[test]
+    fn test_reset_writes_backup_refs() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let base = add_and_commit(&repo, "a.txt", "1\n", "one");
+        add_and_commit(&repo, "a.txt", "1\n2\n", "two");
+        let tip = add_and_commit(&repo, "a.txt", "1\n2\n3\n", "three");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        let soft = git_reset(repo_path.clone(), base.to_string(), "soft".into()).unwrap();
+        assert_eq!(soft.backup.head, tip.to_string());
+        assert_eq!(soft.backup.summary, "three");
+        assert_eq!(
+            soft.backup.description,
+            format!("before soft reset to {}", &base.to_string()[..7])
+        );
+        assert!(soft.checkpoint.is_none());
+        assert_eq!(repo.head().unwrap().target(), Some(base));
+        let status = get_git_status(repo_path.clone()).unwrap();
+        assert!(status
+            .iter()
+            .any(|s| s.path == "a.txt" && s.index_status == "modified"));
+
+        // Undo by resetting to the backup, then throw away the edits for real.
+        git_reset(
+            repo_path.clone(),
+            soft.backup.ref_name.clone(),
+            "mixed".into(),
         )
         .unwrap();
+        assert_eq!(repo.head().unwrap().target(), Some(tip));
+        fs::write(dir.path().join("a.txt"), "dirty\n").unwrap();
+        let hard = git_reset(repo_path.clone(), "HEAD~2".into(), "hard".into()).unwrap();
+        assert_eq!(repo.head().unwrap().target(), Some(base));
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "1\n");
+        let checkpoint = hard.checkpoint.unwrap();
+        assert_eq!(checkpoint.session, "reset");
+        let saved = repo.find_commit(checkpoint.oid.parse().unwrap()).unwrap();
+        let entry = saved.tree().unwrap().get_path(Path::new("a.txt")).unwrap();
+        assert_eq!(repo.find_blob(entry.id()).unwrap().content(), b"dirty\n");
+
+        let backups = list_reset_backups(repo_path.clone()).unwrap();
+        assert_eq!(backups.len(), 3);
+        assert_eq!(backups[0].id, hard.backup.id);
+        assert_eq!(backups[0].head, tip.to_string());
+        assert_eq!(backups[2].id, soft.backup.id);
+        assert!(repo.find_reference(&backups[1].ref_name).is_ok());
+
+        assert!(git_reset(repo_path.clone(), "HEAD".into(), "keep".into()).is_err());
+        assert!(git_reset(repo_path.clone(), "nope".into(), "hard".into()).is_err());
+        assert_eq!(list_reset_backups(repo_path).unwrap().len(), 3);
+    }
 
-        // Verify staged
-        let before = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        assert!(before.iter().any(|s| s.index_status == "modified"));
+    // --- operation journal tests ---
 
-        // Unstage via patch
-        let patch =
-            "--- a/file.txt\n+++ b/file.txt\n@@ -1,3 +1,3 @@\n line1\n-line2\n+modified\n line3\n";
-        git_unstage_hunk(dir.path().to_str().unwrap().to_string(), patch.to_string()).unwrap();
+    #[test]
+    fn test_undo_unstage_all_and_reset() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let base = add_and_commit(&repo, "a.txt", "1\n", "one");
+        let tip = add_and_commit(&repo, "a.txt", "1\n2\n", "two");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        fs::write(dir.path().join("a.txt"), "1\n2\n3\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "b\n").unwrap();
 
-        let after = get_git_status(dir.path().to_str().unwrap().to_string()).unwrap();
-        // After unstage, file should show worktree modified but not index modified
-        let file_status = after.iter().find(|s| s.path == "file.txt").unwrap();
-        assert_eq!(file_status.worktree_status, "modified");
-        assert_eq!(file_status.index_status, "none");
+        git_stage(repo_path.clone(), vec!["a.txt".into(), "b.txt".into()]).unwrap();
+        let mut index = repo.index().unwrap();
+        index.read(true).unwrap();
+        let staged = index.write_tree().unwrap();
+        git_unstage(repo_path.clone(), vec![]).unwrap();
+        // Failed commands are not journaled.
+        assert!(git_reset(repo_path.clone(), base.to_string(), "keep".into()).is_err());
+        git_reset(repo_path.clone(), base.to_string(), "soft".into()).unwrap();
+
+        let operations = list_operations(repo_path.clone()).unwrap();
+        let names: Vec<&str> = operations.iter().map(|o| o.operation.as_str()).collect();
+        assert_eq!(names, vec!["reset", "unstage", "stage"]);
+        assert_eq!(operations[0].before.head, Some(tip.to_string()));
+        assert_eq!(operations[0].after.head, Some(base.to_string()));
+
+        let undone = git_undo_operation(repo_path.clone()).unwrap();
+        assert_eq!(undone.operation, "reset");
+        assert!(undone.undone);
+        assert_eq!(repo.head().unwrap().target(), Some(tip));
+        assert!(repo.head().unwrap().is_branch());
+
+        git_undo_operation(repo_path.clone()).unwrap();
+        index.read(true).unwrap();
+        assert_eq!(index.write_tree().unwrap(), staged);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "1\n2\n3\n"
+        );
+
+        git_undo_operation(repo_path.clone()).unwrap();
+        index.read(true).unwrap();
+        assert_eq!(
+            index.write_tree().unwrap(),
+            repo.find_commit(tip).unwrap().tree_id()
+        );
+        assert!(git_undo_operation(repo_path.clone())
+            .err()
+            .unwrap()
+            .contains("nothing to undo"));
+
+        let reflog = get_reflog(repo_path.clone(), None, Some(2)).unwrap();
+        assert_eq!(reflog.len(), 2);
+        assert_eq!(reflog[0].new_id, tip.to_string());
+        assert_eq!(reflog[0].message, "releash undo: reset");
+        assert_eq!(reflog[1].new_id, base.to_string());
+        assert!(get_reflog(repo_path, Some("refs/heads/nope".into()), None)
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
-    fn test_create_branch_already_exists() {
+    fn test_journal_snapshots_only_rewritten_paths() {
         let (dir, repo) = create_test_repo();
         create_initial_commit(&repo);
-        git_create_branch(
-            dir.path().to_str().unwrap().to_string(),
-            "feature".to_string(),
+        let base = add_and_commit(&repo, "a.txt", "1\n", "one");
+        add_and_commit(&repo, "a.txt", "1\n2\n", "two");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        fs::write(dir.path().join("scratch.txt"), "untracked scratch\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "b\n").unwrap();
+
+        git_stage(repo_path.clone(), vec!["b.txt".into()]).unwrap();
+        let stage = &list_operations(repo_path.clone()).unwrap()[0];
+        assert!(stage.worktree_paths.is_empty());
+        assert_eq!(stage.after.worktree_tree, None);
+        let scratch =
+            git2::Oid::hash_object(git2::ObjectType::Blob, b"untracked scratch\n").unwrap();
+        assert!(!repo.odb().unwrap().exists(scratch));
+
+        fs::write(dir.path().join("a.txt"), "dirty\n").unwrap();
+        git_reset(repo_path.clone(), base.to_string(), "hard".into()).unwrap();
+        let reset = &list_operations(repo_path.clone()).unwrap()[0];
+        assert_eq!(reset.worktree_paths, vec!["a.txt", "b.txt"]);
+        let anchor = repo
+            .find_reference(&format!("{JOURNAL_PREFIX}{}", reset.id))
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        let anchored = anchor.tree().unwrap();
+        for (name, tree) in [
+            ("index-before", &reset.before.index_tree),
+            ("worktree-before", &reset.before.worktree_tree),
+            ("worktree-after", &reset.after.worktree_tree),
+        ] {
+            let entry = anchored.get_name(name).unwrap();
+            assert_eq!(Some(entry.id().to_string()), *tree);
+        }
+        let parents: Vec<String> = anchor.parent_ids().map(|id| id.to_string()).collect();
+        assert!(parents.contains(reset.before.head.as_ref().unwrap()));
+        assert!(parents.contains(reset.after.head.as_ref().unwrap()));
+
+        git_undo_operation(repo_path).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "dirty\n"
+        );
+        assert_eq!(fs::read_to_string(dir.path().join("b.txt")).unwrap(), "b\n");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("scratch.txt")).unwrap(),
+            "untracked scratch\n"
+        );
+    }
+
+    #[test]
+    fn test_undo_stash_save_and_drop() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "a.txt", "1\n", "one");
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        fs::write(dir.path().join("a.txt"), "first\n").unwrap();
+        let first = git_stash_save(repo_path.clone(), Some("first".into()), false, false).unwrap();
+        fs::write(dir.path().join("a.txt"), "second\n").unwrap();
+        git_stash_save(repo_path.clone(), Some("second".into()), false, false).unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "1\n");
+
+        git_undo_operation(repo_path.clone()).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "second\n"
+        );
+        let stashes = list_stashes(repo_path.clone()).unwrap();
+        assert_eq!(stashes.len(), 1);
+        assert_eq!(stashes[0].oid, first.oid);
+
+        git_stash_drop(repo_path.clone(), 0).unwrap();
+        assert!(list_stashes(repo_path.clone()).unwrap().is_empty());
+        let drop = &list_operations(repo_path.clone()).unwrap()[0];
+        assert_eq!(drop.operation, "stash_drop");
+        git_undo_operation(repo_path.clone()).unwrap();
+        let stashes = list_stashes(repo_path).unwrap();
+        assert_eq!(stashes.len(), 1);
+        assert_eq!(stashes[0].oid, first.oid);
+        assert!(stashes[0].message.contains("first"));
+    }
+
+    #[test]
+    fn test_undo_branch_switch_and_discard() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        add_and_commit(&repo, "a.txt", "1\n", "one");
+        let main = repo.head().unwrap().name().unwrap().to_string();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+
+        git_create_branch(repo_path.clone(), "agent".into(), None, Some(true)).unwrap();
+        assert_eq!(repo.head().unwrap().name(), Some("refs/heads/agent"));
+        git_undo_operation(repo_path.clone()).unwrap();
+        assert_eq!(repo.head().unwrap().name(), Some(main.as_str()));
+        assert!(repo.find_branch("agent", BranchType::Local).is_err());
+
+        fs::write(dir.path().join("a.txt"), "edited\n").unwrap();
+        fs::write(dir.path().join("new.txt"), "new\n").unwrap();
+        git_discard_files(
+            repo_path.clone(),
+            vec!["a.txt".into(), "new.txt".into()],
+            None,
         )
         .unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "1\n");
+        assert!(!dir.path().join("new.txt").exists());
 
-        let result = git_create_branch(
-            dir.path().to_str().unwrap().to_string(),
-            "feature".to_string(),
+        // Undo refuses once something else has changed the worktree.
+        fs::write(dir.path().join("a.txt"), "later\n").unwrap();
+        assert!(git_undo_operation(repo_path.clone())
+            .err()
+            .unwrap()
+            .contains("changed since"));
+        fs::write(dir.path().join("a.txt"), "1\n").unwrap();
+
+        let undone = git_undo_operation(repo_path.clone()).unwrap();
+        assert_eq!(undone.operation, "discard_files");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "edited\n"
         );
-        assert!(result.is_err());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("new.txt")).unwrap(),
+            "new\n"
+        );
+        let operations = list_operations(repo_path).unwrap();
+        assert!(operations.iter().all(|o| o.undone));
+    }
+
+    #[test]
+    fn test_undo_apply_patch_for_each_target() {
+        let (dir, repo) = create_test_repo();
+        create_initial_commit(&repo);
+        let base = add_and_commit(&repo, "a.txt", "1\n2\n", "add a");
+        add_and_commit(&repo, "a.txt", "one\n2\n", "spell out one");
+        let patch = export_head_patch(&repo);
+        repo.reset(
+            repo.find_commit(base).unwrap().as_object(),
+            git2::ResetType::Hard,
+            None,
+        )
+        .unwrap();
+        let repo_path = dir.path().to_str().unwrap().to_string();
+        git_create_branch(repo_path.clone(), "keep".into(), None, Some(false)).unwrap();
+
+        let staged = || {
+            let repo = Repository::open(dir.path()).unwrap();
+            let entry = repo
+                .index()
+                .unwrap()
+                .get_path(Path::new("a.txt"), 0)
+                .unwrap();
+            let content = repo.find_blob(entry.id).unwrap().content().to_vec();
+            String::from_utf8(content).unwrap()
+        };
+        let on_disk = || fs::read_to_string(dir.path().join("a.txt")).unwrap();
+
+        for target in ["worktree", "index", "both"] {
+            git_apply_patch(repo_path.clone(), patch.clone(), target.into(), None).unwrap();
+            let applied = (on_disk(), staged());
+            assert_ne!(applied, ("1\n2\n".to_string(), "1\n2\n".to_string()));
+
+            let undone = git_undo_operation(repo_path.clone()).unwrap();
+            assert_eq!(undone.operation, "apply_patch", "{target}");
+            assert_eq!(on_disk(), "1\n2\n", "{target}");
+            assert_eq!(staged(), "1\n2\n", "{target}");
+        }
+        assert!(repo.find_branch("keep", BranchType::Local).is_ok());
     }
 }
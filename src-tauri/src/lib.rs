@@ -4,9 +4,20 @@ mod search;
 mod watcher;
 
 use git::{
-    get_current_branch, get_file_at_ref, get_git_log, get_git_status, get_repo_git_dir,
-    get_staged_content, git_commit, git_create_branch, git_push, git_stage, git_stage_hunk,
-    git_unstage, git_unstage_hunk, list_branches,
+    get_checkpoint_diff, get_commit_detail, get_conflict_content, get_current_branch,
+    get_file_at_ref, get_file_diff, get_git_log, get_git_status, get_merge_base, get_ref_diff,
+    get_reflog, get_repo_git_dir, get_repo_state, get_review_diff, get_staged_content,
+    get_stash_diff, git_abort_pick, git_add_worktree, git_apply_patch, git_blame,
+    git_checkout_branch, git_cherry_pick, git_clear_reviewed, git_commit, git_create_branch,
+    git_create_checkpoint, git_delete_branch, git_discard_files, git_discard_hunk,
+    git_discard_lines, git_export_bundle, git_export_format_patch, git_export_patch, git_fetch,
+    git_lock_worktree, git_mark_reviewed, git_prune_worktrees, git_pull, git_push,
+    git_remove_worktree, git_rename_branch, git_reset, git_resolve_conflict,
+    git_restore_checkpoint, git_restore_discard, git_revert, git_rewrite_abort,
+    git_rewrite_continue, git_rewrite_history, git_stage, git_stage_hunk, git_stash_apply,
+    git_stash_drop, git_stash_pop, git_stash_save, git_undo_operation, git_unlock_worktree,
+    git_unstage, git_unstage_hunk, list_branches, list_checkpoints, list_discard_backups,
+    list_operations, list_reset_backups, list_stashes, list_worktrees, preview_patch,
 };
 use pty::{kill_pty, resize_pty, spawn_pty, write_pty, PtyManager};
 use search::{find_definition, find_references, search_files};
@@ -32,6 +43,7 @@ pub fn run() {
             list_branches,
             get_repo_git_dir,
             get_git_status,
+            get_file_diff,
             get_git_log,
             get_current_branch,
             git_stage,
@@ -41,6 +53,58 @@ pub fn run() {
             git_commit,
             git_push,
             git_create_branch,
+            get_conflict_content,
+            git_resolve_conflict,
+            list_worktrees,
+            git_add_worktree,
+            git_lock_worktree,
+            git_unlock_worktree,
+            git_prune_worktrees,
+            git_remove_worktree,
+            list_stashes,
+            git_stash_save,
+            git_stash_apply,
+            git_stash_pop,
+            git_stash_drop,
+            get_stash_diff,
+            git_fetch,
+            git_pull,
+            git_checkout_branch,
+            git_delete_branch,
+            git_rename_branch,
+            git_discard_files,
+            git_discard_hunk,
+            git_discard_lines,
+            list_discard_backups,
+            git_restore_discard,
+            get_commit_detail,
+            git_blame,
+            get_ref_diff,
+            get_merge_base,
+            git_create_checkpoint,
+            list_checkpoints,
+            get_checkpoint_diff,
+            git_restore_checkpoint,
+            git_mark_reviewed,
+            git_clear_reviewed,
+            get_review_diff,
+            git_export_patch,
+            git_export_format_patch,
+            git_export_bundle,
+            preview_patch,
+            git_apply_patch,
+            git_cherry_pick,
+            git_revert,
+            git_abort_pick,
+            get_repo_state,
+            git_rewrite_history,
+            git_rewrite_continue,
+            git_rewrite_abort,
+            git_reset,
+            list_reset_backups,
+            list_operations,
+            git_undo_operation,
+            get_reflog,
             search_files,
             find_definition,
             find_references
@@ -3,12 +3,18 @@ use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, Pt
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::{Read, Write};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Manager, State};
 
+use crate::git;
+
 static PTY_ID_COUNTER: AtomicU64 = AtomicU64::new(1);
 
+const DEFAULT_CHECKPOINT_IDLE_MS: u64 = 5000;
+const MIN_CHECKPOINT_IDLE_MS: u64 = 100;
+
 fn generate_pty_id() -> u64 {
     PTY_ID_COUNTER.fetch_add(1, Ordering::SeqCst)
 }
@@ -43,6 +49,8 @@ pub fn spawn_pty(
     rows: u16,
     cols: u16,
     cwd: Option<String>,
+    checkpoint_session: Option<String>,
+    checkpoint_idle_ms: Option<u64>,
 ) -> Result<u64, String> {
     let pty_system = native_pty_system();
 
@@ -66,7 +74,7 @@ pub fn spawn_pty(
         }
     }
 
-    if let Some(dir) = cwd {
+    if let Some(dir) = &cwd {
         cmd.cwd(dir);
     }
 
@@ -96,9 +104,15 @@ pub fn spawn_pty(
 
     state.sessions.lock().insert(pty_id, session);
 
+    // Time of output not yet covered by a checkpoint, and whether the reader is alive
+    let last_output: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
+    let reading = Arc::new(AtomicBool::new(true));
+
     // Spawn thread to read PTY output
     let app_clone = app.clone();
     let pty_id_clone = pty_id;
+    let last_output_clone = Arc::clone(&last_output);
+    let reading_clone = Arc::clone(&reading);
     std::thread::spawn(move || {
         let mut buf = [0u8; 4096];
         let mut pending = Vec::new();
@@ -106,6 +120,7 @@ pub fn spawn_pty(
             match reader.read(&mut buf) {
                 Ok(0) => break,
                 Ok(n) => {
+                    *last_output_clone.lock() = Some(Instant::now());
                     pending.extend_from_slice(&buf[..n]);
 
                     let valid_up_to = match std::str::from_utf8(&pending) {
@@ -131,8 +146,38 @@ pub fn spawn_pty(
                 Err(_) => break,
             }
         }
+        reading_clone.store(false, Ordering::SeqCst);
     });
 
+    // Spawn thread to checkpoint the repository once output goes idle
+    if let (Some(session), Some(dir)) = (checkpoint_session, cwd) {
+        let app_clone = app.clone();
+        let idle = Duration::from_millis(
+            checkpoint_idle_ms
+                .unwrap_or(DEFAULT_CHECKPOINT_IDLE_MS)
+                .max(MIN_CHECKPOINT_IDLE_MS),
+        );
+        std::thread::spawn(move || {
+            let poll = idle.min(Duration::from_millis(500));
+            while reading.load(Ordering::SeqCst) {
+                std::thread::sleep(poll);
+                let quiet = {
+                    let mut last = last_output.lock();
+                    let quiet = last.is_some_and(|t| t.elapsed() >= idle);
+                    if quiet {
+                        *last = None;
+                    }
+                    quiet
+                };
+                if quiet {
+                    if let Ok(Some(checkpoint)) = git::auto_checkpoint(&dir, &session) {
+                        let _ = app_clone.emit(git::CHECKPOINT_EVENT, checkpoint);
+                    }
+                }
+            }
+        });
+    }
+
     // Spawn thread to wait for process exit
     let app_clone = app.clone();
     let pty_id_clone = pty_id;
@@ -50,6 +50,7 @@ function App() {
 		updateFontSize,
 		updateDefaultDiffBase,
 		updateDefaultDiffMode,
+		updateDiffBaseRef,
 	} = useSettings();
 	const { comments, addComment, markAsSent } = useLineComments();
 	const { stageHunk, unstageHunk } = useGitActions();
@@ -261,6 +262,7 @@ function App() {
 								onFontSizeChange={updateFontSize}
 								onDiffBaseChange={updateDefaultDiffBase}
 								onDiffModeChange={updateDefaultDiffMode}
+								onDiffBaseRefChange={updateDiffBaseRef}
 							/>
 						) : (
 							<SidebarPanel
@@ -284,8 +286,10 @@ function App() {
 							onTabClick={setActiveTab}
 							onTabClose={handleTabClose}
 							diffBase={diffBase}
+							diffBaseRef={settings.diffBaseRef}
 							diffMode={diffMode}
 							onDiffBaseChange={setDiffBase}
+							onDiffBaseRefChange={updateDiffBaseRef}
 							onDiffModeChange={setDiffMode}
 							onContentChange={updateTabContent}
 							fontSize={settings.fontSize}
@@ -319,6 +323,7 @@ function App() {
 							key={rootPath}
 							cwd={rootPath}
 							theme={settings.theme}
+							onCheckpoint={refreshGit}
 						/>
 					</Panel>
 				</Group>
@@ -43,8 +43,10 @@ describe("EditorPanel", () => {
 				onTabClick={vi.fn()}
 				onTabClose={vi.fn()}
 				diffBase="HEAD"
+				diffBaseRef="main"
 				diffMode="split"
 				onDiffBaseChange={vi.fn()}
+				onDiffBaseRefChange={vi.fn()}
 				onDiffModeChange={vi.fn()}
 			/>,
 		);
@@ -61,8 +63,10 @@ describe("EditorPanel", () => {
 				onTabClick={vi.fn()}
 				onTabClose={vi.fn()}
 				diffBase="HEAD"
+				diffBaseRef="main"
 				diffMode="split"
 				onDiffBaseChange={vi.fn()}
+				onDiffBaseRefChange={vi.fn()}
 				onDiffModeChange={vi.fn()}
 			/>,
 		);
@@ -81,8 +85,10 @@ describe("EditorPanel", () => {
 				onTabClick={onTabClick}
 				onTabClose={vi.fn()}
 				diffBase="HEAD"
+				diffBaseRef="main"
 				diffMode="split"
 				onDiffBaseChange={vi.fn()}
+				onDiffBaseRefChange={vi.fn()}
 				onDiffModeChange={vi.fn()}
 			/>,
 		);
@@ -102,8 +108,10 @@ describe("EditorPanel", () => {
 				onTabClick={vi.fn()}
 				onTabClose={onTabClose}
 				diffBase="HEAD"
+				diffBaseRef="main"
 				diffMode="split"
 				onDiffBaseChange={vi.fn()}
+				onDiffBaseRefChange={vi.fn()}
 				onDiffModeChange={vi.fn()}
 			/>,
 		);
@@ -111,4 +119,29 @@ describe("EditorPanel", () => {
 		await user.click(screen.getByLabelText("Close file.ts"));
 		expect(onTabClose).toHaveBeenCalledWith("/test/file.ts");
 	});
+
+	it("should edit the compare ref when diffing against a merge base", async () => {
+		const user = userEvent.setup();
+		const onDiffBaseRefChange = vi.fn();
+
+		render(
+			<EditorPanel
+				tabs={[mockTab]}
+				activeTab={mockTab}
+				onTabClick={vi.fn()}
+				onTabClose={vi.fn()}
+				diffBase="merge-base"
+				diffBaseRef="main"
+				diffMode="split"
+				onDiffBaseChange={vi.fn()}
+				onDiffBaseRefChange={onDiffBaseRefChange}
+				onDiffModeChange={vi.fn()}
+			/>,
+		);
+
+		const input = screen.getByLabelText("Compare ref");
+		await user.clear(input);
+		await user.type(input, "develop{Enter}");
+		expect(onDiffBaseRefChange).toHaveBeenCalledWith("develop");
+	});
 });
@@ -7,16 +7,14 @@ import {
 } from "lucide-react";
 import { useCallback, useEffect, useMemo, useRef, useState } from "react";
 import { Group, Panel, Separator } from "react-resizable-panels";
+import { useDiffBaseRevision } from "@/hooks/useDiffBaseRevision";
 import { useGitOriginalContent } from "@/hooks/useGitOriginalContent";
-import { useHunks } from "@/hooks/useHunks";
+import { useFileDiffHunks, useHunks } from "@/hooks/useHunks";
 import {
-	type ChangeGroup,
 	computeChangeGroups,
-	computeHunks,
 	type Hunk,
 	markStagedGroups,
 } from "@/lib/computeHunks";
-import { generateGroupPatch, generatePatch } from "@/lib/generatePatch";
 import { cn } from "@/lib/utils";
 import type { LineComment } from "@/types/comment";
 import type { TabInfo } from "@/types/editor";
@@ -36,8 +34,10 @@ export interface EditorPanelProps {
 	onTabClick: (path: string) => void;
 	onTabClose: (path: string) => void;
 	diffBase: DiffBase;
+	diffBaseRef: string;
 	diffMode: DiffMode;
 	onDiffBaseChange: (base: DiffBase) => void;
+	onDiffBaseRefChange: (ref: string) => void;
 	onDiffModeChange: (mode: DiffMode) => void;
 	onContentChange?: (path: string, content: string) => void;
 	fontSize?: number;
@@ -49,8 +49,8 @@ export interface EditorPanelProps {
 		endLine?: number,
 	) => void;
 	rootPath?: string | null;
-	onStageHunk?: (repoPath: string, patch: string) => Promise<void>;
-	onUnstageHunk?: (repoPath: string, patch: string) => Promise<void>;
+	onStageHunk?: (repoPath: string, patchBase64: string) => Promise<void>;
+	onUnstageHunk?: (repoPath: string, patchBase64: string) => Promise<void>;
 	onSendToTerminal?: (comments: LineComment[]) => void;
 	theme?: Theme;
 	gitRefreshKey?: number;
@@ -66,8 +66,10 @@ export function EditorPanel({
 	onTabClick,
 	onTabClose,
 	diffBase,
+	diffBaseRef,
 	diffMode,
 	onDiffBaseChange,
+	onDiffBaseRefChange,
 	onDiffModeChange,
 	onContentChange,
 	fontSize,
@@ -75,6 +77,7 @@ export function EditorPanel({
 	onAddComment,
 	rootPath,
 	onStageHunk,
+	onUnstageHunk,
 	onSendToTerminal,
 	theme,
 	gitRefreshKey,
@@ -93,51 +96,72 @@ export function EditorPanel({
 	const revealKeyRef = useRef(0);
 	const filePath = activeTab?.path ?? null;
 
-	const originalContent = useGitOriginalContent(
-		filePath,
+	const isRefBase = diffBase === "ref" || diffBase === "merge-base";
+	const baseRevision = useDiffBaseRevision(
+		rootPath ?? null,
 		diffBase,
-		activeTab?.originalContent ?? "",
+		diffBaseRef,
 		gitRefreshKey,
 	);
 
-	const stagedContent = useGitOriginalContent(
-		diffBase === "HEAD" ? filePath : null,
-		"staged",
-		"",
+	const originalContent = useGitOriginalContent(
+		filePath,
+		diffBase,
+		activeTab?.originalContent ?? "",
 		gitRefreshKey,
+		baseRevision,
 	);
 
 	const modifiedContent = activeTab?.content ?? "";
+	const savedContent = activeTab?.originalContent;
+
+	const relativePath = useMemo(() => {
+		if (!rootPath || !filePath) return null;
+		return filePath.startsWith(`${rootPath}/`)
+			? filePath.slice(rootPath.length + 1)
+			: filePath;
+	}, [rootPath, filePath]);
 
 	const {
+		hunks,
 		changeGroups: rawChangeGroups,
 		currentIndex,
 		total,
 		goTo,
-	} = useHunks(originalContent, modifiedContent, filePath ?? undefined);
+	} = useHunks(
+		rootPath ?? null,
+		relativePath,
+		diffBase === "HEAD"
+			? "head_to_worktree"
+			: diffBase === "staged"
+				? "index_to_worktree"
+				: "commit_to_worktree",
+		gitRefreshKey,
+		savedContent,
+		baseRevision,
+	);
+
+	const headPath = diffBase === "HEAD" ? relativePath : null;
+	const stagedHunks = useFileDiffHunks(
+		rootPath ?? null,
+		headPath,
+		"head_to_index",
+		gitRefreshKey,
+		savedContent,
+	);
+	const unstagedHunks = useFileDiffHunks(
+		rootPath ?? null,
+		headPath,
+		"index_to_worktree",
+		gitRefreshKey,
+		savedContent,
+	);
 
 	const changeGroups = useMemo(() => {
 		if (diffBase !== "HEAD") return rawChangeGroups;
-		const hunks = computeHunks(
-			originalContent,
-			modifiedContent,
-			filePath ?? undefined,
-		);
-		const stagedHunks = computeHunks(
-			originalContent,
-			stagedContent,
-			filePath ?? undefined,
-		);
 		const stagedGroups = computeChangeGroups(stagedHunks);
 		return markStagedGroups(rawChangeGroups, stagedGroups, hunks, stagedHunks);
-	}, [
-		rawChangeGroups,
-		diffBase,
-		originalContent,
-		modifiedContent,
-		stagedContent,
-		filePath,
-	]);
+	}, [rawChangeGroups, diffBase, hunks, stagedHunks]);
 
 	const commentRanges = useMemo(() => {
 		if (!comments || !filePath) return undefined;
@@ -170,202 +194,111 @@ export function EditorPanel({
 		[comments, filePath],
 	);
 
-	const getRelativePath = useCallback(() => {
-		if (!rootPath || !filePath) return null;
-		return filePath.startsWith(`${rootPath}/`)
-			? filePath.slice(rootPath.length + 1)
-			: filePath;
-	}, [rootPath, filePath]);
-
-	const findMatchingGroup = useCallback(
-		(
-			targetLines: string[],
-			hunks: Hunk[],
-			groups: ChangeGroup[],
-			reverse = false,
-		) => {
-			let target: string;
-			if (reverse) {
-				const newMinus = targetLines
-					.filter((l) => l.startsWith("+"))
-					.map((l) => `-${l.slice(1)}`);
-				const newPlus = targetLines
-					.filter((l) => l.startsWith("-"))
-					.map((l) => `+${l.slice(1)}`);
-				target = [...newMinus, ...newPlus].join("\n");
-			} else {
-				target = targetLines.join("\n");
-			}
-			for (const g of groups) {
-				const h = hunks.find((h) => h.index === g.hunkIndex);
+	const findMatchingHunk = useCallback(
+		(groupIndex: number, candidates: Hunk[]) => {
+			const group = rawChangeGroups.find((g) => g.groupIndex === groupIndex);
+			const hunk = hunks.find((h) => h.index === group?.hunkIndex);
+			if (!group || !hunk) return null;
+			const target = hunk.lines
+				.slice(group.lineOffsetStart, group.lineOffsetEnd + 1)
+				.join("\n");
+			for (const g of computeChangeGroups(candidates)) {
+				const h = candidates.find((h) => h.index === g.hunkIndex);
 				if (!h) continue;
 				const lines = h.lines
 					.slice(g.lineOffsetStart, g.lineOffsetEnd + 1)
 					.join("\n");
-				if (lines === target) return { group: g, hunk: h };
+				if (lines === target) return h;
 			}
 			return null;
 		},
-		[],
+		[rawChangeGroups, hunks],
 	);
 
 	const handleStageGroup = useCallback(
 		async (groupIndex: number) => {
-			const relativePath = getRelativePath();
-			if (!relativePath || !rootPath) return;
-			const allHunks = computeHunks(
-				originalContent,
-				modifiedContent,
-				relativePath,
+			if (!rootPath) return;
+			const hunk = findMatchingHunk(
+				groupIndex,
+				diffBase === "HEAD" ? unstagedHunks : hunks,
 			);
-			const allGroups = computeChangeGroups(allHunks);
-			const group = allGroups.find((g) => g.groupIndex === groupIndex);
-			if (!group) return;
-			const hunk = allHunks.find((h) => h.index === group.hunkIndex);
 			if (!hunk) return;
-
-			let patchHunk = hunk;
-			let patchGroup = group;
-
-			if (diffBase === "HEAD") {
-				const targetLines = hunk.lines.slice(
-					group.lineOffsetStart,
-					group.lineOffsetEnd + 1,
-				);
-				const s2wHunks = computeHunks(
-					stagedContent,
-					modifiedContent,
-					relativePath,
-				);
-				const s2wGroups = computeChangeGroups(s2wHunks);
-				const match = findMatchingGroup(targetLines, s2wHunks, s2wGroups);
-				if (!match) return;
-				patchHunk = match.hunk;
-				patchGroup = match.group;
-			}
-
-			const patch = generateGroupPatch(relativePath, patchHunk, patchGroup);
-			if (patch) {
-				try {
-					await onStageHunk?.(rootPath, patch);
-					onGitChanged?.();
-				} catch (e) {
-					console.error("Stage group failed:", e);
-				}
+			try {
+				await onStageHunk?.(rootPath, hunk.patchBase64);
+				onGitChanged?.();
+			} catch (e) {
+				console.error("Stage group failed:", e);
 			}
 		},
 		[
-			getRelativePath,
 			rootPath,
-			originalContent,
-			modifiedContent,
-			stagedContent,
+			findMatchingHunk,
 			diffBase,
+			unstagedHunks,
+			hunks,
 			onStageHunk,
 			onGitChanged,
-			findMatchingGroup,
 		],
 	);
 
 	const handleUnstageGroup = useCallback(
 		async (groupIndex: number) => {
-			const relativePath = getRelativePath();
-			if (!relativePath || !rootPath) return;
-
-			const allHunks = computeHunks(
-				originalContent,
-				modifiedContent,
-				relativePath,
-			);
-			const allGroups = computeChangeGroups(allHunks);
-			const group = allGroups.find((g) => g.groupIndex === groupIndex);
-			if (!group) return;
-			const hunk = allHunks.find((h) => h.index === group.hunkIndex);
+			if (!rootPath) return;
+			const hunk = findMatchingHunk(groupIndex, stagedHunks);
 			if (!hunk) return;
+			try {
+				await onUnstageHunk?.(rootPath, hunk.patchBase64);
+				onGitChanged?.();
+			} catch (e) {
+				console.error("Unstage group failed:", e);
+			}
+		},
+		[rootPath, findMatchingHunk, stagedHunks, onUnstageHunk, onGitChanged],
+	);
 
-			const targetLines = hunk.lines.slice(
-				group.lineOffsetStart,
-				group.lineOffsetEnd + 1,
-			);
-			const s2hHunks = computeHunks(
-				stagedContent,
-				originalContent,
-				relativePath,
-			);
-			const s2hGroups = computeChangeGroups(s2hHunks);
-			const match = findMatchingGroup(targetLines, s2hHunks, s2hGroups, true);
-			if (!match) return;
-
-			const patch = generateGroupPatch(relativePath, match.hunk, match.group);
-			if (patch) {
-				try {
-					await onStageHunk?.(rootPath, patch);
-					onGitChanged?.();
-				} catch (e) {
-					console.error("Unstage group failed:", e);
-				}
+	// hunkはcontextなしなので、下から適用すれば前のhunkの行番号がずれない
+	const applyBottomUp = useCallback(
+		async (
+			targets: Hunk[],
+			apply:
+				| ((repoPath: string, patchBase64: string) => Promise<void>)
+				| undefined,
+		) => {
+			if (!rootPath || !apply) return;
+			for (const hunk of [...targets].reverse()) {
+				await apply(rootPath, hunk.patchBase64);
 			}
 		},
-		[
-			getRelativePath,
-			rootPath,
-			originalContent,
-			modifiedContent,
-			stagedContent,
-			onStageHunk,
-			onGitChanged,
-			findMatchingGroup,
-		],
+		[rootPath],
 	);
 
 	const handleStageAll = useCallback(async () => {
-		const relativePath = getRelativePath();
-		if (!relativePath || !rootPath) return;
-		const base = diffBase === "HEAD" ? stagedContent : originalContent;
-		const allHunks = computeHunks(base, modifiedContent, relativePath);
-		const allIndices = allHunks.map((h) => h.index);
-		const patch = generatePatch(relativePath, allHunks, allIndices);
-		if (patch) {
-			try {
-				await onStageHunk?.(rootPath, patch);
-				onGitChanged?.();
-			} catch (e) {
-				console.error("Stage all failed:", e);
-			}
+		try {
+			await applyBottomUp(
+				diffBase === "HEAD" ? unstagedHunks : hunks,
+				onStageHunk,
+			);
+		} catch (e) {
+			console.error("Stage all failed:", e);
 		}
+		onGitChanged?.();
 	}, [
-		getRelativePath,
-		rootPath,
-		originalContent,
-		modifiedContent,
-		stagedContent,
+		applyBottomUp,
 		diffBase,
+		unstagedHunks,
+		hunks,
 		onStageHunk,
 		onGitChanged,
 	]);
 
 	const handleUnstageAll = useCallback(async () => {
-		const relativePath = getRelativePath();
-		if (!relativePath || !rootPath || !stagedContent) return;
-		const allHunks = computeHunks(stagedContent, originalContent, relativePath);
-		const allIndices = allHunks.map((h) => h.index);
-		const patch = generatePatch(relativePath, allHunks, allIndices);
-		if (patch) {
-			try {
-				await onStageHunk?.(rootPath, patch);
-				onGitChanged?.();
-			} catch (e) {
-				console.error("Unstage all failed:", e);
-			}
+		try {
+			await applyBottomUp(stagedHunks, onUnstageHunk);
+		} catch (e) {
+			console.error("Unstage all failed:", e);
 		}
-	}, [
-		getRelativePath,
-		rootPath,
-		originalContent,
-		stagedContent,
-		onStageHunk,
-		onGitChanged,
-	]);
+		onGitChanged?.();
+	}, [applyBottomUp, stagedHunks, onUnstageHunk, onGitChanged]);
 
 	const revealHunk = useCallback(
 		(index: number) => {
@@ -455,7 +388,7 @@ export function EditorPanel({
 									fontSize={fontSize}
 									changeGroups={changeGroups}
 									commentRanges={commentRanges}
-									onStageHunk={handleStageGroup}
+									onStageHunk={isRefBase ? undefined : handleStageGroup}
 									onUnstageHunk={
 										diffBase === "HEAD" ? handleUnstageGroup : undefined
 									}
@@ -483,10 +416,30 @@ export function EditorPanel({
 									>
 										<option value="HEAD">HEAD</option>
 										<option value="staged">Staged</option>
+										<option value="ref">Ref</option>
+										<option value="merge-base">Merge Base</option>
 									</select>
+									{isRefBase && (
+										<input
+											key={diffBaseRef}
+											type="text"
+											defaultValue={diffBaseRef}
+											aria-label="Compare ref"
+											onBlur={(e) => {
+												const ref = e.target.value.trim();
+												if (ref && ref !== diffBaseRef) {
+													onDiffBaseRefChange(ref);
+												}
+											}}
+											onKeyDown={(e) => {
+												if (e.key === "Enter") e.currentTarget.blur();
+											}}
+											className="w-28 bg-muted border border-border rounded px-2 py-0.5 text-xs font-mono focus:outline-none focus:ring-1 focus:ring-primary"
+										/>
+									)}
 									{total > 0 && (
 										<div className="flex items-center gap-1 ml-2">
-											{onStageHunk && (
+											{onStageHunk && !isRefBase && (
 												<>
 													<button
 														type="button"
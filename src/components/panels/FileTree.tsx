@@ -20,6 +20,8 @@ function getStatusColor(status: FileStatus) {
 			return "text-status-deleted";
 		case "untracked":
 			return "text-status-untracked";
+		case "conflicted":
+			return "text-status-conflicted";
 		case "ignored":
 			return "text-status-ignored";
 		default:
@@ -37,6 +39,8 @@ function getStatusIndicator(status: FileStatus) {
 			return "D";
 		case "untracked":
 			return "U";
+		case "conflicted":
+			return "!";
 		case "ignored":
 			return null;
 		default:
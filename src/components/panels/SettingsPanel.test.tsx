@@ -9,6 +9,7 @@ describe("SettingsPanel", () => {
 		fontSize: 14,
 		defaultDiffBase: "staged",
 		defaultDiffMode: "inline",
+		diffBaseRef: "main",
 	};
 
 	const defaultProps = {
@@ -17,6 +18,7 @@ describe("SettingsPanel", () => {
 		onFontSizeChange: vi.fn(),
 		onDiffBaseChange: vi.fn(),
 		onDiffModeChange: vi.fn(),
+		onDiffBaseRefChange: vi.fn(),
 	};
 
 	it("should render Settings header", () => {
@@ -79,6 +81,20 @@ describe("SettingsPanel", () => {
 		expect(onDiffBaseChange).toHaveBeenCalledWith("HEAD");
 	});
 
+	it("should call onDiffBaseRefChange when compare ref is edited", () => {
+		const onDiffBaseRefChange = vi.fn();
+		render(
+			<SettingsPanel
+				{...defaultProps}
+				onDiffBaseRefChange={onDiffBaseRefChange}
+			/>,
+		);
+		const input = screen.getByLabelText("Compare Ref") as HTMLInputElement;
+		expect(input.value).toBe("main");
+		fireEvent.change(input, { target: { value: "develop" } });
+		expect(onDiffBaseRefChange).toHaveBeenCalledWith("develop");
+	});
+
 	it("should call onDiffModeChange when view is changed", () => {
 		const onDiffModeChange = vi.fn();
 		render(
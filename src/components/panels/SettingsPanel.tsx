@@ -7,6 +7,7 @@ export interface SettingsPanelProps {
 	onFontSizeChange: (size: number) => void;
 	onDiffBaseChange: (base: DiffBase) => void;
 	onDiffModeChange: (mode: DiffMode) => void;
+	onDiffBaseRefChange: (ref: string) => void;
 }
 
 export function SettingsPanel({
@@ -15,6 +16,7 @@ export function SettingsPanel({
 	onFontSizeChange,
 	onDiffBaseChange,
 	onDiffModeChange,
+	onDiffBaseRefChange,
 }: SettingsPanelProps) {
 	return (
 		<div className="h-full flex flex-col bg-sidebar">
@@ -59,9 +61,27 @@ export function SettingsPanel({
 						>
 							<option value="staged">Staged</option>
 							<option value="HEAD">HEAD</option>
+							<option value="ref">Ref</option>
+							<option value="merge-base">Merge Base</option>
 						</select>
 					</div>
 
+					<div className="flex flex-col gap-1.5">
+						<label
+							htmlFor="diff-base-ref-input"
+							className="text-xs font-medium text-muted-foreground"
+						>
+							Compare Ref
+						</label>
+						<input
+							id="diff-base-ref-input"
+							type="text"
+							value={settings.diffBaseRef}
+							onChange={(e) => onDiffBaseRefChange(e.target.value)}
+							className="w-full bg-muted border border-border rounded px-2 py-1 text-xs font-mono focus:outline-none focus:ring-1 focus:ring-primary"
+						/>
+					</div>
+
 					<div className="flex flex-col gap-1.5">
 						<label
 							htmlFor="diff-mode-select"
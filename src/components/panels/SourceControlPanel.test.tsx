@@ -234,6 +234,34 @@ describe("SourceControlPanel", () => {
 		});
 	});
 
+	it("should show hook output when a commit hook fails", async () => {
+		mockGitActions.commit.mockRejectedValue({
+			reason: "hook_failed",
+			hook: "pre-commit",
+			exit_code: 1,
+			output: "lint: 2 problems\n",
+			message: "pre-commit hook failed; commit aborted",
+		});
+		mockGitStatus.stagedFiles = [
+			{
+				path: "file.txt",
+				index_status: "new",
+				worktree_status: "none",
+			},
+		];
+		render(<SourceControlPanel rootPath="/test/repo" />);
+
+		fireEvent.change(screen.getByPlaceholderText("Commit summary"), {
+			target: { value: "test" },
+		});
+		fireEvent.click(screen.getByText("Commit"));
+
+		await waitFor(() => {
+			expect(screen.getByText(/pre-commit hook failed/)).toBeInTheDocument();
+		});
+		expect(screen.getByText(/lint: 2 problems/)).toBeInTheDocument();
+	});
+
 	it("should call push", async () => {
 		render(<SourceControlPanel rootPath="/test/repo" />);
 
@@ -6,6 +6,7 @@ import {
 	Minus,
 	Pencil,
 	Plus,
+	TriangleAlert,
 	X,
 } from "lucide-react";
 import { useCallback, useState } from "react";
@@ -13,7 +14,15 @@ import { ScrollArea } from "@/components/ui/scroll-area";
 import { useGitActions } from "@/hooks/useGitActions";
 import { useGitStatus } from "@/hooks/useGitStatus";
 import { cn } from "@/lib/utils";
-import type { GitFileStatus } from "@/types/git";
+import type { CommitError, GitFileStatus } from "@/types/git";
+
+function commitErrorText(e: unknown): string {
+	if (typeof e === "object" && e !== null && "reason" in e) {
+		const err = e as CommitError;
+		return [err.message, err.output.trim()].filter(Boolean).join("\n");
+	}
+	return String(e);
+}
 
 function statusColor(status: string): string {
 	switch (status) {
@@ -25,6 +34,8 @@ function statusColor(status: string): string {
 			return "text-status-deleted";
 		case "renamed":
 			return "text-status-modified";
+		case "conflicted":
+			return "text-status-conflicted";
 		default:
 			return "text-muted-foreground";
 	}
@@ -41,6 +52,8 @@ function StatusIcon({ status }: { status: string }) {
 			return <Plus className={iconClass} />;
 		case "deleted":
 			return <Minus className={iconClass} />;
+		case "conflicted":
+			return <TriangleAlert className={iconClass} />;
 		default:
 			return null;
 	}
@@ -232,7 +245,7 @@ export function SourceControlPanel({
 			refreshStatus();
 			onGitChanged?.();
 		} catch (e) {
-			setError(String(e));
+			setError(commitErrorText(e));
 		} finally {
 			setLoading(false);
 		}
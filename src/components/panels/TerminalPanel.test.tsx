@@ -32,6 +32,7 @@ describe("TerminalPanel", () => {
 			expect.objectContaining({ current: expect.any(HTMLDivElement) }),
 			undefined,
 			undefined,
+			undefined,
 		);
 	});
 });
@@ -7,6 +7,7 @@ import {
 	ContextMenuTrigger,
 } from "@/components/ui/context-menu";
 import { useTerminal } from "@/hooks/useTerminal";
+import type { CheckpointInfo } from "@/types/git";
 import type { Theme } from "@/types/settings";
 import "@xterm/xterm/css/xterm.css";
 
@@ -17,17 +18,19 @@ export interface TerminalPanelHandle {
 export interface TerminalPanelProps {
 	cwd?: string | null;
 	theme?: Theme;
+	onCheckpoint?: (checkpoint: CheckpointInfo) => void;
 }
 
 export const TerminalPanel = forwardRef<
 	TerminalPanelHandle,
 	TerminalPanelProps
->(function TerminalPanel({ cwd, theme }, ref) {
+>(function TerminalPanel({ cwd, theme, onCheckpoint }, ref) {
 	const containerRef = useRef<HTMLDivElement>(null);
 	const { terminalRef, writeToTerminal } = useTerminal(
 		containerRef,
 		cwd,
 		theme,
+		onCheckpoint,
 	);
 
 	useImperativeHandle(
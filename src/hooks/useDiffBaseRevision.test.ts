@@ -0,0 +1,62 @@
+import { renderHook, waitFor } from "@testing-library/react";
+import { beforeEach, describe, expect, it, vi } from "vitest";
+import { useDiffBaseRevision } from "./useDiffBaseRevision";
+
+const mockInvoke = vi.fn();
+vi.mock("@tauri-apps/api/core", () => ({
+	invoke: (...args: unknown[]) => mockInvoke(...args),
+}));
+
+describe("useDiffBaseRevision", () => {
+	beforeEach(() => {
+		vi.clearAllMocks();
+	});
+
+	it("should use HEAD for the HEAD base", () => {
+		const { result } = renderHook(() =>
+			useDiffBaseRevision("/repo", "HEAD", "main"),
+		);
+		expect(result.current).toBe("HEAD");
+		expect(mockInvoke).not.toHaveBeenCalled();
+	});
+
+	it("should have no revision for the staged base", () => {
+		const { result } = renderHook(() =>
+			useDiffBaseRevision("/repo", "staged", "main"),
+		);
+		expect(result.current).toBeNull();
+	});
+
+	it("should use the configured ref for the ref base", () => {
+		const { result } = renderHook(() =>
+			useDiffBaseRevision("/repo", "ref", "develop"),
+		);
+		expect(result.current).toBe("develop");
+		expect(mockInvoke).not.toHaveBeenCalled();
+	});
+
+	it("should resolve the merge base with HEAD", async () => {
+		mockInvoke.mockResolvedValue("abc123");
+		const { result } = renderHook(() =>
+			useDiffBaseRevision("/repo", "merge-base", "main"),
+		);
+		await waitFor(() => {
+			expect(result.current).toBe("abc123");
+		});
+		expect(mockInvoke).toHaveBeenCalledWith("get_merge_base", {
+			repoPath: "/repo",
+			base: "main",
+		});
+	});
+
+	it("should have no revision when the merge base cannot be found", async () => {
+		mockInvoke.mockRejectedValue("Invalid revision 'nope'");
+		const { result } = renderHook(() =>
+			useDiffBaseRevision("/repo", "merge-base", "nope"),
+		);
+		await waitFor(() => {
+			expect(mockInvoke).toHaveBeenCalled();
+		});
+		expect(result.current).toBeNull();
+	});
+});
@@ -0,0 +1,49 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useEffect, useState } from "react";
+import type { DiffBase } from "@/types/settings";
+
+export function useDiffBaseRevision(
+	repoPath: string | null,
+	diffBase: DiffBase,
+	diffBaseRef: string,
+	refreshKey?: number,
+): string | null {
+	const [mergeBase, setMergeBase] = useState<string | null>(null);
+
+	// biome-ignore lint/correctness/useExhaustiveDependencies: HEADが動いたらmerge baseを再計算
+	useEffect(() => {
+		if (diffBase !== "merge-base" || !repoPath || !diffBaseRef) {
+			setMergeBase(null);
+			return;
+		}
+
+		let cancelled = false;
+
+		invoke<string>("get_merge_base", { repoPath, base: diffBaseRef })
+			.then((oid) => {
+				if (!cancelled) {
+					setMergeBase(oid);
+				}
+			})
+			.catch(() => {
+				if (!cancelled) {
+					setMergeBase(null);
+				}
+			});
+
+		return () => {
+			cancelled = true;
+		};
+	}, [repoPath, diffBase, diffBaseRef, refreshKey]);
+
+	switch (diffBase) {
+		case "HEAD":
+			return "HEAD";
+		case "ref":
+			return diffBaseRef || null;
+		case "merge-base":
+			return mergeBase;
+		default:
+			return null;
+	}
+}
@@ -57,15 +57,54 @@ describe("useGitActions", () => {
 		expect(hash).toBe("abc123");
 	});
 
-	it("push should invoke git_push and return output", async () => {
-		mockInvoke.mockResolvedValue("Everything up-to-date");
+	it("commit should pass commit options", async () => {
+		mockInvoke.mockResolvedValue("abc123");
+		const { result } = renderHook(() => useGitActions());
+
+		await result.current.commit("/repo", "", { amend: true, sign_off: true });
+		expect(mockInvoke).toHaveBeenCalledWith("git_commit", {
+			repoPath: "/repo",
+			message: "",
+			options: { amend: true, sign_off: true },
+		});
+	});
+
+	it("push should invoke git_push and return the result", async () => {
+		const pushResult = { remote: "origin", refspecs: [], updated: [] };
+		mockInvoke.mockResolvedValue(pushResult);
 		const { result } = renderHook(() => useGitActions());
 
 		const output = await result.current.push("/repo");
 		expect(mockInvoke).toHaveBeenCalledWith("git_push", {
 			repoPath: "/repo",
 		});
-		expect(output).toBe("Everything up-to-date");
+		expect(output).toBe(pushResult);
+	});
+
+	it("push should pass remote options", async () => {
+		mockInvoke.mockResolvedValue(undefined);
+		const { result } = renderHook(() => useGitActions());
+
+		await result.current.push("/repo", {
+			remote: "upstream",
+			forceWithLease: true,
+		});
+		expect(mockInvoke).toHaveBeenCalledWith("git_push", {
+			repoPath: "/repo",
+			remote: "upstream",
+			forceWithLease: true,
+		});
+	});
+
+	it("pull should invoke git_pull with rebase", async () => {
+		mockInvoke.mockResolvedValue(undefined);
+		const { result } = renderHook(() => useGitActions());
+
+		await result.current.pull("/repo", { rebase: true });
+		expect(mockInvoke).toHaveBeenCalledWith("git_pull", {
+			repoPath: "/repo",
+			rebase: true,
+		});
 	});
 
 	it("createBranch should invoke git_create_branch", async () => {
@@ -1,5 +1,16 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useCallback } from "react";
+import type {
+	CommitOptions,
+	FetchResult,
+	PullResult,
+	PushResult,
+} from "../types/git";
+
+export interface RemoteOptions {
+	remote?: string;
+	token?: string;
+}
 
 export function useGitActions() {
 	const stage = useCallback(async (repoPath: string, paths: string[]) => {
@@ -11,23 +22,66 @@ export function useGitActions() {
 	}, []);
 
 	const commit = useCallback(
-		async (repoPath: string, message: string): Promise<string> => {
-			return await invoke<string>("git_commit", { repoPath, message });
+		async (
+			repoPath: string,
+			message: string,
+			options?: CommitOptions,
+		): Promise<string> => {
+			return await invoke<string>("git_commit", {
+				repoPath,
+				message,
+				options,
+			});
 		},
 		[],
 	);
 
-	const push = useCallback(async (repoPath: string): Promise<string> => {
-		return await invoke<string>("git_push", { repoPath });
-	}, []);
+	const push = useCallback(
+		async (
+			repoPath: string,
+			options: RemoteOptions & {
+				refspec?: string;
+				forceWithLease?: boolean;
+			} = {},
+		): Promise<PushResult> => {
+			return await invoke<PushResult>("git_push", { repoPath, ...options });
+		},
+		[],
+	);
 
-	const stageHunk = useCallback(async (repoPath: string, patch: string) => {
-		await invoke("git_stage_hunk", { repoPath, patch });
-	}, []);
+	const fetch = useCallback(
+		async (
+			repoPath: string,
+			options: RemoteOptions & { refspecs?: string[]; prune?: boolean } = {},
+		): Promise<FetchResult> => {
+			return await invoke<FetchResult>("git_fetch", { repoPath, ...options });
+		},
+		[],
+	);
 
-	const unstageHunk = useCallback(async (repoPath: string, patch: string) => {
-		await invoke("git_unstage_hunk", { repoPath, patch });
-	}, []);
+	const pull = useCallback(
+		async (
+			repoPath: string,
+			options: RemoteOptions & { branch?: string; rebase?: boolean } = {},
+		): Promise<PullResult> => {
+			return await invoke<PullResult>("git_pull", { repoPath, ...options });
+		},
+		[],
+	);
+
+	const stageHunk = useCallback(
+		async (repoPath: string, patchBase64: string) => {
+			await invoke("git_stage_hunk", { repoPath, patchBase64 });
+		},
+		[],
+	);
+
+	const unstageHunk = useCallback(
+		async (repoPath: string, patchBase64: string) => {
+			await invoke("git_unstage_hunk", { repoPath, patchBase64 });
+		},
+		[],
+	);
 
 	const createBranch = useCallback(
 		async (repoPath: string, branchName: string) => {
@@ -36,5 +90,15 @@ export function useGitActions() {
 		[],
 	);
 
-	return { stage, unstage, stageHunk, unstageHunk, commit, push, createBranch };
+	return {
+		stage,
+		unstage,
+		stageHunk,
+		unstageHunk,
+		commit,
+		push,
+		fetch,
+		pull,
+		createBranch,
+	};
 }
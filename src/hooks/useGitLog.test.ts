@@ -1,4 +1,4 @@
-import { renderHook, waitFor } from "@testing-library/react";
+import { act, renderHook, waitFor } from "@testing-library/react";
 import { beforeEach, describe, expect, it, vi } from "vitest";
 import type { CommitInfo } from "@/types/git";
 import { useGitLog } from "./useGitLog";
@@ -30,6 +30,10 @@ describe("useGitLog", () => {
 				author_name: "Test",
 				author_email: "test@test.com",
 				timestamp: 1700000000,
+				parents: ["def5678901234"],
+				insertions: 3,
+				deletions: 1,
+				files_changed: 1,
 			},
 			{
 				hash: "def5678901234",
@@ -38,6 +42,10 @@ describe("useGitLog", () => {
 				author_name: "Test",
 				author_email: "test@test.com",
 				timestamp: 1699000000,
+				parents: [],
+				insertions: 0,
+				deletions: 0,
+				files_changed: 0,
 			},
 		];
 		mockInvoke.mockResolvedValue(mockCommits);
@@ -53,6 +61,7 @@ describe("useGitLog", () => {
 		expect(mockInvoke).toHaveBeenCalledWith("get_git_log", {
 			repoPath: "/test/repo",
 			limit: 50,
+			filter: {},
 		});
 	});
 
@@ -65,10 +74,55 @@ describe("useGitLog", () => {
 			expect(mockInvoke).toHaveBeenCalledWith("get_git_log", {
 				repoPath: "/test/repo",
 				limit: 10,
+				filter: {},
 			});
 		});
 	});
 
+	it("should pass filters and page with the last hash as cursor", async () => {
+		const page = (hashes: string[]) =>
+			hashes.map((hash) => ({
+				hash,
+				short_hash: hash.slice(0, 7),
+				message: hash,
+				author_name: "Test",
+				author_email: "test@test.com",
+				timestamp: 1700000000,
+				parents: [],
+				insertions: 0,
+				deletions: 0,
+				files_changed: 0,
+			}));
+		mockInvoke
+			.mockResolvedValueOnce(page(["aaa", "bbb"]))
+			.mockResolvedValueOnce(page(["ccc"]));
+
+		const { result } = renderHook(() =>
+			useGitLog("/test/repo", 2, { path: "src" }),
+		);
+
+		await waitFor(() => {
+			expect(result.current.commits).toHaveLength(2);
+		});
+		expect(result.current.hasMore).toBe(true);
+
+		await act(async () => {
+			await result.current.loadMore();
+		});
+
+		expect(mockInvoke).toHaveBeenLastCalledWith("get_git_log", {
+			repoPath: "/test/repo",
+			limit: 2,
+			filter: { path: "src", cursor: "bbb" },
+		});
+		expect(result.current.commits.map((c) => c.hash)).toEqual([
+			"aaa",
+			"bbb",
+			"ccc",
+		]);
+		expect(result.current.hasMore).toBe(false);
+	});
+
 	it("should handle error gracefully", async () => {
 		mockInvoke.mockRejectedValue(new Error("failed"));
 
@@ -1,33 +1,66 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useCallback, useEffect, useState } from "react";
-import type { CommitInfo } from "@/types/git";
+import type { CommitInfo, LogFilter } from "@/types/git";
 
-export function useGitLog(rootPath: string | null, limit?: number) {
+export function useGitLog(
+	rootPath: string | null,
+	limit?: number,
+	filter?: LogFilter,
+) {
 	const [commits, setCommits] = useState<CommitInfo[]>([]);
 	const [loading, setLoading] = useState(false);
+	const [hasMore, setHasMore] = useState(false);
+	const pageSize = limit ?? 50;
+	const filterKey = JSON.stringify(filter ?? {});
 
 	const fetch = useCallback(async () => {
 		if (!rootPath) {
 			setCommits([]);
+			setHasMore(false);
 			return;
 		}
 		setLoading(true);
 		try {
 			const result = await invoke<CommitInfo[]>("get_git_log", {
 				repoPath: rootPath,
-				limit: limit ?? 50,
+				limit: pageSize,
+				filter: JSON.parse(filterKey) as LogFilter,
 			});
 			setCommits(result);
+			setHasMore(result.length === pageSize);
 		} catch {
 			setCommits([]);
+			setHasMore(false);
 		} finally {
 			setLoading(false);
 		}
-	}, [rootPath, limit]);
+	}, [rootPath, pageSize, filterKey]);
+
+	const loadMore = useCallback(async () => {
+		const last = commits[commits.length - 1];
+		if (!rootPath || !last || !hasMore) return;
+		setLoading(true);
+		try {
+			const result = await invoke<CommitInfo[]>("get_git_log", {
+				repoPath: rootPath,
+				limit: pageSize,
+				filter: {
+					...(JSON.parse(filterKey) as LogFilter),
+					cursor: last.hash,
+				},
+			});
+			setCommits((prev) => [...prev, ...result]);
+			setHasMore(result.length === pageSize);
+		} catch {
+			setHasMore(false);
+		} finally {
+			setLoading(false);
+		}
+	}, [rootPath, pageSize, filterKey, commits, hasMore]);
 
 	useEffect(() => {
 		fetch();
 	}, [fetch]);
 
-	return { commits, loading };
+	return { commits, loading, hasMore, loadMore };
 }
@@ -17,6 +17,17 @@ vi.mock("@/lib/normalizePath", () => ({
 	normalizePath: (p: string) => p,
 }));
 
+function textBlob(content: string) {
+	return {
+		binary: false,
+		encoding: "UTF-8",
+		content,
+		size: content.length,
+		truncated: false,
+		image: null,
+	};
+}
+
 describe("useGitOriginalContent", () => {
 	beforeEach(() => {
 		vi.clearAllMocks();
@@ -26,7 +37,8 @@ describe("useGitOriginalContent", () => {
 	it("should return content from get_file_at_ref on success", async () => {
 		mockInvoke.mockImplementation((cmd: string) => {
 			if (cmd === "get_repo_git_dir") return Promise.resolve("/repo/.git");
-			if (cmd === "get_file_at_ref") return Promise.resolve("original content");
+			if (cmd === "get_file_at_ref")
+				return Promise.resolve(textBlob("original content"));
 			return Promise.resolve("");
 		});
 
@@ -43,7 +55,7 @@ describe("useGitOriginalContent", () => {
 		mockInvoke.mockImplementation((cmd: string) => {
 			if (cmd === "get_repo_git_dir") return Promise.resolve("/repo/.git");
 			if (cmd === "get_staged_content")
-				return Promise.resolve("staged content");
+				return Promise.resolve(textBlob("staged content"));
 			return Promise.resolve("");
 		});
 
@@ -56,6 +68,57 @@ describe("useGitOriginalContent", () => {
 		});
 	});
 
+	it("should read the file at the resolved base revision", async () => {
+		mockInvoke.mockImplementation((cmd: string) => {
+			if (cmd === "get_repo_git_dir") return Promise.resolve("/repo/.git");
+			if (cmd === "get_file_at_ref")
+				return Promise.resolve(textBlob("merge base content"));
+			return Promise.resolve("");
+		});
+
+		const { result } = renderHook(() =>
+			useGitOriginalContent(
+				"/repo/file.ts",
+				"merge-base",
+				"fallback",
+				0,
+				"abc123",
+			),
+		);
+
+		await waitFor(() => {
+			expect(result.current).toBe("merge base content");
+		});
+		expect(mockInvoke).toHaveBeenCalledWith("get_file_at_ref", {
+			filePath: "/repo/file.ts",
+			gitRef: "abc123",
+		});
+	});
+
+	it("should return empty string for binary blobs", async () => {
+		mockInvoke.mockImplementation((cmd: string) => {
+			if (cmd === "get_repo_git_dir") return Promise.resolve("/repo/.git");
+			if (cmd === "get_file_at_ref")
+				return Promise.resolve({
+					binary: true,
+					encoding: null,
+					content: "",
+					size: 4,
+					truncated: false,
+					image: null,
+				});
+			return Promise.resolve("");
+		});
+
+		const { result } = renderHook(() =>
+			useGitOriginalContent("/repo/image.bin", "HEAD", "fallback"),
+		);
+
+		await waitFor(() => {
+			expect(result.current).toBe("");
+		});
+	});
+
 	it("should return empty string when invoke fails (untracked file)", async () => {
 		mockInvoke.mockImplementation((cmd: string) => {
 			if (cmd === "get_repo_git_dir") return Promise.resolve("/repo/.git");
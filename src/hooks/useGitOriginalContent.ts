@@ -4,12 +4,14 @@ import { useEffect, useRef, useState } from "react";
 import type { DiffBase } from "@/components/panels/MonacoDiffViewer";
 import type { FileChangeEvent } from "@/hooks/useFileWatcher";
 import { normalizePath } from "@/lib/normalizePath";
+import type { BlobContent } from "@/types/git";
 
 export function useGitOriginalContent(
 	filePath: string | null,
 	diffBase: DiffBase,
 	fallbackContent: string,
 	externalRefreshKey?: number,
+	baseRevision?: string | null,
 ): string {
 	const [originalContent, setOriginalContent] = useState(fallbackContent);
 	const [refreshKey, setRefreshKey] = useState(0);
@@ -82,23 +84,34 @@ export function useGitOriginalContent(
 			return;
 		}
 
+		const gitRef =
+			diffBase === "staged"
+				? null
+				: diffBase === "HEAD"
+					? "HEAD"
+					: (baseRevision ?? null);
+		if (diffBase !== "staged" && !gitRef) {
+			setOriginalContent("");
+			return;
+		}
+
 		let cancelled = false;
 
 		const fetchContent = async () => {
 			try {
-				let content: string;
-				if (diffBase === "staged") {
-					content = await invoke<string>("get_staged_content", {
+				let blob: BlobContent;
+				if (gitRef) {
+					blob = await invoke<BlobContent>("get_file_at_ref", {
 						filePath,
+						gitRef,
 					});
 				} else {
-					content = await invoke<string>("get_file_at_ref", {
+					blob = await invoke<BlobContent>("get_staged_content", {
 						filePath,
-						gitRef: diffBase,
 					});
 				}
 				if (!cancelled) {
-					setOriginalContent(content);
+					setOriginalContent(blob.binary ? "" : blob.content);
 				}
 			} catch {
 				if (!cancelled) {
@@ -112,7 +125,14 @@ export function useGitOriginalContent(
 		return () => {
 			cancelled = true;
 		};
-	}, [filePath, diffBase, fallbackContent, refreshKey, externalRefreshKey]);
+	}, [
+		filePath,
+		diffBase,
+		baseRevision,
+		fallbackContent,
+		refreshKey,
+		externalRefreshKey,
+	]);
 
 	return originalContent;
 }
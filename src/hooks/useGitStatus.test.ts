@@ -76,6 +76,31 @@ describe("useGitStatus", () => {
 		);
 	});
 
+	it("should map conflicted status separately from modified", async () => {
+		const mockEntries: GitFileStatus[] = [
+			{
+				path: "conflict.txt",
+				index_status: "conflicted",
+				worktree_status: "conflicted",
+			},
+			{ path: "edited.txt", index_status: "none", worktree_status: "modified" },
+		];
+		mockInvoke.mockResolvedValue(mockEntries);
+
+		const { result } = renderHook(() => useGitStatus("/test/repo"));
+
+		await waitFor(() => {
+			expect(result.current.statusMap.size).toBe(2);
+		});
+
+		expect(result.current.statusMap.get("/test/repo/conflict.txt")).toBe(
+			"conflicted",
+		);
+		expect(result.current.statusMap.get("/test/repo/edited.txt")).toBe(
+			"modified",
+		);
+	});
+
 	it("should map index-only statuses when worktree is none", async () => {
 		const mockEntries: GitFileStatus[] = [
 			{
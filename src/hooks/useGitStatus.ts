@@ -6,10 +6,14 @@ import type { GitFileStatus } from "@/types/git";
 
 function toFileStatus(entry: GitFileStatus): FileStatus {
 	if (entry.worktree_status === "ignored") return "ignored";
+	if (entry.worktree_status === "conflicted") return "conflicted";
 	if (entry.worktree_status === "new") return "untracked";
 	if (entry.worktree_status === "modified") return "modified";
 	if (entry.worktree_status === "deleted") return "deleted";
+	if (entry.worktree_status === "renamed") return "modified";
+	if (entry.worktree_status === "copied") return "untracked";
 	if (entry.index_status === "new") return "added";
+	if (entry.index_status === "copied") return "added";
 	if (entry.index_status === "modified") return "modified";
 	if (entry.index_status === "deleted") return "deleted";
 	if (entry.index_status === "renamed") return "modified";
@@ -1,28 +1,134 @@
-import { act, renderHook } from "@testing-library/react";
-import { describe, expect, it } from "vitest";
+import { act, renderHook, waitFor } from "@testing-library/react";
+import { beforeEach, describe, expect, it, vi } from "vitest";
+import type { DiffHunkInfo, FileDiff } from "@/types/git";
 import { useHunks } from "./useHunks";
 
+const mockInvoke = vi.fn();
+vi.mock("@tauri-apps/api/core", () => ({
+	invoke: (...args: unknown[]) => mockInvoke(...args),
+}));
+
+function modifiedHunk(line: number, from: string, to: string): DiffHunkInfo {
+	return {
+		header: `@@ -${line} +${line} @@`,
+		old_start: line,
+		old_lines: 1,
+		new_start: line,
+		new_lines: 1,
+		lines: [
+			{
+				origin: "deletion",
+				content: from,
+				old_lineno: line,
+				new_lineno: null,
+				no_newline_at_eof: false,
+			},
+			{
+				origin: "addition",
+				content: to,
+				old_lineno: null,
+				new_lineno: line,
+				no_newline_at_eof: false,
+			},
+		],
+		patch_base64: btoa(`${from}->${to}`),
+	};
+}
+
+function fileDiff(hunks: DiffHunkInfo[]): FileDiff {
+	return {
+		old_path: "file.txt",
+		new_path: "file.txt",
+		status: "modified",
+		binary: false,
+		additions: hunks.length,
+		deletions: hunks.length,
+		hunks,
+	};
+}
+
+const twoHunks = fileDiff([
+	modifiedHunk(2, "b", "B"),
+	modifiedHunk(18, "r", "R"),
+]);
+
+async function renderTwoHunks() {
+	mockInvoke.mockResolvedValue([twoHunks]);
+	const hook = renderHook(() =>
+		useHunks("/repo", "file.txt", "head_to_worktree"),
+	);
+	await waitFor(() => {
+		expect(hook.result.current.total).toBe(2);
+	});
+	return hook;
+}
+
 describe("useHunks", () => {
-	it("should return empty hunks for identical content", () => {
-		const { result } = renderHook(() => useHunks("hello\n", "hello\n"));
+	beforeEach(() => {
+		vi.clearAllMocks();
+	});
+
+	it("should request zero-context hunks for the file", async () => {
+		await renderTwoHunks();
+		expect(mockInvoke).toHaveBeenCalledWith("get_file_diff", {
+			repoPath: "/repo",
+			mode: "head_to_worktree",
+			paths: ["file.txt"],
+			contextLines: 0,
+			base: null,
+		});
+	});
+
+	it("should diff against the given commit", async () => {
+		mockInvoke.mockResolvedValue([twoHunks]);
+		const { result } = renderHook(() =>
+			useHunks("/repo", "file.txt", "commit_to_worktree", 0, "", "abc123"),
+		);
+		await waitFor(() => {
+			expect(result.current.total).toBe(2);
+		});
+		expect(mockInvoke).toHaveBeenCalledWith(
+			"get_file_diff",
+			expect.objectContaining({ mode: "commit_to_worktree", base: "abc123" }),
+		);
+	});
+
+	it("should not fetch a commit diff without a base", () => {
+		renderHook(() =>
+			useHunks("/repo", "file.txt", "commit_to_worktree", 0, "", null),
+		);
+		expect(mockInvoke).not.toHaveBeenCalled();
+	});
+
+	it("should return empty hunks when the file has no changes", async () => {
+		mockInvoke.mockResolvedValue([]);
+		const { result } = renderHook(() =>
+			useHunks("/repo", "file.txt", "head_to_worktree"),
+		);
+		await waitFor(() => {
+			expect(mockInvoke).toHaveBeenCalled();
+		});
 		expect(result.current.hunks).toEqual([]);
 		expect(result.current.total).toBe(0);
 		expect(result.current.currentGroup).toBeNull();
 	});
 
-	it("should compute hunks for different content", () => {
+	it("should not fetch without a repository", () => {
 		const { result } = renderHook(() =>
-			useHunks("line1\nline2\n", "line1\nmodified\n"),
+			useHunks(null, "file.txt", "head_to_worktree"),
 		);
-		expect(result.current.hunks.length).toBeGreaterThan(0);
-		expect(result.current.total).toBeGreaterThan(0);
+		expect(mockInvoke).not.toHaveBeenCalled();
+		expect(result.current.total).toBe(0);
+	});
+
+	it("should keep the backend patch on each hunk", async () => {
+		const { result } = await renderTwoHunks();
+		expect(result.current.hunks[0].patchBase64).toBe(btoa("b->B"));
+		expect(result.current.hunks[0].lines).toEqual(["-b", "+B"]);
 	});
 
-	it("should navigate to next hunk", () => {
-		const lines =
-			"a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn\no\np\nq\nr\ns\nt\n";
-		const modified = lines.replace("b\n", "B\n").replace("r\n", "R\n");
-		const { result } = renderHook(() => useHunks(lines, modified));
+	it("should navigate to next hunk", async () => {
+		const { result } = await renderTwoHunks();
 
 		expect(result.current.currentIndex).toBe(0);
 
@@ -32,11 +138,8 @@ describe("useHunks", () => {
 		expect(result.current.currentIndex).toBe(1);
 	});
 
-	it("should wrap around when navigating past last hunk", () => {
-		const lines =
-			"a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn\no\np\nq\nr\ns\nt\n";
-		const modified = lines.replace("b\n", "B\n").replace("r\n", "R\n");
-		const { result } = renderHook(() => useHunks(lines, modified));
+	it("should wrap around when navigating past last hunk", async () => {
+		const { result } = await renderTwoHunks();
 
 		act(() => {
 			result.current.goToNext();
@@ -47,11 +150,8 @@ describe("useHunks", () => {
 		expect(result.current.currentIndex).toBe(0);
 	});
 
-	it("should navigate to previous hunk with wrap", () => {
-		const lines =
-			"a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn\no\np\nq\nr\ns\nt\n";
-		const modified = lines.replace("b\n", "B\n").replace("r\n", "R\n");
-		const { result } = renderHook(() => useHunks(lines, modified));
+	it("should navigate to previous hunk with wrap", async () => {
+		const { result } = await renderTwoHunks();
 
 		act(() => {
 			result.current.goToPrev();
@@ -59,11 +159,8 @@ describe("useHunks", () => {
 		expect(result.current.currentIndex).toBe(result.current.total - 1);
 	});
 
-	it("should go to specific hunk index", () => {
-		const lines =
-			"a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn\no\np\nq\nr\ns\nt\n";
-		const modified = lines.replace("b\n", "B\n").replace("r\n", "R\n");
-		const { result } = renderHook(() => useHunks(lines, modified));
+	it("should go to specific hunk index", async () => {
+		const { result } = await renderTwoHunks();
 
 		act(() => {
 			result.current.goTo(1);
@@ -71,9 +168,10 @@ describe("useHunks", () => {
 		expect(result.current.currentIndex).toBe(1);
 	});
 
-	it("should return currentGroup when hunks exist", () => {
-		const { result } = renderHook(() => useHunks("line1\n", "modified\n"));
+	it("should return currentGroup when hunks exist", async () => {
+		const { result } = await renderTwoHunks();
 		expect(result.current.currentGroup).not.toBeNull();
 		expect(result.current.currentGroup?.groupIndex).toBe(0);
+		expect(result.current.currentGroup?.newStart).toBe(2);
 	});
 });
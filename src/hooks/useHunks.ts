@@ -1,18 +1,73 @@
+import { invoke } from "@tauri-apps/api/core";
 import { useCallback, useEffect, useMemo, useState } from "react";
 import {
 	type ChangeGroup,
 	computeChangeGroups,
-	computeHunks,
+	type Hunk,
+	hunksFromFileDiff,
 } from "@/lib/computeHunks";
+import type { FileDiff, GitDiffMode } from "@/types/git";
+
+export function useFileDiffHunks(
+	repoPath: string | null,
+	filePath: string | null,
+	mode: GitDiffMode,
+	refreshKey?: number,
+	savedContent?: string,
+	base?: string | null,
+): Hunk[] {
+	const [hunks, setHunks] = useState<Hunk[]>([]);
+
+	// biome-ignore lint/correctness/useExhaustiveDependencies: refreshKey/savedContent変更時に再取得
+	useEffect(() => {
+		if (!repoPath || !filePath || (mode === "commit_to_worktree" && !base)) {
+			setHunks([]);
+			return;
+		}
+
+		let cancelled = false;
+
+		invoke<FileDiff[]>("get_file_diff", {
+			repoPath,
+			mode,
+			paths: [filePath],
+			contextLines: 0,
+			base: mode === "commit_to_worktree" ? base : null,
+		})
+			.then((diffs) => {
+				if (!cancelled) {
+					setHunks(hunksFromFileDiff(diffs[0]));
+				}
+			})
+			.catch(() => {
+				if (!cancelled) {
+					setHunks([]);
+				}
+			});
+
+		return () => {
+			cancelled = true;
+		};
+	}, [repoPath, filePath, mode, base, refreshKey, savedContent]);
+
+	return hunks;
+}
 
 export function useHunks(
-	original: string,
-	modified: string,
-	filePath?: string,
+	repoPath: string | null,
+	filePath: string | null,
+	mode: GitDiffMode,
+	refreshKey?: number,
+	savedContent?: string,
+	base?: string | null,
 ) {
-	const hunks = useMemo(
-		() => computeHunks(original, modified, filePath),
-		[original, modified, filePath],
+	const hunks = useFileDiffHunks(
+		repoPath,
+		filePath,
+		mode,
+		refreshKey,
+		savedContent,
+		base,
 	);
 
 	const changeGroups = useMemo(() => computeChangeGroups(hunks), [hunks]);
@@ -19,6 +19,7 @@ describe("useSettings", () => {
 			fontSize: 14,
 			defaultDiffBase: "staged",
 			defaultDiffMode: "inline",
+			diffBaseRef: "main",
 		});
 	});
 
@@ -47,6 +48,7 @@ describe("useSettings", () => {
 			fontSize: 14,
 			defaultDiffBase: "staged",
 			defaultDiffMode: "inline",
+			diffBaseRef: "main",
 		});
 	});
 
@@ -88,6 +90,16 @@ describe("useSettings", () => {
 		expect(document.documentElement.classList.contains("light")).toBe(false);
 	});
 
+	it("should update diffBaseRef", () => {
+		const { result } = renderHook(() => useSettings());
+
+		act(() => {
+			result.current.updateDiffBaseRef("develop");
+		});
+
+		expect(result.current.settings.diffBaseRef).toBe("develop");
+	});
+
 	it("should update fontSize", () => {
 		const { result } = renderHook(() => useSettings());
 
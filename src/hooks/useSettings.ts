@@ -64,11 +64,16 @@ export function useSettings() {
 		setSettings((prev) => ({ ...prev, defaultDiffMode }));
 	}, []);
 
+	const updateDiffBaseRef = useCallback((diffBaseRef: string) => {
+		setSettings((prev) => ({ ...prev, diffBaseRef }));
+	}, []);
+
 	return {
 		settings,
 		updateTheme,
 		updateFontSize,
 		updateDefaultDiffBase,
 		updateDefaultDiffMode,
+		updateDiffBaseRef,
 	};
 }
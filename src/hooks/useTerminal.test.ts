@@ -1,6 +1,6 @@
 import { renderHook, waitFor } from "@testing-library/react";
 import { beforeEach, describe, expect, it, vi } from "vitest";
-import { useTerminal } from "./useTerminal";
+import { checkpointSessionFor, useTerminal } from "./useTerminal";
 
 const mockInvoke = vi.fn();
 const mockListen = vi.fn();
@@ -60,6 +60,7 @@ describe("useTerminal", () => {
 	let containerRef: { current: HTMLDivElement | null };
 	let mockUnlistenOutput: ReturnType<typeof vi.fn>;
 	let mockUnlistenExit: ReturnType<typeof vi.fn>;
+	let mockUnlistenCheckpoint: ReturnType<typeof vi.fn>;
 
 	beforeEach(() => {
 		vi.clearAllMocks();
@@ -68,11 +69,13 @@ describe("useTerminal", () => {
 
 		mockUnlistenOutput = vi.fn();
 		mockUnlistenExit = vi.fn();
+		mockUnlistenCheckpoint = vi.fn();
 
 		mockInvoke.mockResolvedValue(1);
 		mockListen
 			.mockResolvedValueOnce(mockUnlistenOutput)
-			.mockResolvedValueOnce(mockUnlistenExit);
+			.mockResolvedValueOnce(mockUnlistenExit)
+			.mockResolvedValueOnce(mockUnlistenCheckpoint);
 	});
 
 	it("Terminal と FitAddon が正しく生成される", () => {
@@ -93,10 +96,51 @@ describe("useTerminal", () => {
 				rows: 24,
 				cols: 80,
 				cwd: null,
+				checkpointSession: null,
 			});
 		});
 	});
 
+	it("cwd からチェックポイントのセッションを決めて spawn_pty に渡す", async () => {
+		renderHook(() => useTerminal(containerRef, "/work/my repo"));
+
+		await waitFor(() => {
+			expect(mockInvoke).toHaveBeenCalledWith("spawn_pty", {
+				rows: 24,
+				cols: 80,
+				cwd: "/work/my repo",
+				checkpointSession: "terminal-my-repo",
+			});
+		});
+		expect(checkpointSessionFor("/work/feature.x/")).toBe(
+			"terminal-feature-x",
+		);
+	});
+
+	it("自分のセッションの git-checkpoint-created だけを通知する", async () => {
+		const onCheckpoint = vi.fn();
+		renderHook(() =>
+			useTerminal(containerRef, "/work/repo", undefined, onCheckpoint),
+		);
+
+		await waitFor(() => {
+			expect(mockListen).toHaveBeenCalledWith(
+				"git-checkpoint-created",
+				expect.any(Function),
+			);
+		});
+		const handler = mockListen.mock.calls.find(
+			([event]) => event === "git-checkpoint-created",
+		)?.[1] as (event: { payload: { session: string } }) => void;
+
+		handler({ payload: { session: "terminal-other" } });
+		expect(onCheckpoint).not.toHaveBeenCalled();
+
+		const checkpoint = { id: "terminal-repo/1", session: "terminal-repo" };
+		handler({ payload: checkpoint });
+		expect(onCheckpoint).toHaveBeenCalledWith(checkpoint);
+	});
+
 	it("pty-output と pty-exit のリスナーが登録される", async () => {
 		renderHook(() => useTerminal(containerRef));
 
@@ -135,6 +179,7 @@ describe("useTerminal", () => {
 
 		expect(mockUnlistenOutput).toHaveBeenCalled();
 		expect(mockUnlistenExit).toHaveBeenCalled();
+		expect(mockUnlistenCheckpoint).toHaveBeenCalled();
 		expect(mockInvoke).toHaveBeenCalledWith("kill_pty", { ptyId: 1 });
 		expect(mockTerminalInstance.dispose).toHaveBeenCalled();
 	});
@@ -3,6 +3,7 @@ import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import { FitAddon } from "@xterm/addon-fit";
 import { type ITheme, Terminal } from "@xterm/xterm";
 import { type RefObject, useCallback, useEffect, useRef } from "react";
+import type { CheckpointInfo } from "@/types/git";
 import type { Theme } from "@/types/settings";
 
 interface PtyOutput {
@@ -65,10 +66,17 @@ function getTerminalTheme(theme?: Theme): ITheme {
 	return theme === "light" ? terminalLightTheme : terminalDarkTheme;
 }
 
+// Checkpoint session names become a ref component, so keep them to safe characters.
+export function checkpointSessionFor(cwd: string): string {
+	const name = cwd.split(/[\\/]/).filter(Boolean).pop() ?? "";
+	return `terminal-${name.replace(/[^A-Za-z0-9_-]/g, "-")}`;
+}
+
 export function useTerminal(
 	containerRef: RefObject<HTMLDivElement | null>,
 	cwd?: string | null,
 	theme?: Theme,
+	onCheckpoint?: (checkpoint: CheckpointInfo) => void,
 ) {
 	const terminalRef = useRef<Terminal | null>(null);
 	const fitAddonRef = useRef<FitAddon | null>(null);
@@ -76,6 +84,8 @@ export function useTerminal(
 	const resizeObserverRef = useRef<ResizeObserver | null>(null);
 	const themeRef = useRef(theme);
 	themeRef.current = theme;
+	const onCheckpointRef = useRef(onCheckpoint);
+	onCheckpointRef.current = onCheckpoint;
 
 	useEffect(() => {
 		const container = containerRef.current;
@@ -101,6 +111,8 @@ export function useTerminal(
 
 		let unlistenOutput: UnlistenFn | null = null;
 		let unlistenExit: UnlistenFn | null = null;
+		let unlistenCheckpoint: UnlistenFn | null = null;
+		const checkpointSession = cwd ? checkpointSessionFor(cwd) : null;
 
 		const initPty = async () => {
 			unlistenOutput = await listen<PtyOutput>("pty-output", (event) => {
@@ -118,6 +130,15 @@ export function useTerminal(
 				}
 			});
 
+			unlistenCheckpoint = await listen<CheckpointInfo>(
+				"git-checkpoint-created",
+				(event) => {
+					if (event.payload.session === checkpointSession) {
+						onCheckpointRef.current?.(event.payload);
+					}
+				},
+			);
+
 			if (!isMounted) return;
 
 			const { rows, cols } = terminal;
@@ -125,6 +146,7 @@ export function useTerminal(
 				rows,
 				cols,
 				cwd: cwd ?? null,
+				checkpointSession,
 			});
 
 			if (!isMounted) {
@@ -170,6 +192,7 @@ export function useTerminal(
 			resizeObserver.disconnect();
 			unlistenOutput?.();
 			unlistenExit?.();
+			unlistenCheckpoint?.();
 			if (ptyIdRef.current !== null) {
 				invoke("kill_pty", { ptyId: ptyIdRef.current }).catch(() => {});
 			}
@@ -1,89 +1,123 @@
 import { describe, expect, it } from "vitest";
+import type { DiffHunkInfo, DiffLineInfo, FileDiff } from "@/types/git";
 import {
 	computeChangeGroups,
-	computeHunks,
+	hunksFromFileDiff,
 	markStagedGroups,
 } from "./computeHunks";
 
-describe("computeHunks", () => {
-	it("should return empty array for identical content", () => {
-		const hunks = computeHunks("hello\nworld\n", "hello\nworld\n");
-		expect(hunks).toEqual([]);
+function line(
+	origin: DiffLineInfo["origin"],
+	content: string,
+	noNewline = false,
+): DiffLineInfo {
+	return {
+		origin,
+		content,
+		old_lineno: null,
+		new_lineno: null,
+		no_newline_at_eof: noNewline,
+	};
+}
+
+function hunk(
+	oldStart: number,
+	oldLines: number,
+	newStart: number,
+	newLines: number,
+	lines: DiffLineInfo[],
+): DiffHunkInfo {
+	return {
+		header: `@@ -${oldStart},${oldLines} +${newStart},${newLines} @@`,
+		old_start: oldStart,
+		old_lines: oldLines,
+		new_start: newStart,
+		new_lines: newLines,
+		lines,
+		patch_base64: btoa(`${oldStart}:${newStart}`),
+	};
+}
+
+function fileDiff(hunks: DiffHunkInfo[]): FileDiff {
+	return {
+		old_path: "file.txt",
+		new_path: "file.txt",
+		status: "modified",
+		binary: false,
+		additions: 0,
+		deletions: 0,
+		hunks,
+	};
+}
+
+describe("hunksFromFileDiff", () => {
+	it("should return empty array without a diff", () => {
+		expect(hunksFromFileDiff(undefined)).toEqual([]);
 	});
 
-	it("should detect added lines", () => {
-		const hunks = computeHunks("line1\nline2\n", "line1\nline2\nline3\n");
-		expect(hunks.length).toBe(1);
-		expect(hunks[0].lines).toContain("+line3");
-	});
-
-	it("should detect removed lines", () => {
-		const hunks = computeHunks("line1\nline2\nline3\n", "line1\nline3\n");
-		expect(hunks.length).toBe(1);
-		expect(hunks[0].lines).toContain("-line2");
-	});
-
-	it("should detect modified lines", () => {
-		const hunks = computeHunks(
-			"line1\noriginal\nline3\n",
-			"line1\nmodified\nline3\n",
+	it("should prefix lines by origin", () => {
+		const hunks = hunksFromFileDiff(
+			fileDiff([
+				hunk(1, 2, 1, 2, [
+					line("context", "line1"),
+					line("deletion", "original"),
+					line("addition", "modified"),
+				]),
+			]),
 		);
-		expect(hunks.length).toBe(1);
-		expect(hunks[0].lines).toContain("-original");
-		expect(hunks[0].lines).toContain("+modified");
+		expect(hunks[0].lines).toEqual([" line1", "-original", "+modified"]);
 	});
 
-	it("should detect multiple hunks", () => {
-		const lines =
-			"a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn\no\np\nq\nr\ns\nt\n";
-		const modified = lines.replace("b\n", "B\n").replace("r\n", "R\n");
-		const hunks = computeHunks(lines, modified);
-		expect(hunks.length).toBe(2);
-	});
-
-	it("should handle empty original", () => {
-		const hunks = computeHunks("", "new content\n");
-		expect(hunks.length).toBe(1);
-		expect(hunks[0].lines).toContain("+new content");
-	});
-
-	it("should handle empty modified", () => {
-		const hunks = computeHunks("content\n", "");
-		expect(hunks.length).toBe(1);
-		expect(hunks[0].lines).toContain("-content");
+	it("should add a marker after a line without trailing newline", () => {
+		const hunks = hunksFromFileDiff(
+			fileDiff([hunk(1, 0, 1, 1, [line("addition", "last", true)])]),
+		);
+		expect(hunks[0].lines).toEqual(["+last", "\\ No newline at end of file"]);
 	});
 
-	it("should assign sequential indices", () => {
-		const lines =
-			"a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn\no\np\nq\nr\ns\nt\n";
-		const modified = lines.replace("b\n", "B\n").replace("r\n", "R\n");
-		const hunks = computeHunks(lines, modified);
-		expect(hunks[0].index).toBe(0);
-		expect(hunks[1].index).toBe(1);
+	it("should assign sequential indices and keep the patch", () => {
+		const hunks = hunksFromFileDiff(
+			fileDiff([
+				hunk(2, 1, 2, 1, [line("deletion", "b"), line("addition", "B")]),
+				hunk(18, 1, 18, 1, [line("deletion", "r"), line("addition", "R")]),
+			]),
+		);
+		expect(hunks.map((h) => h.index)).toEqual([0, 1]);
+		expect(hunks[1].patchBase64).toBe(btoa("18:18"));
 	});
 
-	it("should include oldStart/newStart/oldLines/newLines", () => {
-		const hunks = computeHunks("line1\nline2\n", "line1\nline2\nline3\n");
-		expect(hunks[0]).toHaveProperty("oldStart");
-		expect(hunks[0]).toHaveProperty("newStart");
-		expect(hunks[0]).toHaveProperty("oldLines");
-		expect(hunks[0]).toHaveProperty("newLines");
+	it("should point deletion-only hunks at the line after the change", () => {
+		const hunks = hunksFromFileDiff(
+			fileDiff([hunk(4, 1, 3, 0, [line("deletion", "d")])]),
+		);
+		expect(hunks[0].newStart).toBe(4);
+		expect(computeChangeGroups(hunks)[0].newStart).toBe(3);
 	});
 });
 
 describe("markStagedGroups", () => {
-	const head = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn\no\np\nq\nr\ns\nt\n";
+	const changeB = hunk(2, 1, 2, 1, [
+		line("deletion", "b"),
+		line("addition", "B"),
+	]);
+	const changeR = hunk(18, 1, 18, 1, [
+		line("deletion", "r"),
+		line("addition", "R"),
+	]);
+
+	function mark(working: DiffHunkInfo[], staged: DiffHunkInfo[]) {
+		const hunks = hunksFromFileDiff(fileDiff(working));
+		const stagedHunks = hunksFromFileDiff(fileDiff(staged));
+		return markStagedGroups(
+			computeChangeGroups(hunks),
+			computeChangeGroups(stagedHunks),
+			hunks,
+			stagedHunks,
+		);
+	}
 
 	it("should mark groups as staged when staged diff matches", () => {
-		const working = head.replace("b\n", "B\n").replace("r\n", "R\n");
-		const staged = head.replace("b\n", "B\n");
-
-		const hunks = computeHunks(head, working);
-		const groups = computeChangeGroups(hunks);
-		const stagedHunks = computeHunks(head, staged);
-		const stagedGroups = computeChangeGroups(stagedHunks);
-
-		const result = markStagedGroups(groups, stagedGroups, hunks, stagedHunks);
+		const result = mark([changeB, changeR], [changeB]);
 
 		expect(result).toHaveLength(2);
 		expect(result[0].isStaged).toBe(true);
@@ -91,29 +125,14 @@ describe("markStagedGroups", () => {
 	});
 
 	it("should mark all groups as staged when all changes are staged", () => {
-		const working = head.replace("b\n", "B\n");
-		const staged = working;
-
-		const hunks = computeHunks(head, working);
-		const groups = computeChangeGroups(hunks);
-		const stagedHunks = computeHunks(head, staged);
-		const stagedGroups = computeChangeGroups(stagedHunks);
-
-		const result = markStagedGroups(groups, stagedGroups, hunks, stagedHunks);
+		const result = mark([changeB], [changeB]);
 
 		expect(result).toHaveLength(1);
 		expect(result[0].isStaged).toBe(true);
 	});
 
 	it("should mark all groups as unstaged when nothing is staged", () => {
-		const working = head.replace("b\n", "B\n");
-
-		const hunks = computeHunks(head, working);
-		const groups = computeChangeGroups(hunks);
-		const stagedHunks = computeHunks(head, head);
-		const stagedGroups = computeChangeGroups(stagedHunks);
-
-		const result = markStagedGroups(groups, stagedGroups, hunks, stagedHunks);
+		const result = mark([changeB], []);
 
 		expect(result).toHaveLength(1);
 		expect(result[0].isStaged).toBe(false);
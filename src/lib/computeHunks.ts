@@ -1,4 +1,4 @@
-import { structuredPatch } from "diff";
+import type { FileDiff } from "@/types/git";
 
 export interface Hunk {
 	index: number;
@@ -7,6 +7,7 @@ export interface Hunk {
 	newStart: number;
 	newLines: number;
 	lines: string[];
+	patchBase64: string;
 }
 
 export interface ChangeGroup {
@@ -19,22 +20,24 @@ export interface ChangeGroup {
 	isStaged?: boolean;
 }
 
-export function computeHunks(
-	original: string,
-	modified: string,
-	filePath = "file",
-): Hunk[] {
-	if (typeof original !== "string" || typeof modified !== "string") {
-		return [];
-	}
-	const patch = structuredPatch(filePath, filePath, original, modified);
-	return patch.hunks.map((h, i) => ({
+const ORIGIN_PREFIX = { context: " ", addition: "+", deletion: "-" } as const;
+
+export function hunksFromFileDiff(diff: FileDiff | undefined): Hunk[] {
+	if (!diff) return [];
+	return diff.hunks.map((h, i) => ({
 		index: i,
-		oldStart: h.oldStart,
-		oldLines: h.oldLines,
-		newStart: h.newStart,
-		newLines: h.newLines,
-		lines: h.lines,
+		oldStart: h.old_start,
+		oldLines: h.old_lines,
+		// 削除のみのhunkは直前の行を指すので、他のhunkと同じく変更位置の行に揃える
+		newStart: h.new_lines === 0 ? h.new_start + 1 : h.new_start,
+		newLines: h.new_lines,
+		lines: h.lines.flatMap((line) => {
+			const text = `${ORIGIN_PREFIX[line.origin]}${line.content}`;
+			return line.no_newline_at_eof
+				? [text, "\\ No newline at end of file"]
+				: [text];
+		}),
+		patchBase64: h.patch_base64,
 	}));
 }
 
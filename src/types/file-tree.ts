@@ -3,6 +3,7 @@ export type FileStatus =
 	| "added"
 	| "deleted"
 	| "untracked"
+	| "conflicted"
 	| "ignored"
 	| null;
 
@@ -1,9 +1,29 @@
 export interface GitFileStatus {
 	path: string;
-	index_status: "new" | "modified" | "deleted" | "renamed" | "none";
-	worktree_status: "new" | "modified" | "deleted" | "ignored" | "none";
+	index_status:
+		| "new"
+		| "modified"
+		| "deleted"
+		| "renamed"
+		| "copied"
+		| "conflicted"
+		| "none";
+	worktree_status:
+		| "new"
+		| "modified"
+		| "deleted"
+		| "renamed"
+		| "copied"
+		| "conflicted"
+		| "ignored"
+		| "none";
+	old_path?: string | null;
+	similarity?: number | null;
+	review_state?: ReviewState;
 }
 
+export type ReviewState = "reviewed" | "changed_since_review" | "never_reviewed";
+
 export interface CommitInfo {
 	hash: string;
 	short_hash: string;
@@ -11,4 +31,385 @@ export interface CommitInfo {
 	author_name: string;
 	author_email: string;
 	timestamp: number;
+	parents: string[];
+	insertions: number;
+	deletions: number;
+	files_changed: number;
+}
+
+export interface LogFilter {
+	cursor?: string;
+	revision?: string;
+	path?: string;
+	author?: string;
+	message_regex?: string;
+	since?: number;
+	until?: number;
+}
+
+export interface CommitTrailer {
+	key: string;
+	value: string;
+}
+
+export interface CommitSignature {
+	format: "openpgp" | "x509" | "ssh";
+	status: "good" | "untrusted" | "bad" | "unknown";
+	signer: string | null;
+}
+
+export interface CommitDetail {
+	hash: string;
+	short_hash: string;
+	message: string;
+	author_name: string;
+	author_email: string;
+	author_timestamp: number;
+	committer_name: string;
+	committer_email: string;
+	committer_timestamp: number;
+	parents: string[];
+	trailers: CommitTrailer[];
+	signature: CommitSignature | null;
+	compared_parent: string | null;
+	files: FileDiff[];
+}
+
+export interface BlameLine {
+	line: number;
+	content: string;
+	hash: string | null;
+	short_hash: string;
+	author_name: string;
+	author_email: string;
+	timestamp: number;
+	summary: string;
+	original_path: string | null;
+	original_line: number;
+}
+
+export interface HunkApplyError {
+	reason:
+		| "invalid_patch"
+		| "context_mismatch"
+		| "missing_file"
+		| "binary"
+		| "other";
+	path: string | null;
+	hunk_index: number | null;
+	hunk_header: string | null;
+	message: string;
+}
+
+export interface RefDiff {
+	base: string;
+	target: string;
+	merge_base: string | null;
+	ahead: number;
+	behind: number;
+	files_changed: number;
+	insertions: number;
+	deletions: number;
+	files: FileDiff[];
+}
+
+export type GitDiffMode =
+	| "head_to_index"
+	| "index_to_worktree"
+	| "head_to_worktree"
+	| "commit_to_worktree";
+
+export interface DiffLineInfo {
+	origin: "context" | "addition" | "deletion";
+	content: string;
+	old_lineno: number | null;
+	new_lineno: number | null;
+	no_newline_at_eof: boolean;
+}
+
+export interface DiffHunkInfo {
+	header: string;
+	old_start: number;
+	old_lines: number;
+	new_start: number;
+	new_lines: number;
+	lines: DiffLineInfo[];
+	patch_base64: string;
+}
+
+export interface FileDiff {
+	old_path: string | null;
+	new_path: string | null;
+	status: string;
+	binary: boolean;
+	additions: number;
+	deletions: number;
+	hunks: DiffHunkInfo[];
+}
+
+export interface ImagePreview {
+	mime_type: string;
+	data_base64: string;
+}
+
+export interface BlobContent {
+	binary: boolean;
+	encoding: string | null;
+	content: string;
+	size: number;
+	truncated: boolean;
+	image: ImagePreview | null;
+}
+
+export interface ConflictContent {
+	path: string;
+	base: BlobContent | null;
+	ours: BlobContent | null;
+	theirs: BlobContent | null;
+}
+
+export type ConflictResolution = "mark_resolved" | "ours" | "theirs";
+
+export interface WorktreeInfo {
+	name: string | null;
+	path: string;
+	is_main: boolean;
+	branch: string | null;
+	head: string | null;
+	locked: boolean;
+	lock_reason: string | null;
+	prunable: boolean;
+}
+
+export interface StashEntry {
+	index: number;
+	message: string;
+	oid: string;
+	base: string | null;
+	time: number;
+	has_untracked: boolean;
+}
+
+export interface RemoteProgress {
+	repo_path: string;
+	operation: "push" | "fetch" | "pull";
+	stage:
+		| "receiving"
+		| "resolving"
+		| "packing"
+		| "sending"
+		| "remote"
+		| "ref_updated"
+		| "ref_rejected";
+	current: number;
+	total: number;
+	bytes: number;
+	message: string | null;
+}
+
+export interface RefUpdate {
+	refname: string;
+	old: string | null;
+	new: string | null;
+	message: string | null;
+}
+
+export interface PushResult {
+	remote: string;
+	refspecs: string[];
+	updated: RefUpdate[];
+}
+
+export interface FetchResult {
+	remote: string;
+	received_objects: number;
+	received_bytes: number;
+	updated: RefUpdate[];
+}
+
+export interface PullResult {
+	fetch: FetchResult;
+	outcome: "up_to_date" | "fast_forward" | "rebased";
+	head: string | null;
+}
+
+export interface BranchInfo {
+	name: string;
+	refname: string;
+	is_remote: boolean;
+	is_head: boolean;
+	upstream: string | null;
+	ahead: number | null;
+	behind: number | null;
+	last_commit_hash: string | null;
+	last_commit_summary: string | null;
+	last_commit_timestamp: number | null;
+	worktree_path: string | null;
+}
+
+export interface CheckoutError {
+	reason: "dirty_worktree" | "checked_out_elsewhere" | "other";
+	blocking_paths: string[];
+	message: string;
+}
+
+export interface CommitOptions {
+	amend?: boolean;
+	sign_off?: boolean;
+	author_name?: string;
+	author_email?: string;
+	allow_empty?: boolean;
+	sign?: boolean;
+	skip_hooks?: boolean;
+}
+
+export interface CommitError {
+	reason: "hook_failed" | "other";
+	hook: string | null;
+	exit_code: number | null;
+	output: string;
+	message: string;
+}
+
+export interface HookOutput {
+	repo_path: string;
+	hook: string;
+	stream: "stdout" | "stderr";
+	line: string;
+}
+
+export interface DiscardBackup {
+	id: string;
+	description: string;
+	paths: string[];
+	timestamp: number;
+}
+
+export type DiscardSource = "index" | "head";
+
+export interface CheckpointInfo {
+	id: string;
+	session: string;
+	number: number;
+	oid: string;
+	label: string;
+	timestamp: number;
+	head: string | null;
+}
+
+export type PatchSource = "staged" | "unstaged" | "range";
+
+export type PatchTarget = "worktree" | "index" | "both";
+
+export interface PatchHunkPreview {
+	header: string;
+	applies: boolean;
+}
+
+export interface PatchFilePreview {
+	old_path: string | null;
+	new_path: string | null;
+	status: string;
+	binary: boolean;
+	applies: boolean;
+	has_base: boolean;
+	hunks: PatchHunkPreview[];
+}
+
+export interface PatchMessagePreview {
+	subject: string | null;
+	files: PatchFilePreview[];
+}
+
+export interface PatchPreview {
+	messages: PatchMessagePreview[];
+}
+
+export interface PatchApplyResult {
+	applied: string[];
+	conflicted: string[];
+	messages_applied: number;
+	messages_total: number;
+	failed: HunkApplyError | null;
+}
+
+export interface PickResult {
+	commit: string | null;
+	conflicts: string[];
+	message: string;
+}
+
+export interface RepoState {
+	state:
+		| "clean"
+		| "merge"
+		| "cherry_pick"
+		| "revert"
+		| "rebase"
+		| "bisect"
+		| "apply_mailbox"
+		| "rewrite";
+	head: string | null;
+	message: string | null;
+}
+
+export type RewriteAction = "pick" | "reword" | "squash" | "fixup" | "drop";
+
+export interface RewriteStep {
+	action: RewriteAction;
+	hash: string;
+	message?: string | null;
+}
+
+export interface RewriteStatus {
+	state: "done" | "stopped";
+	head: string;
+	backup_ref: string;
+	stopped_at: string | null;
+	conflicts: string[];
+	remaining: number;
+}
+
+export type ResetMode = "soft" | "mixed" | "hard";
+
+export interface ResetBackup {
+	id: string;
+	ref_name: string;
+	head: string;
+	summary: string;
+	description: string;
+	timestamp: number;
+}
+
+export interface ResetResult {
+	backup: ResetBackup;
+	checkpoint: CheckpointInfo | null;
+}
+
+export interface OperationState {
+	head: string | null;
+	head_ref: string | null;
+	index_tree: string | null;
+	worktree_tree: string | null;
+	branches: Record<string, string>;
+	stashes: string[];
+}
+
+export interface OperationEntry {
+	id: number;
+	operation: string;
+	timestamp: number;
+	before: OperationState;
+	after: OperationState;
+	worktree_paths: string[];
+	undone: boolean;
+}
+
+export interface ReflogEntry {
+	old_id: string;
+	new_id: string;
+	message: string;
+	committer_name: string;
+	committer_email: string;
+	timestamp: number;
 }
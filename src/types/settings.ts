@@ -1,5 +1,5 @@
 export type Theme = "dark" | "light";
-export type DiffBase = "HEAD" | "staged";
+export type DiffBase = "HEAD" | "staged" | "ref" | "merge-base";
 export type DiffMode = "gutter" | "inline" | "split";
 
 export interface AppSettings {
@@ -7,6 +7,7 @@ export interface AppSettings {
 	fontSize: number;
 	defaultDiffBase: DiffBase;
 	defaultDiffMode: DiffMode;
+	diffBaseRef: string;
 }
 
 export const DEFAULT_SETTINGS: AppSettings = {
@@ -14,4 +15,5 @@ export const DEFAULT_SETTINGS: AppSettings = {
 	fontSize: 14,
 	defaultDiffBase: "staged",
 	defaultDiffMode: "inline",
+	diffBaseRef: "main",
 };